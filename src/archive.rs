@@ -0,0 +1,139 @@
+use anyhow::Context;
+use std::fs::File;
+use std::path::Path;
+use std::{fs, io};
+use zip::write::SimpleFileOptions;
+
+/// File in the project root listing paths to leave out of exported archives, one pattern per line.
+pub const IGNORE_FILE_NAME: &str = ".smded_export_ignore";
+
+/// Patterns read from an [`IGNORE_FILE_NAME`] file. Blank lines and lines starting with `#` are
+/// skipped. Patterns containing a `/` are matched against the path relative to the project root,
+/// others against each path component, and `*` matches any run of characters other than `/`.
+#[derive(Default)]
+struct IgnoreList {
+    patterns: Vec<String>,
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            text.char_indices()
+                .map(|(i, _)| i)
+                .chain([text.len()])
+                .take_while(|&i| !text[..i].contains('/'))
+                .any(|i| glob_matches(rest, &text[i..]))
+        }
+    }
+}
+
+impl IgnoreList {
+    fn parse(contents: &str) -> Self {
+        let patterns = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.trim_matches('/').to_owned())
+            .collect();
+        Self { patterns }
+    }
+
+    fn load(project_path: &Path) -> anyhow::Result<Self> {
+        match fs::read_to_string(project_path.join(IGNORE_FILE_NAME)) {
+            Ok(contents) => Ok(Self::parse(&contents)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).context(format!("Failed to read {IGNORE_FILE_NAME}")),
+        }
+    }
+
+    /// `relative_path` uses `/` as separator, regardless of platform.
+    fn is_ignored(&self, relative_path: &str) -> bool {
+        self.patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob_matches(pattern, relative_path)
+            } else {
+                relative_path
+                    .split('/')
+                    .any(|component| glob_matches(pattern, component))
+            }
+        })
+    }
+}
+
+/// Writes the contents of the project directory to a ZIP archive at `output_path`, skipping files
+/// listed in the project's ignore file. Returns the number of files archived.
+pub fn export_project_archive(project_path: &Path, output_path: &Path) -> anyhow::Result<usize> {
+    let ignore_list = IgnoreList::load(project_path)?;
+    // Avoid archiving the archive itself if it's being written inside the project
+    let output_canonical = output_path
+        .parent()
+        .and_then(|dir| fs::canonicalize(dir).ok())
+        .zip(output_path.file_name())
+        .map(|(dir, name)| dir.join(name));
+
+    let mut zip = zip::ZipWriter::new(File::create(output_path)?);
+    let options = SimpleFileOptions::default();
+    let mut file_count = 0;
+    let mut pending_dirs = vec![String::new()];
+    while let Some(relative_dir) = pending_dirs.pop() {
+        let mut entries =
+            fs::read_dir(project_path.join(&relative_dir))?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative_path = if relative_dir.is_empty() {
+                name
+            } else {
+                format!("{relative_dir}/{name}")
+            };
+            if ignore_list.is_ignored(&relative_path)
+                || output_canonical.as_deref() == fs::canonicalize(entry.path()).ok().as_deref()
+            {
+                continue;
+            }
+
+            if entry.file_type()?.is_dir() {
+                zip.add_directory(&relative_path, options)?;
+                pending_dirs.push(relative_path);
+            } else {
+                zip.start_file(&relative_path, options)?;
+                io::copy(&mut File::open(entry.path())?, &mut zip)
+                    .with_context(|| format!("Failed to archive {relative_path}"))?;
+                file_count += 1;
+            }
+        }
+    }
+    zip.finish()?;
+    Ok(file_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("rom.sfc", "rom.sfc"));
+        assert!(glob_matches("*.sfc", "hack.sfc"));
+        assert!(glob_matches("Export/*/CRE", "Export/Tileset/CRE"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("*.sfc", "hack.smc"));
+        assert!(!glob_matches("Export/*", "Export/Tileset/CRE"));
+    }
+
+    #[test]
+    fn test_ignore_list() {
+        let ignore_list =
+            IgnoreList::parse("# Build outputs\n*.sfc\n\n/Build/\nData/Tileset/*.xml\n");
+        assert!(ignore_list.is_ignored("hack.sfc"));
+        assert!(ignore_list.is_ignored("Build"));
+        assert!(ignore_list.is_ignored("Build/patch.ips"));
+        assert!(ignore_list.is_ignored("Data/Tileset/00.xml"));
+        assert!(!ignore_list.is_ignored("Data/Tileset/SCE/00.xml"));
+        assert!(!ignore_list.is_ignored("project.xml"));
+    }
+}
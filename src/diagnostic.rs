@@ -0,0 +1,71 @@
+use crate::room::RoomRef;
+use crate::tileset::TilesetRef;
+use std::fmt::Display;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Severity {
+    /// Suspicious data that was loaded as-is or recovered from.
+    Warning,
+    /// Data that couldn't be loaded and was left out of the project.
+    Error,
+}
+
+/// Part of the project a diagnostic is about, which the UI can jump to.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DiagnosticLocation {
+    Room(RoomRef),
+    RoomState { room: RoomRef, state: usize },
+    Door { room: RoomRef, door: usize },
+    Tileset(TilesetRef),
+}
+
+impl DiagnosticLocation {
+    /// Room the location is in, if any.
+    pub fn room(self) -> Option<RoomRef> {
+        match self {
+            DiagnosticLocation::Room(room)
+            | DiagnosticLocation::RoomState { room, .. }
+            | DiagnosticLocation::Door { room, .. } => Some(room),
+            DiagnosticLocation::Tileset(_) => None,
+        }
+    }
+}
+
+/// Problem found in the project data that didn't prevent it from loading.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub severity: Severity,
+    pub location: Option<DiagnosticLocation>,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            severity: Severity::Warning,
+            location: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            severity: Severity::Error,
+            location: None,
+        }
+    }
+
+    pub fn at(self, location: DiagnosticLocation) -> Self {
+        Self {
+            location: Some(location),
+            ..self
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
@@ -0,0 +1,171 @@
+//! Formatting of door code ops as 65816 assembly, for reading what a door's setup code does.
+
+use crate::hex_types::{HexSize, HexU8, HexValue};
+use crate::smart_xml::{CodeOp, ScrollDataChangeEntry};
+
+/// Addressing mode of an instruction, which determines how its argument is written.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Mode {
+    Implied,
+    Immediate,
+    Direct,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Long,
+    LongX,
+    /// Branch offset, relative to the next instruction.
+    Relative,
+}
+
+/// Opcodes with known mnemonics. Ops not in this table are shown as raw hex. Covers the
+/// instructions commonly found in door code; add entries as needed.
+const OPCODES: &[(u8, &str, Mode)] = &[
+    (0x08, "PHP", Mode::Implied),
+    (0x09, "ORA", Mode::Immediate),
+    (0x0D, "ORA", Mode::Absolute),
+    (0x18, "CLC", Mode::Implied),
+    (0x1A, "INC", Mode::Implied),
+    (0x20, "JSR", Mode::Absolute),
+    (0x22, "JSL", Mode::Long),
+    (0x28, "PLP", Mode::Implied),
+    (0x29, "AND", Mode::Immediate),
+    (0x2D, "AND", Mode::Absolute),
+    (0x38, "SEC", Mode::Implied),
+    (0x3A, "DEC", Mode::Implied),
+    (0x48, "PHA", Mode::Implied),
+    (0x4C, "JMP", Mode::Absolute),
+    (0x5C, "JML", Mode::Long),
+    (0x60, "RTS", Mode::Implied),
+    (0x64, "STZ", Mode::Direct),
+    (0x68, "PLA", Mode::Implied),
+    (0x69, "ADC", Mode::Immediate),
+    (0x6B, "RTL", Mode::Implied),
+    (0x6D, "ADC", Mode::Absolute),
+    (0x80, "BRA", Mode::Relative),
+    (0x85, "STA", Mode::Direct),
+    (0x8D, "STA", Mode::Absolute),
+    (0x8E, "STX", Mode::Absolute),
+    (0x8F, "STA", Mode::Long),
+    (0x9C, "STZ", Mode::Absolute),
+    (0x9D, "STA", Mode::AbsoluteX),
+    (0x9E, "STZ", Mode::AbsoluteX),
+    (0x9F, "STA", Mode::LongX),
+    (0xA0, "LDY", Mode::Immediate),
+    (0xA2, "LDX", Mode::Immediate),
+    (0xA5, "LDA", Mode::Direct),
+    (0xA9, "LDA", Mode::Immediate),
+    (0xAD, "LDA", Mode::Absolute),
+    (0xAE, "LDX", Mode::Absolute),
+    (0xAF, "LDA", Mode::Long),
+    (0xB9, "LDA", Mode::AbsoluteY),
+    (0xBD, "LDA", Mode::AbsoluteX),
+    (0xBF, "LDA", Mode::LongX),
+    (0xC2, "REP", Mode::Immediate),
+    (0xC9, "CMP", Mode::Immediate),
+    (0xCD, "CMP", Mode::Absolute),
+    (0xD0, "BNE", Mode::Relative),
+    (0xE2, "SEP", Mode::Immediate),
+    (0xE8, "INX", Mode::Implied),
+    (0xEA, "NOP", Mode::Implied),
+    (0xF0, "BEQ", Mode::Relative),
+];
+
+fn lookup(op: HexU8) -> Option<(&'static str, Mode)> {
+    OPCODES
+        .iter()
+        .find(|&&(opcode, _, _)| opcode == op.0)
+        .map(|&(_, mnemonic, mode)| (mnemonic, mode))
+}
+
+/// Width of the argument of `op`, if its addressing mode fixes it. Immediate arguments depend on
+/// the processor flags at that point, so they and unknown ops return `None`.
+pub fn operand_size(op: HexU8) -> Option<HexSize> {
+    match lookup(op)?.1 {
+        Mode::Implied | Mode::Immediate => None,
+        Mode::Direct | Mode::Relative => Some(HexSize::Byte),
+        Mode::Absolute | Mode::AbsoluteX | Mode::AbsoluteY => Some(HexSize::Word),
+        Mode::Long | Mode::LongX => Some(HexSize::Long),
+    }
+}
+
+/// Formats an op as an assembly instruction, e.g. `LDA #$0001` or `STA $7ECD20,X`. Unknown
+/// opcodes, or known ones with an argument that doesn't match their addressing mode, are written
+/// as raw hex.
+pub fn format_op(op: &CodeOp) -> String {
+    let raw = || match op.arg {
+        Some(arg) => format!("{} {arg}", op.op),
+        None => op.op.to_string(),
+    };
+    let Some((mnemonic, mode)) = lookup(op.op) else {
+        return raw();
+    };
+    match (mode, op.arg) {
+        (Mode::Implied, None) => mnemonic.to_string(),
+        (Mode::Immediate, Some(arg)) => format!("{mnemonic} #{arg}"),
+        (Mode::Direct | Mode::Absolute | Mode::Long, Some(arg)) => format!("{mnemonic} {arg}"),
+        (Mode::AbsoluteX | Mode::LongX, Some(arg)) => format!("{mnemonic} {arg},X"),
+        (Mode::AbsoluteY, Some(arg)) => format!("{mnemonic} {arg},Y"),
+        (Mode::Relative, Some(HexValue::Byte(offset))) => {
+            format!("{mnemonic} {:+}", offset.0 as i8)
+        }
+        _ => raw(),
+    }
+}
+
+/// Name of a scroll value, as used by SMART.
+pub fn scroll_name(scroll: HexU8) -> &'static str {
+    match scroll.0 {
+        0x00 => "Red",
+        0x01 => "Blue",
+        0x02 => "Green",
+        _ => "Unknown",
+    }
+}
+
+pub fn format_scroll_change(entry: &ScrollDataChangeEntry) -> String {
+    match entry {
+        ScrollDataChangeEntry::Change { screen, scroll } => {
+            format!("Screen {screen} → {scroll} ({})", scroll_name(*scroll))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex_types::{HexU16, HexU24};
+
+    fn op(op: u8, arg: Option<HexValue>) -> CodeOp {
+        CodeOp { op: HexU8(op), arg }
+    }
+
+    #[test]
+    fn test_format_op() {
+        let word = |v| Some(HexValue::Word(HexU16(v)));
+        assert_eq!(format_op(&op(0xA9, word(0x0001))), "LDA #$0001");
+        assert_eq!(
+            format_op(&op(0x8F, Some(HexValue::Long(HexU24(0x7ECD20))))),
+            "STA $7ECD20"
+        );
+        assert_eq!(format_op(&op(0x9D, word(0x1234))), "STA $1234,X");
+        assert_eq!(format_op(&op(0x6B, None)), "RTL");
+        assert_eq!(
+            format_op(&op(0xD0, Some(HexValue::Byte(HexU8(0xFC))))),
+            "BNE -4"
+        );
+        // Unknown opcode, and known opcode with a missing argument
+        assert_eq!(format_op(&op(0x42, word(0x0005))), "$42 $0005");
+        assert_eq!(format_op(&op(0xA9, None)), "$A9");
+    }
+
+    #[test]
+    fn test_opcode_table_unique() {
+        for (i, (opcode, _, _)) in OPCODES.iter().enumerate() {
+            assert!(
+                OPCODES[i + 1..].iter().all(|(other, _, _)| other != opcode),
+                "duplicate opcode {opcode:02X}"
+            );
+        }
+    }
+}
@@ -1,10 +1,13 @@
+use crate::hex_types::HexU16;
 use bit_field::BitField;
 use egui::Color32;
-use std::iter;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::{array, iter};
 use tracing::warn;
 
 #[repr(transparent)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct SnesColor(pub u16);
 
 impl SnesColor {
@@ -26,12 +29,30 @@ impl From<SnesColor> for Color32 {
     }
 }
 
+#[derive(Clone)]
 pub struct Palette(pub Vec<SnesColor>);
 
-type PaletteLine4Bpp<Color> = [Color; Palette::LINE_4BPP_LEN];
+/// Result of comparing the entries at the same index of two palettes.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ColorDiff {
+    Identical,
+    /// Absolute difference of each 5-bit channel, in RGB order.
+    Changed([u16; 3]),
+    /// Entry only exists in the compared palette.
+    Added,
+    /// Entry only exists in the base palette.
+    Removed,
+}
+
+pub type PaletteLine4Bpp<Color> = [Color; Palette::LINE_4BPP_LEN];
+pub type PaletteLine2Bpp<Color> = [Color; Palette::LINE_2BPP_LEN];
 
 impl Palette {
     pub const LINE_4BPP_LEN: usize = 16;
+    pub const LINE_2BPP_LEN: usize = 4;
+    /// Number of colors that tiles can reference through the palette bits of a `TilemapEntry`.
+    pub const ADDRESSABLE_4BPP_LEN: usize =
+        Self::LINE_4BPP_LEN * TilemapEntry::ADDRESSABLE_PALETTES;
 
     pub fn as_4bpp_lines(&self) -> &[PaletteLine4Bpp<SnesColor>] {
         let (lines, rest) = self.0.as_chunks();
@@ -41,15 +62,27 @@ impl Palette {
         lines
     }
 
+    #[expect(unused)]
+    pub fn as_2bpp_lines(&self) -> &[PaletteLine2Bpp<SnesColor>] {
+        let (lines, rest) = self.0.as_chunks();
+        if !rest.is_empty() {
+            warn!("Palette contains {} leftover entries", rest.len());
+        }
+        lines
+    }
+
     pub fn to_4bpp_color32_lines(&self) -> impl Iterator<Item = PaletteLine4Bpp<Color32>> {
         self.as_4bpp_lines()
             .iter()
             .map(|line| line.map(Color32::from))
     }
 
-    pub fn truncate_checked(&mut self, new_len: usize) -> Result<(), ()> {
-        if new_len > self.0.len() || self.0[new_len..].iter().any(|&SnesColor(x)| x != 0) {
-            Err(())
+    /// Drops the entries past `new_len`, as long as they're all blank. Otherwise, leaves the palette
+    /// unchanged and returns the index of the first non-blank one.
+    pub fn truncate_checked(&mut self, new_len: usize) -> Result<(), usize> {
+        let excess = self.0.get(new_len..).unwrap_or_default();
+        if let Some(i) = excess.iter().position(|&SnesColor(x)| x != 0) {
+            Err(new_len + i)
         } else {
             self.0.truncate(new_len);
             Ok(())
@@ -59,6 +92,73 @@ impl Palette {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Number of complete 4bpp lines in the palette.
+    pub fn line_count(&self) -> usize {
+        self.len() / Self::LINE_4BPP_LEN
+    }
+
+    /// Index and value of the entry closest to `target` in 5-bit RGB space, preferring the lowest
+    /// index on ties. Returns `None` if the palette is empty.
+    #[cfg_attr(not(test), expect(unused))]
+    pub fn find_nearest_color(&self, target: SnesColor) -> Option<(usize, SnesColor)> {
+        nearest_snes_color(&self.0, target)
+    }
+
+    /// Like [`Palette::find_nearest_color`], but only searching 4bpp line `line`. The returned
+    /// index is within the line, as used by tile pixels. Returns `None` if the palette doesn't
+    /// have that line.
+    #[cfg_attr(not(test), expect(unused))]
+    pub fn find_nearest_in_line(
+        &self,
+        line: usize,
+        target: SnesColor,
+    ) -> Option<(usize, SnesColor)> {
+        nearest_snes_color(self.as_4bpp_lines().get(line)?, target)
+    }
+
+    /// Compares each entry against the one at the same index in `other`.
+    pub fn diff(&self, other: &Palette) -> Vec<ColorDiff> {
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|i| match (self.0.get(i), other.0.get(i)) {
+                (Some(a), Some(b)) if a == b => ColorDiff::Identical,
+                (Some(a), Some(b)) => {
+                    let (a, b) = (a.as_rgb_5bpc(), b.as_rgb_5bpc());
+                    ColorDiff::Changed(array::from_fn(|c| a[c].abs_diff(b[c])))
+                }
+                (None, Some(_)) => ColorDiff::Added,
+                (Some(_), None) => ColorDiff::Removed,
+                (None, None) => unreachable!(),
+            })
+            .collect()
+    }
+}
+
+/// Index of the entry of `colors` closest to `target`, by Euclidean distance between the RGB
+/// triplets returned by `rgb`. Ties go to the lowest index.
+fn nearest_rgb<C>(colors: &[C], target: [u16; 3], rgb: impl Fn(&C) -> [u16; 3]) -> Option<usize> {
+    let distance = |c: &C| {
+        iter::zip(rgb(c), target)
+            .map(|(x, x0)| u32::from(x.abs_diff(x0)).pow(2))
+            .sum::<u32>()
+    };
+    // `min_by_key` keeps the first of equal minimums
+    colors
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, c)| distance(c))
+        .map(|(i, _)| i)
+}
+
+/// Entry of `colors` closest to `target` in 5-bit RGB space.
+fn nearest_snes_color(colors: &[SnesColor], target: SnesColor) -> Option<(usize, SnesColor)> {
+    let i = nearest_rgb(colors, target.as_rgb_5bpc(), |c| c.as_rgb_5bpc())?;
+    Some((i, colors[i]))
 }
 
 impl From<Vec<u16>> for Palette {
@@ -67,13 +167,71 @@ impl From<Vec<u16>> for Palette {
     }
 }
 
+/// Value that doesn't fit in the bits of a tilemap or level data entry field.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FieldOverflow {
+    pub field: &'static str,
+    pub value: usize,
+    /// Number of distinct values the field can hold.
+    pub limit: usize,
+}
+
+impl FieldOverflow {
+    /// Checks that `value` fits in `bits`, returning it ready to be stored with `set_bits`.
+    pub fn check(field: &'static str, value: usize, bits: Range<usize>) -> Result<u16, Self> {
+        let limit = 1 << bits.len();
+        if value < limit {
+            Ok(value as u16)
+        } else {
+            Err(Self {
+                field,
+                value,
+                limit,
+            })
+        }
+    }
+}
+
+impl Display for FieldOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:#X} is out of range (must be below {:#X})",
+            self.field, self.value, self.limit
+        )
+    }
+}
+
+impl std::error::Error for FieldOverflow {}
+
+/// SNES BG tilemap entry. All bits have defined semantics:
+///
+/// - 0-9: Tile number
+/// - 10-12: Palette line
+/// - 13: Priority
+/// - 14: Horizontal flip
+/// - 15: Vertical flip
 #[derive(Copy, Clone)]
 pub struct TilemapEntry(pub u16);
 
-// TODO: Replace with bitfields! macro?
 impl TilemapEntry {
+    const TILE_ID_BITS: Range<usize> = 0..10;
+    const PALETTE_BITS: Range<usize> = 10..13;
+
+    /// Converts from the exported tiletable representation. Since there are no reserved bits in
+    /// the format, every value is valid and the conversion is lossless.
+    pub fn from_xml(value: HexU16) -> Self {
+        Self(value.0)
+    }
+
+    /// Converts to the exported tiletable representation.
+    #[cfg_attr(not(test), expect(unused))]
+    pub fn to_xml(self) -> HexU16 {
+        HexU16(self.0)
+    }
+
     pub fn tile_id(self) -> usize {
-        usize::from(self.0.get_bits(0..10))
+        usize::from(self.0.get_bits(Self::TILE_ID_BITS))
     }
 
     pub const H_FLIP_FLAG: u16 = 1 << 14;
@@ -93,20 +251,32 @@ impl TilemapEntry {
 
     pub const ADDRESSABLE_PALETTES: usize = 1 << 3;
     pub fn palette(self) -> usize {
-        usize::from(self.0.get_bits(10..13))
+        usize::from(self.0.get_bits(Self::PALETTE_BITS))
     }
 
-    // TODO: Silently discards overflow
-    pub fn for_tile(tile: usize) -> Self {
-        Self((tile & ((1 << 10) - 1)) as u16)
+    /// Entry showing `tile` with every other field cleared. Fails for tiles past the 1024 that
+    /// a tilemap can address.
+    pub fn try_for_tile(tile: usize) -> Result<Self, FieldOverflow> {
+        let tile = FieldOverflow::check("Tile", tile, Self::TILE_ID_BITS)?;
+        Ok(Self(tile))
     }
 
-    pub fn with_palette(mut self, pal: usize) -> Self {
-        self.0.set_bits(10..13, pal as u16);
-        self
+    pub fn try_with_palette(mut self, pal: usize) -> Result<Self, FieldOverflow> {
+        let pal = FieldOverflow::check("Palette line", pal, Self::PALETTE_BITS)?;
+        self.0.set_bits(Self::PALETTE_BITS, pal);
+        Ok(self)
     }
 }
 
+/// Index of the color in `palette` closest to `color` in RGB space.
+fn nearest_color(palette: &[Color32], color: Color32) -> usize {
+    let rgb = |c: &Color32| {
+        let [r, g, b, _] = c.to_array();
+        [r, g, b].map(u16::from)
+    };
+    nearest_rgb(palette, rgb(&color), rgb).unwrap_or(0)
+}
+
 fn spread_u8_x4(x: u8) -> u32 {
     let mut x = u32::from(x);
     x = 0x000F_000F & (x | x << 12);
@@ -121,22 +291,74 @@ fn decode_bitplanes(bitplanes: [u8; 4]) -> u32 {
 }
 
 #[repr(transparent)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Snes4BppTile(pub [u8; TILE_SIZE * 4]);
 
 pub const TILE_SIZE: usize = 8;
 
 impl Snes4BppTile {
+    #[cfg_attr(not(test), expect(unused))]
     pub fn from_bytes(data: &[u8; 32]) -> Self {
         Self(*data)
     }
 
+    /// Encodes a tile from the palette index of each of its pixels, in row-major order. Only the
+    /// low 4 bits of each index are used.
+    pub fn encode_from_indices(indices: &[u8; TILE_SIZE * TILE_SIZE]) -> Self {
+        let mut data = [0; TILE_SIZE * 4];
+        let (bp01, bp23) = data.as_chunks_mut::<2>().0.split_at_mut(TILE_SIZE);
+        let (rows, _) = indices.as_chunks::<TILE_SIZE>();
+        for ((row, out01), out23) in rows.iter().zip(bp01).zip(bp23) {
+            for (x, &index) in row.iter().enumerate() {
+                let bit = TILE_SIZE - 1 - x;
+                out01[0].set_bit(bit, index.get_bit(0));
+                out01[1].set_bit(bit, index.get_bit(1));
+                out23[0].set_bit(bit, index.get_bit(2));
+                out23[1].set_bit(bit, index.get_bit(3));
+            }
+        }
+        Self(data)
+    }
+
+    /// Encodes a tile from its pixel colors, in row-major order, using the closest color of
+    /// `palette` for each pixel. Fully transparent pixels use color 0, which is transparent when
+    /// drawn over other layers. Returns `None` if any pixel is partially transparent, since that
+    /// can't be represented.
+    #[cfg_attr(not(test), expect(unused))]
+    pub fn encode_from_colors(
+        pixels: &[Color32; TILE_SIZE * TILE_SIZE],
+        palette: &PaletteLine4Bpp<Color32>,
+    ) -> Option<Self> {
+        let mut indices = [0; TILE_SIZE * TILE_SIZE];
+        for (index, pixel) in iter::zip(&mut indices, pixels) {
+            *index = match pixel.a() {
+                0 => 0,
+                u8::MAX => nearest_color(palette, *pixel) as u8,
+                _ => return None,
+            };
+        }
+        Some(Self::encode_from_indices(&indices))
+    }
+
     fn bitplane_sets(&self) -> impl Iterator<Item = [u8; 4]> {
         let (pairs, _) = self.0.as_chunks::<2>();
         let (bp01, bp23) = pairs.split_at(TILE_SIZE);
         iter::zip(bp01, bp23).map(|(&[bp0, bp1], &[bp2, bp3])| [bp0, bp1, bp2, bp3])
     }
 
+    /// Palette index of each pixel, in row-major order.
+    #[cfg_attr(not(test), expect(unused))]
+    pub fn pixel_indices(&self) -> [u8; TILE_SIZE * TILE_SIZE] {
+        let mut indices = [0; TILE_SIZE * TILE_SIZE];
+        let rows = indices.as_chunks_mut::<TILE_SIZE>().0;
+        for (bp, row) in iter::zip(self.bitplane_sets().map(decode_bitplanes), rows) {
+            for (x, index) in row.iter_mut().enumerate() {
+                *index = (bp >> (32 - 4 * (x + 1)) & 0xF) as u8;
+            }
+        }
+        indices
+    }
+
     pub fn write_to_image<'p, const H_FLIP: bool, const USE_TRANSPARENCY: bool>(
         &self,
         palette: &PaletteLine4Bpp<Color32>,
@@ -167,51 +389,551 @@ impl Snes4BppTile {
         flips: [bool; 2],
     ) {
         match flips {
-            [false, false] => self.write_to_image::<false, false>(palette, output_slivers),
-            [true, false] => self.write_to_image::<true, false>(palette, output_slivers),
-            [false, true] => self.write_to_image::<false, false>(palette, output_slivers.rev()),
-            [true, true] => self.write_to_image::<true, false>(palette, output_slivers.rev()),
+            [false, false] => {
+                self.write_to_image::<false, USE_TRANSPARENCY>(palette, output_slivers)
+            }
+            [true, false] => self.write_to_image::<true, USE_TRANSPARENCY>(palette, output_slivers),
+            [false, true] => {
+                self.write_to_image::<false, USE_TRANSPARENCY>(palette, output_slivers.rev())
+            }
+            [true, true] => {
+                self.write_to_image::<true, USE_TRANSPARENCY>(palette, output_slivers.rev())
+            }
         }
     }
 
+    /// Renders the tiles of `model`, as described in [`tiles_to_image`].
     pub fn tiles_to_image<'p>(
-        mut get_tile: impl FnMut(usize) -> Option<&'p Snes4BppTile>,
+        get_tile: impl FnMut(usize) -> Option<&'p Snes4BppTile>,
         palette: &[PaletteLine4Bpp<Color32>; TilemapEntry::ADDRESSABLE_PALETTES],
         model: &impl GridModel<Item = TilemapEntry>,
+        transparency: bool,
     ) -> ([usize; 2], Vec<Color32>) {
-        let [tiles_per_row, n_rows] = model.dimensions();
-        let [width, height] = [tiles_per_row * TILE_SIZE, n_rows * TILE_SIZE];
-        let mut pixels = vec![Color32::TRANSPARENT; width * height];
-        let slivers = pixels.as_chunks_mut::<TILE_SIZE>().0;
-
-        for (tile_y, row_slivers) in slivers
-            .chunks_exact_mut(tiles_per_row * TILE_SIZE)
-            .enumerate()
-        {
-            for tile_x in 0..tiles_per_row {
-                let Some(tile) = model.get(tile_x, tile_y) else {
-                    continue;
-                };
-                let Some(tile_gfx) = get_tile(tile.tile_id()) else {
+        tiles_to_image(get_tile, palette, model, transparency)
+    }
+}
+
+/// 2bpp tile, as used by the map and HUD graphics. Stored as 8 rows of 2 interleaved bitplanes.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Snes2BppTile(pub [u8; TILE_SIZE * 2]);
+
+#[cfg_attr(not(test), expect(unused))]
+impl Snes2BppTile {
+    pub fn from_bytes(data: &[u8; 16]) -> Self {
+        Self(*data)
+    }
+
+    /// Encodes a tile from the palette index of each of its pixels, in row-major order. Only the
+    /// low 2 bits of each index are used.
+    pub fn encode(indices: &[[u8; TILE_SIZE]; TILE_SIZE]) -> Self {
+        let mut data = [0; TILE_SIZE * 2];
+        for (row, out) in iter::zip(indices, data.as_chunks_mut::<2>().0) {
+            for (x, &index) in row.iter().enumerate() {
+                let bit = TILE_SIZE - 1 - x;
+                out[0].set_bit(bit, index.get_bit(0));
+                out[1].set_bit(bit, index.get_bit(1));
+            }
+        }
+        Self(data)
+    }
+
+    /// Pixels of each row, as 4-bit nibbles with the leftmost pixel in the most significant one.
+    fn decoded_rows(&self) -> impl Iterator<Item = u32> {
+        let (rows, _) = self.0.as_chunks::<2>();
+        rows.iter()
+            .map(|&[bp0, bp1]| decode_bitplanes([bp0, bp1, 0, 0]))
+    }
+
+    /// Palette index of each pixel, in row-major order.
+    pub fn pixel_indices(&self) -> [[u8; TILE_SIZE]; TILE_SIZE] {
+        let mut indices = [[0; TILE_SIZE]; TILE_SIZE];
+        for (bp, row) in iter::zip(self.decoded_rows(), &mut indices) {
+            for (x, index) in row.iter_mut().enumerate() {
+                *index = (bp >> (32 - 4 * (x + 1)) & 0xF) as u8;
+            }
+        }
+        indices
+    }
+
+    pub fn write_to_image<'p, const H_FLIP: bool, const USE_TRANSPARENCY: bool>(
+        &self,
+        palette: &PaletteLine2Bpp<Color32>,
+        output: impl Iterator<Item = &'p mut [Color32; TILE_SIZE]>,
+    ) {
+        for (mut bp, out_row) in self.decoded_rows().zip(output) {
+            for out_p in out_row {
+                let index;
+                if H_FLIP {
+                    index = bp & 0xF;
+                    bp >>= 4;
+                } else {
+                    index = bp >> (32 - 4);
+                    bp <<= 4;
+                }
+                if USE_TRANSPARENCY && index == 0 {
                     continue;
-                };
-
-                let output_slivers = row_slivers[tile_x..].iter_mut().step_by(tiles_per_row);
-                tile_gfx.write_to_image_flippable::<false>(
-                    &palette[tile.palette()],
-                    output_slivers,
-                    [tile.h_flip(), tile.v_flip()],
-                );
+                }
+                *out_p = palette[index as usize];
+            }
+        }
+    }
+
+    pub fn write_to_image_flippable<'p, const USE_TRANSPARENCY: bool>(
+        &self,
+        palette: &PaletteLine2Bpp<Color32>,
+        output_slivers: impl DoubleEndedIterator<Item = &'p mut [Color32; TILE_SIZE]>,
+        flips: [bool; 2],
+    ) {
+        match flips {
+            [false, false] => {
+                self.write_to_image::<false, USE_TRANSPARENCY>(palette, output_slivers)
+            }
+            [true, false] => self.write_to_image::<true, USE_TRANSPARENCY>(palette, output_slivers),
+            [false, true] => {
+                self.write_to_image::<false, USE_TRANSPARENCY>(palette, output_slivers.rev())
+            }
+            [true, true] => {
+                self.write_to_image::<true, USE_TRANSPARENCY>(palette, output_slivers.rev())
             }
         }
+    }
+
+    /// Renders the tiles of `model`, as described in [`tiles_to_image`].
+    #[expect(unused)]
+    pub fn tiles_to_image<'p>(
+        get_tile: impl FnMut(usize) -> Option<&'p Snes2BppTile>,
+        palette: &[PaletteLine2Bpp<Color32>; TilemapEntry::ADDRESSABLE_PALETTES],
+        model: &impl GridModel<Item = TilemapEntry>,
+        transparency: bool,
+    ) -> ([usize; 2], Vec<Color32>) {
+        tiles_to_image(get_tile, palette, model, transparency)
+    }
+}
+
+/// Tile format that can be drawn by [`tiles_to_image`].
+pub trait SnesTile {
+    /// Colors of a palette line, indexed by the tile's pixels.
+    type PaletteLine;
+
+    fn write_to_image_flippable<'p, const USE_TRANSPARENCY: bool>(
+        &self,
+        palette: &Self::PaletteLine,
+        output_slivers: impl DoubleEndedIterator<Item = &'p mut [Color32; TILE_SIZE]>,
+        flips: [bool; 2],
+    );
+}
+
+impl SnesTile for Snes4BppTile {
+    type PaletteLine = PaletteLine4Bpp<Color32>;
+
+    fn write_to_image_flippable<'p, const USE_TRANSPARENCY: bool>(
+        &self,
+        palette: &Self::PaletteLine,
+        output_slivers: impl DoubleEndedIterator<Item = &'p mut [Color32; TILE_SIZE]>,
+        flips: [bool; 2],
+    ) {
+        Snes4BppTile::write_to_image_flippable::<USE_TRANSPARENCY>(
+            self,
+            palette,
+            output_slivers,
+            flips,
+        );
+    }
+}
+
+impl SnesTile for Snes2BppTile {
+    type PaletteLine = PaletteLine2Bpp<Color32>;
 
-        ([width, height], pixels)
+    fn write_to_image_flippable<'p, const USE_TRANSPARENCY: bool>(
+        &self,
+        palette: &Self::PaletteLine,
+        output_slivers: impl DoubleEndedIterator<Item = &'p mut [Color32; TILE_SIZE]>,
+        flips: [bool; 2],
+    ) {
+        Snes2BppTile::write_to_image_flippable::<USE_TRANSPARENCY>(
+            self,
+            palette,
+            output_slivers,
+            flips,
+        );
     }
 }
 
+/// Renders the tiles of `model`. With `transparency`, pixels of color 0 are left transparent
+/// instead of taking the color from the palette, so the image can be layered over another.
+pub fn tiles_to_image<'p, T: SnesTile + 'p>(
+    mut get_tile: impl FnMut(usize) -> Option<&'p T>,
+    palette: &[T::PaletteLine; TilemapEntry::ADDRESSABLE_PALETTES],
+    model: &impl GridModel<Item = TilemapEntry>,
+    transparency: bool,
+) -> ([usize; 2], Vec<Color32>) {
+    let [tiles_per_row, n_rows] = model.dimensions();
+    let [width, height] = [tiles_per_row * TILE_SIZE, n_rows * TILE_SIZE];
+    let mut pixels = vec![Color32::TRANSPARENT; width * height];
+    let slivers = pixels.as_chunks_mut::<TILE_SIZE>().0;
+
+    for (tile_y, row_slivers) in slivers
+        .chunks_exact_mut(tiles_per_row * TILE_SIZE)
+        .enumerate()
+    {
+        for tile_x in 0..tiles_per_row {
+            let Some(tile) = model.get(tile_x, tile_y) else {
+                continue;
+            };
+            let Some(tile_gfx) = get_tile(tile.tile_id()) else {
+                continue;
+            };
+
+            let output_slivers = row_slivers[tile_x..].iter_mut().step_by(tiles_per_row);
+            let palette = &palette[tile.palette()];
+            let flips = [tile.h_flip(), tile.v_flip()];
+            if transparency {
+                tile_gfx.write_to_image_flippable::<true>(palette, output_slivers, flips);
+            } else {
+                tile_gfx.write_to_image_flippable::<false>(palette, output_slivers, flips);
+            }
+        }
+    }
+
+    ([width, height], pixels)
+}
+
 pub trait GridModel {
     type Item;
 
     fn dimensions(&self) -> [usize; 2];
     fn get(&self, x: usize, y: usize) -> Option<Self::Item>;
 }
+
+/// Lets models that wrap another one, like `SubGridModel`, either own or borrow it.
+impl<G: GridModel + ?Sized> GridModel for &G {
+    type Item = G::Item;
+
+    fn dimensions(&self) -> [usize; 2] {
+        (**self).dimensions()
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
+        (**self).get(x, y)
+    }
+}
+
+/// Rectangular view into a region of another `GridModel`, e.g. a single screen of level data.
+pub struct SubGridModel<G> {
+    pub inner: G,
+    pub origin: [usize; 2],
+    pub size: [usize; 2],
+}
+
+impl<G: GridModel> GridModel for SubGridModel<G> {
+    type Item = G::Item;
+
+    fn dimensions(&self) -> [usize; 2] {
+        self.size
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
+        if x >= self.size[0] || y >= self.size[1] {
+            return None;
+        }
+        self.inner.get(self.origin[0] + x, self.origin[1] + y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(r: u16, g: u16, b: u16) -> SnesColor {
+        SnesColor(r | g << 5 | b << 10)
+    }
+
+    #[test]
+    fn test_find_nearest_color() {
+        let mut colors = vec![SnesColor(0); 2 * Palette::LINE_4BPP_LEN];
+        colors[1] = rgb(31, 0, 0);
+        colors[2] = rgb(0, 31, 0);
+        colors[3] = rgb(31, 0, 0);
+        colors[17] = rgb(0, 0, 31);
+        colors[18] = rgb(10, 10, 10);
+        let palette = Palette(colors);
+
+        // Exact entries, with duplicates resolving to the first one
+        assert_eq!(
+            palette.find_nearest_color(rgb(0, 0, 31)),
+            Some((17, rgb(0, 0, 31)))
+        );
+        assert_eq!(
+            palette.find_nearest_color(rgb(31, 0, 0)),
+            Some((1, rgb(31, 0, 0)))
+        );
+        assert_eq!(
+            palette.find_nearest_color(rgb(0, 0, 0)),
+            Some((0, rgb(0, 0, 0)))
+        );
+        // Nearest by distance
+        assert_eq!(
+            palette.find_nearest_color(rgb(25, 3, 2)),
+            Some((1, rgb(31, 0, 0)))
+        );
+        assert_eq!(
+            palette.find_nearest_color(rgb(9, 12, 8)),
+            Some((18, rgb(10, 10, 10)))
+        );
+
+        assert_eq!(
+            palette.find_nearest_in_line(1, rgb(0, 0, 31)),
+            Some((1, rgb(0, 0, 31)))
+        );
+        assert_eq!(
+            palette.find_nearest_in_line(0, rgb(0, 0, 31)),
+            Some((0, rgb(0, 0, 0)))
+        );
+        assert_eq!(
+            palette.find_nearest_in_line(1, rgb(31, 2, 0)),
+            Some((2, rgb(10, 10, 10)))
+        );
+        assert_eq!(palette.find_nearest_in_line(2, rgb(0, 0, 0)), None);
+        assert_eq!(Palette(vec![]).find_nearest_color(rgb(0, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_tilemap_entry_xml_round_trip() {
+        for value in 0..=u16::MAX {
+            assert_eq!(
+                TilemapEntry::from_xml(HexU16(value)).to_xml(),
+                HexU16(value)
+            );
+        }
+    }
+
+    #[test]
+    fn test_tilemap_entry_setters() {
+        let entry = TilemapEntry::try_for_tile(0x3FF).unwrap();
+        assert_eq!(entry.tile_id(), 0x3FF);
+        let entry = entry.try_with_palette(7).unwrap();
+        assert_eq!((entry.tile_id(), entry.palette()), (0x3FF, 7));
+        assert_eq!(entry.0, 0x1FFF);
+
+        assert_eq!(
+            TilemapEntry::try_for_tile(0x400).err(),
+            Some(FieldOverflow {
+                field: "Tile",
+                value: 0x400,
+                limit: 0x400
+            })
+        );
+        let overflow = entry.try_with_palette(8).err().unwrap();
+        assert_eq!(
+            overflow.to_string(),
+            "Palette line 0x8 is out of range (must be below 0x8)"
+        );
+    }
+
+    #[test]
+    fn test_palette_diff() {
+        let base = Palette::from(vec![0x0000, 0x7FFF, 0x001F]);
+        let other = Palette::from(vec![0x0000, 0x7C1F, 0x001A, 0x1234]);
+
+        assert_eq!(
+            base.diff(&other),
+            vec![
+                ColorDiff::Identical,
+                ColorDiff::Changed([0, 31, 0]),
+                ColorDiff::Changed([5, 0, 0]),
+                ColorDiff::Added,
+            ]
+        );
+        assert_eq!(other.diff(&base)[3], ColorDiff::Removed);
+    }
+
+    #[test]
+    fn test_palette_truncate_checked() {
+        let mut palette = Palette::from(vec![0x1234; 8]);
+        palette.0.extend([SnesColor(0x7FFF), SnesColor(0x0000)]);
+        assert_eq!(palette.truncate_checked(8), Err(8));
+        assert_eq!(palette.len(), 10);
+
+        let mut palette = Palette::from(vec![0x1234, 0x0000, 0x0000]);
+        assert_eq!(palette.truncate_checked(1), Ok(()));
+        assert_eq!(palette.len(), 1);
+        assert_eq!(palette.truncate_checked(4), Ok(()));
+        assert_eq!(palette.len(), 1);
+    }
+
+    #[test]
+    fn test_2bpp_tile_decode() {
+        let mut data = [0; 16];
+        // Row 0: planes 1111_0000 and 1100_1100. Row 7: plane 1 only on the rightmost pixel.
+        data[..2].copy_from_slice(&[0xF0, 0xCC]);
+        data[14..].copy_from_slice(&[0x00, 0x01]);
+        let tile = Snes2BppTile::from_bytes(&data);
+
+        let indices = tile.pixel_indices();
+        assert_eq!(indices[0], [3, 3, 1, 1, 2, 2, 0, 0]);
+        assert_eq!(indices[7], [0, 0, 0, 0, 0, 0, 0, 2]);
+        assert!(indices[1..7].iter().flatten().all(|&i| i == 0));
+        assert_eq!(Snes2BppTile::encode(&indices), tile);
+
+        let palette = [Color32::BLACK, Color32::RED, Color32::GREEN, Color32::BLUE];
+        let mut pixels = [[Color32::TRANSPARENT; TILE_SIZE]; TILE_SIZE];
+        tile.write_to_image_flippable::<false>(&palette, pixels.iter_mut(), [true, true]);
+        assert_eq!(pixels[0][0], Color32::GREEN);
+        assert_eq!(pixels[7], [0, 0, 2, 2, 1, 1, 3, 3].map(|i| palette[i]));
+    }
+
+    /// Deterministic pseudo-random tile data, as the tests can't depend on a RNG crate.
+    fn noise_tile(seed: u32) -> Snes4BppTile {
+        let mut state = seed;
+        Snes4BppTile(array::from_fn(|_| {
+            // xorshift32
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        }))
+    }
+
+    #[test]
+    fn test_4bpp_tile_encode() {
+        let blank = Snes4BppTile([0; 32]);
+        assert_eq!(blank.pixel_indices(), [0; 64]);
+        assert_eq!(
+            Snes4BppTile::encode_from_indices(&blank.pixel_indices()),
+            blank
+        );
+
+        let palette: PaletteLine4Bpp<Color32> =
+            array::from_fn(|i| Color32::from_rgb(i as u8 * 16, 255 - i as u8 * 16, 128));
+        for seed in [1, 0xDEAD_BEEF, 12345] {
+            let tile = noise_tile(seed);
+            let indices = tile.pixel_indices();
+            assert_eq!(Snes4BppTile::encode_from_indices(&indices), tile);
+
+            let mut pixels = [Color32::TRANSPARENT; 64];
+            let slivers = pixels.as_chunks_mut::<TILE_SIZE>().0;
+            tile.write_to_image::<false, false>(&palette, slivers.iter_mut());
+            assert_eq!(
+                Snes4BppTile::encode_from_colors(&pixels, &palette),
+                Some(tile)
+            );
+        }
+    }
+
+    #[test]
+    fn test_4bpp_tile_encode_from_colors() {
+        let palette: PaletteLine4Bpp<Color32> =
+            array::from_fn(|i| Color32::from_gray(i as u8 * 17));
+        let mut pixels = [Color32::from_gray(20); 64];
+        pixels[1] = Color32::TRANSPARENT;
+        pixels[2] = Color32::WHITE;
+        let tile = Snes4BppTile::encode_from_colors(&pixels, &palette).unwrap();
+        assert_eq!(tile.pixel_indices()[..4], [1, 0, 15, 1]);
+
+        pixels[3] = Color32::from_black_alpha(128);
+        assert_eq!(Snes4BppTile::encode_from_colors(&pixels, &palette), None);
+    }
+
+    /// Single tile grid, showing tile 0.
+    struct OneTileGrid;
+
+    impl GridModel for OneTileGrid {
+        type Item = TilemapEntry;
+
+        fn dimensions(&self) -> [usize; 2] {
+            [1, 1]
+        }
+
+        fn get(&self, _x: usize, _y: usize) -> Option<Self::Item> {
+            TilemapEntry::try_for_tile(0).ok()
+        }
+    }
+
+    #[test]
+    fn test_tiles_to_image_transparency() {
+        // Left half of each row is color 1, right half color 0
+        let tile =
+            Snes4BppTile::from_bytes(&array::from_fn(
+                |i| if i < 16 && i % 2 == 0 { 0xF0 } else { 0 },
+            ));
+        let mut palette = [[Color32::BLACK; 16]; TilemapEntry::ADDRESSABLE_PALETTES];
+        palette[0][1] = Color32::RED;
+
+        let (_, opaque) =
+            Snes4BppTile::tiles_to_image(|_| Some(&tile), &palette, &OneTileGrid, false);
+        assert_eq!(
+            opaque[..8],
+            [[Color32::RED; 4], [Color32::BLACK; 4]].concat()
+        );
+        let (_, transparent) =
+            Snes4BppTile::tiles_to_image(|_| Some(&tile), &palette, &OneTileGrid, true);
+        assert_eq!(
+            transparent[..8],
+            [[Color32::RED; 4], [Color32::TRANSPARENT; 4]].concat()
+        );
+    }
+
+    /// Grid where each cell contains its own coordinates.
+    struct CoordGrid([usize; 2]);
+
+    impl GridModel for CoordGrid {
+        type Item = [usize; 2];
+
+        fn dimensions(&self) -> [usize; 2] {
+            self.0
+        }
+
+        fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
+            (x < self.0[0] && y < self.0[1]).then_some([x, y])
+        }
+    }
+
+    #[test]
+    fn test_sub_grid_model() {
+        let grid = CoordGrid([32, 16]);
+        let sub = SubGridModel {
+            inner: &grid,
+            origin: [16, 0],
+            size: [16, 16],
+        };
+
+        assert_eq!(sub.dimensions(), [16, 16]);
+        assert_eq!(sub.get(0, 0), Some([16, 0]));
+        assert_eq!(sub.get(15, 15), Some([31, 15]));
+        // Parent has data at these coordinates, but they're outside of the view.
+        let left_sub = SubGridModel {
+            inner: &grid,
+            origin: [0, 0],
+            size: [16, 16],
+        };
+        assert_eq!(left_sub.get(16, 0), None);
+        assert_eq!(left_sub.get(0, 16), None);
+        // Out of bounds of both the view and the parent
+        assert_eq!(sub.get(16, 0), None);
+    }
+
+    #[test]
+    fn test_sub_grid_model_owned() {
+        // Views can own their inner model, including another view
+        let sub = SubGridModel {
+            inner: SubGridModel {
+                inner: CoordGrid([32, 16]),
+                origin: [16, 0],
+                size: [16, 16],
+            },
+            origin: [4, 8],
+            size: [4, 4],
+        };
+        assert_eq!(sub.dimensions(), [4, 4]);
+        assert_eq!(sub.get(1, 2), Some([21, 10]));
+        assert_eq!(sub.get(4, 0), None);
+        // Or borrow it
+        let borrowed = SubGridModel {
+            inner: &sub,
+            origin: [1, 1],
+            size: [2, 2],
+        };
+        assert_eq!(borrowed.get(0, 0), Some([21, 9]));
+    }
+}
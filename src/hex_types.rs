@@ -4,9 +4,11 @@ use std::borrow::Cow;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
-use std::num::TryFromIntError;
+use std::num::{ParseIntError, TryFromIntError};
 use std::str::FromStr;
 
+mod ops;
+
 struct HexDeserializeVisitor<T>(PhantomData<fn() -> T>);
 
 impl<'de, T: FromStr + TryFrom<u64>> de::Visitor<'de> for HexDeserializeVisitor<T>
@@ -186,6 +188,22 @@ impl<'de> Deserialize<'de> for HexU16 {
 #[repr(transparent)]
 pub struct HexU24(pub u32);
 
+impl HexU24 {
+    /// Bank byte of a SNES address (bits 16-23).
+    pub fn bank(self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    /// Address within the bank (bits 0-15).
+    pub fn offset(self) -> HexU16 {
+        HexU16(self.0 as u16)
+    }
+
+    pub fn from_bank_offset(bank: u8, offset: HexU16) -> HexU24 {
+        HexU24(u32::from(bank) << 16 | u32::from(offset.0))
+    }
+}
+
 impl Display for HexU24 {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "${:06X}", self.0)
@@ -257,6 +275,158 @@ impl<'de> Deserialize<'de> for HexU24 {
     }
 }
 
+/// Signed byte, stored and displayed in the same two's-complement hex form as [`HexU8`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, TransparentWrapper)]
+#[repr(transparent)]
+pub struct HexI8(pub i8);
+
+impl Display for HexI8 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "${:02X}", self.0)
+    }
+}
+
+impl Debug for HexI8 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self, f)
+    }
+}
+
+impl fmt::UpperHex for HexI8 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:02X}", self.0)
+    }
+}
+
+impl fmt::LowerHex for HexI8 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}", self.0)
+    }
+}
+
+impl From<i8> for HexI8 {
+    fn from(val: i8) -> HexI8 {
+        HexI8(val)
+    }
+}
+
+impl TryFrom<u64> for HexI8 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        u8::try_from(value).map(|x| HexI8(x as i8))
+    }
+}
+
+impl From<HexI8> for i8 {
+    fn from(val: HexI8) -> i8 {
+        val.0
+    }
+}
+
+impl FromStr for HexI8 {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.strip_prefix('$').unwrap_or(s);
+        u8::from_str_radix(trimmed, 16).map(|x| HexI8(x as i8))
+    }
+}
+
+impl Serialize for HexI8 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HexI8 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(HexDeserializeVisitor::<HexI8>(PhantomData))
+    }
+}
+
+/// Signed word, stored and displayed in the same two's-complement hex form as [`HexU16`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, TransparentWrapper)]
+#[repr(transparent)]
+pub struct HexI16(pub i16);
+
+impl Display for HexI16 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "${:04X}", self.0)
+    }
+}
+
+impl Debug for HexI16 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self, f)
+    }
+}
+
+impl fmt::UpperHex for HexI16 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:04X}", self.0)
+    }
+}
+
+impl fmt::LowerHex for HexI16 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}", self.0)
+    }
+}
+
+impl From<i16> for HexI16 {
+    fn from(val: i16) -> HexI16 {
+        HexI16(val)
+    }
+}
+
+impl TryFrom<u64> for HexI16 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        u16::try_from(value).map(|x| HexI16(x as i16))
+    }
+}
+
+impl From<HexI16> for i16 {
+    fn from(val: HexI16) -> i16 {
+        val.0
+    }
+}
+
+impl FromStr for HexI16 {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.strip_prefix('$').unwrap_or(s);
+        u16::from_str_radix(trimmed, 16).map(|x| HexI16(x as i16))
+    }
+}
+
+impl Serialize for HexI16 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HexI16 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(HexDeserializeVisitor::<HexI16>(PhantomData))
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
 pub enum HexValue {
     Byte(HexU8),
@@ -318,8 +488,58 @@ impl From<HexU24> for HexValue {
     }
 }
 
+/// Width of a `HexValue`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum HexSize {
+    Byte,
+    Word,
+    Long,
+}
+
+// Crappy way of generating a ParseIntError with kind PosOverflow
+fn overflow_error() -> ParseIntError {
+    u8::from_str_radix("FFF", 16).unwrap_err()
+}
+
+impl HexValue {
+    pub fn value(self) -> u32 {
+        match self {
+            HexValue::Byte(HexU8(x)) => x.into(),
+            HexValue::Word(HexU16(x)) => x.into(),
+            HexValue::Long(HexU24(x)) => x,
+        }
+    }
+
+    /// Converts to a value of width `size`, or returns `None` if it doesn't fit.
+    pub fn resized(self, size: HexSize) -> Option<Self> {
+        let value = self.value();
+        Some(match size {
+            HexSize::Byte => HexValue::Byte(HexU8(value.try_into().ok()?)),
+            HexSize::Word => HexValue::Word(HexU16(value.try_into().ok()?)),
+            HexSize::Long => HexValue::Long(HexU24(value)),
+        })
+    }
+
+    /// Parses a value with width `size`, instead of inferring it from the number of digits like
+    /// `from_str` does. Fails if the value doesn't fit in `size`.
+    pub fn from_str_sized(s: &str, size: HexSize) -> Result<Self, ParseIntError> {
+        let trimmed = s.strip_prefix('$').unwrap_or(s);
+        let val = u32::from_str_radix(trimmed, 16)?;
+        HexValue::Long(HexU24(val))
+            .resized(size)
+            .filter(|_| val <= 0xFF_FFFF)
+            .ok_or_else(overflow_error)
+    }
+
+    /// Parses a value with width `size` if it fits, otherwise with the width inferred from its
+    /// digits like `from_str`, so that data wider than expected is kept instead of rejected.
+    pub fn from_str_size_hint(s: &str, size: HexSize) -> Result<Self, ParseIntError> {
+        HexValue::from_str_sized(s, size).or_else(|_| HexValue::from_str(s))
+    }
+}
+
 impl FromStr for HexValue {
-    type Err = std::num::ParseIntError;
+    type Err = ParseIntError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let trimmed = s.strip_prefix('$').unwrap_or(s);
@@ -329,12 +549,20 @@ impl FromStr for HexValue {
             1..=2 => HexValue::Byte(HexU8(val as u8)),
             3..=4 => HexValue::Word(HexU16(val as u16)),
             5..=6 => HexValue::Long(HexU24(val)),
-            // Crappy way of generating a ParseIntError with kind PosOverflow
-            _ => return Err(u8::from_str_radix("FFF", 16).unwrap_err()),
+            _ => return Err(overflow_error()),
         })
     }
 }
 
+impl Serialize for HexValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 impl<'de> Deserialize<'de> for HexValue {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -345,6 +573,36 @@ impl<'de> Deserialize<'de> for HexValue {
     }
 }
 
+/// Serde adapter for `HexValue` fields with a width implied by the surrounding data, instead of
+/// inferred from the number of digits. This keeps e.g. a word argument of `$0005` from becoming a
+/// byte after a round-trip. `BYTES` is the width in bytes, from 1 to 3. Values that don't fit in
+/// it keep the width they were written with. Used as `#[serde(with = "SizedHexValue::<2>")]`.
+pub struct SizedHexValue<const BYTES: usize>;
+
+impl<const BYTES: usize> SizedHexValue<BYTES> {
+    const SIZE: HexSize = match BYTES {
+        1 => HexSize::Byte,
+        2 => HexSize::Word,
+        3 => HexSize::Long,
+        _ => panic!("HexValue width must be 1 to 3 bytes"),
+    };
+
+    pub fn serialize<S>(value: &HexValue, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&value.resized(Self::SIZE).unwrap_or(*value))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HexValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Cow<str> = Deserialize::deserialize(deserializer)?;
+        HexValue::from_str_size_hint(&s, Self::SIZE).map_err(de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -380,4 +638,90 @@ mod tests {
             &std::num::IntErrorKind::PosOverflow
         );
     }
+
+    #[test]
+    fn test_hexvalue_from_str_sized() {
+        assert_eq!(HexValue::from_str("$05"), Ok(HexValue::Byte(HexU8(0x05))));
+        assert_eq!(
+            HexValue::from_str_sized("$05", HexSize::Word),
+            Ok(HexValue::Word(HexU16(0x0005)))
+        );
+        assert_eq!(
+            HexValue::from_str_sized("$0005", HexSize::Byte),
+            Ok(HexValue::Byte(HexU8(0x05)))
+        );
+        assert_eq!(
+            HexValue::from_str_sized("$05", HexSize::Long),
+            Ok(HexValue::Long(HexU24(0x000005)))
+        );
+        assert_eq!(
+            HexValue::from_str_sized("$100", HexSize::Byte)
+                .unwrap_err()
+                .kind(),
+            &std::num::IntErrorKind::PosOverflow
+        );
+        assert_eq!(
+            HexValue::from_str_sized("$1000000", HexSize::Long)
+                .unwrap_err()
+                .kind(),
+            &std::num::IntErrorKind::PosOverflow
+        );
+
+        assert_eq!(
+            HexValue::from_str_size_hint("$05", HexSize::Word),
+            Ok(HexValue::Word(HexU16(0x0005)))
+        );
+        assert_eq!(
+            HexValue::from_str_size_hint("$0100", HexSize::Byte),
+            Ok(HexValue::Word(HexU16(0x0100)))
+        );
+        assert_eq!(
+            HexValue::from_str_size_hint("$1000000", HexSize::Long)
+                .unwrap_err()
+                .kind(),
+            &std::num::IntErrorKind::PosOverflow
+        );
+    }
+
+    #[test]
+    fn test_sized_hexvalue_serde() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Arg {
+            #[serde(rename = "$text", with = "SizedHexValue::<2>")]
+            value: HexValue,
+        }
+
+        let parsed: Arg = quick_xml::de::from_str("<Arg>05</Arg>").unwrap();
+        assert_eq!(parsed.value, HexValue::Word(HexU16(0x0005)));
+        let serialized = quick_xml::se::to_string(&parsed).unwrap();
+        assert_eq!(serialized, "<Arg>$0005</Arg>");
+
+        let byte = Arg {
+            value: HexValue::Byte(HexU8(0x05)),
+        };
+        assert_eq!(quick_xml::se::to_string(&byte).unwrap(), "<Arg>$0005</Arg>");
+
+        // Wider values than declared keep their own width
+        let parsed: Arg = quick_xml::de::from_str("<Arg>010000</Arg>").unwrap();
+        assert_eq!(parsed.value, HexValue::Long(HexU24(0x010000)));
+        let serialized = quick_xml::se::to_string(&parsed).unwrap();
+        assert_eq!(serialized, "<Arg>$010000</Arg>");
+    }
+
+    #[test]
+    fn test_signed_hex_fromstr() {
+        assert_eq!(HexI8::from_str("$FF"), Ok(HexI8(-1)));
+        assert_eq!(HexI8::from_str("7F"), Ok(HexI8(127)));
+        assert_eq!(HexI8::from_str("$80"), Ok(HexI8(-128)));
+        assert_eq!(HexI16::from_str("$FFFE"), Ok(HexI16(-2)));
+        assert_eq!(HexI16::from_str("$0100"), Ok(HexI16(0x100)));
+        assert_eq!(
+            HexI8::from_str("$100").unwrap_err().kind(),
+            &std::num::IntErrorKind::PosOverflow
+        );
+
+        assert_eq!(HexI8(-1).to_string(), "$FF");
+        assert_eq!(HexI16(-0x10).to_string(), "$FFF0");
+        assert_eq!(HexI16(0x20).to_string(), "$0020");
+    }
 }
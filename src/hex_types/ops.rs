@@ -0,0 +1,105 @@
+//! Arithmetic and bitwise operators for the hex types, so SNES addresses and masks can be
+//! manipulated without unwrapping them. All operations wrap, and `HexU24` results are truncated to
+//! 24 bits.
+
+use super::{HexU8, HexU16, HexU24};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Not, Shl, Shr};
+
+macro_rules! impl_hex_ops {
+    ($ty:ident, $inner:ty) => {
+        impl Add<$inner> for $ty {
+            type Output = $ty;
+
+            fn add(self, rhs: $inner) -> $ty {
+                $ty::from(self.0.wrapping_add(rhs))
+            }
+        }
+
+        impl BitAnd for $ty {
+            type Output = $ty;
+
+            fn bitand(self, rhs: $ty) -> $ty {
+                $ty(self.0 & rhs.0)
+            }
+        }
+
+        impl BitOr for $ty {
+            type Output = $ty;
+
+            fn bitor(self, rhs: $ty) -> $ty {
+                $ty(self.0 | rhs.0)
+            }
+        }
+
+        impl BitXor for $ty {
+            type Output = $ty;
+
+            fn bitxor(self, rhs: $ty) -> $ty {
+                $ty(self.0 ^ rhs.0)
+            }
+        }
+
+        impl Not for $ty {
+            type Output = $ty;
+
+            fn not(self) -> $ty {
+                $ty::from(!self.0)
+            }
+        }
+
+        /// Bits shifted out are discarded, so shifting by the width or more gives 0.
+        impl Shl<u8> for $ty {
+            type Output = $ty;
+
+            fn shl(self, rhs: u8) -> $ty {
+                $ty::from(self.0.checked_shl(rhs.into()).unwrap_or(0))
+            }
+        }
+
+        impl Shr<u8> for $ty {
+            type Output = $ty;
+
+            fn shr(self, rhs: u8) -> $ty {
+                $ty(self.0.checked_shr(rhs.into()).unwrap_or(0))
+            }
+        }
+    };
+}
+
+// `HexU24::from` truncates to 24 bits
+impl_hex_ops!(HexU8, u8);
+impl_hex_ops!(HexU16, u16);
+impl_hex_ops!(HexU24, u32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_ops() {
+        assert_eq!(HexU8(0xFF) + 2, HexU8(0x01));
+        assert_eq!(HexU16(0xFFFF) + 1, HexU16(0x0000));
+        assert_eq!(HexU24(0xFF_FFFF) + 1, HexU24(0x00_0000));
+        assert_eq!(HexU24(0x8F_8000) + 0x10, HexU24(0x8F_8010));
+
+        assert_eq!(HexU16(0x1234) & HexU16(0x00FF), HexU16(0x0034));
+        assert_eq!(HexU8(0x0F) | HexU8(0x30), HexU8(0x3F));
+        assert_eq!(HexU8(0xFF) ^ HexU8(0x0F), HexU8(0xF0));
+        assert_eq!(!HexU8(0x0F), HexU8(0xF0));
+        assert_eq!(!HexU24(0x00_FFFF), HexU24(0xFF_0000));
+
+        assert_eq!(HexU24(0x8F) << 16, HexU24(0x8F_0000));
+        assert_eq!(HexU24(0x8F_0000) << 8, HexU24(0x00_0000));
+        assert_eq!(HexU16(0x1234) >> 8, HexU16(0x0012));
+        assert_eq!(HexU8(0x80) << 8, HexU8(0x00));
+        assert_eq!(HexU24(0xFF_FFFF) >> 40, HexU24(0));
+    }
+
+    #[test]
+    fn test_hex_u24_bank_offset() {
+        let addr = HexU24(0x8F_91F8);
+        assert_eq!(addr.bank(), 0x8F);
+        assert_eq!(addr.offset(), HexU16(0x91F8));
+        assert_eq!(HexU24::from_bank_offset(0x8F, HexU16(0x91F8)), addr);
+    }
+}
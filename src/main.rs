@@ -1,20 +1,31 @@
+mod archive;
+mod diagnostic;
+mod door_code;
 mod gfx;
 mod hex_types;
 mod project;
+mod project_source;
 mod room;
 #[allow(unused)]
 mod smart_xml;
+mod snes_address;
+mod snes_constants;
 mod tileset;
 mod ui;
+mod undo;
 mod util;
 
-use crate::project::{ProjectData, load_smart_project};
+use crate::project::{LoadPhase, LoadProgress, ProjectData, load_smart_project_with_progress};
 use crate::ui::promise::{EguiWaker, Promise};
+use crate::ui::recent_projects::RecentProjects;
+use crate::ui::settings::Settings;
 use crate::ui::views::{StartupDialog, Workspace};
 use blocking::{Task, unblock};
 use eframe::egui;
 use egui::{Color32, Context, Frame, Id, StrokeKind, ViewportBuilder, Visuals};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use std::{env, mem};
 
 const APP_ID: &str = "SMDEd";
@@ -60,17 +71,34 @@ fn main() -> eframe::Result {
 
 enum ApplicationUiState {
     NoOpenProject(StartupDialog),
-    LoadingProject(Promise<Task<anyhow::Result<ProjectData>>>),
-    ProjectLoaded(Workspace),
+    LoadingProject(
+        Promise<Task<anyhow::Result<ProjectData>>>,
+        /// Latest progress reported by the loading thread.
+        Arc<Mutex<Option<LoadProgress>>>,
+    ),
+    ProjectLoaded(Box<Workspace>),
     Invalid, // Used to facilitate state transitions
 }
 
 impl ApplicationUiState {
     fn load_project(ctx: &Context, project_path: PathBuf) -> Self {
-        ApplicationUiState::LoadingProject(Promise::launched(
+        let progress = Arc::new(Mutex::new(None));
+        let report_progress = {
+            let progress = progress.clone();
+            let ctx = ctx.clone();
+            move |p| {
+                *progress.lock().unwrap() = Some(p);
+                ctx.request_repaint();
+            }
+        };
+        let load_mode = Settings::get(ctx).load_mode;
+        let promise = Promise::launched(
             EguiWaker::for_context(ctx),
-            unblock(move || load_smart_project(&project_path)),
-        ))
+            unblock(move || {
+                load_smart_project_with_progress(&project_path, load_mode, report_progress)
+            }),
+        );
+        ApplicationUiState::LoadingProject(promise, progress)
     }
 }
 
@@ -80,21 +108,48 @@ struct Application {
 
 impl Application {
     fn new(cc: &eframe::CreationContext) -> Self {
+        Settings::get(&cc.egui_ctx).apply_theme(&cc.egui_ctx);
         Application {
-            state: ApplicationUiState::NoOpenProject(StartupDialog::new(&cc.egui_ctx)),
+            state: ApplicationUiState::NoOpenProject(StartupDialog::new(&cc.egui_ctx, cc.storage)),
         }
     }
 
     fn with_opened_project(cc: &eframe::CreationContext, project_path: PathBuf) -> Self {
+        Settings::get(&cc.egui_ctx).apply_theme(&cc.egui_ctx);
         Application {
             state: ApplicationUiState::load_project(&cc.egui_ctx, project_path),
         }
     }
+
+    fn show_load_progress(ui: &mut egui::Ui, progress: Option<LoadProgress>) {
+        let Some(progress) = progress else {
+            ui.spinner();
+            ui.label("Loading project...");
+            return;
+        };
+        let phase = match progress.phase {
+            LoadPhase::Tilesets => "tilesets",
+            LoadPhase::Rooms => "rooms",
+            LoadPhase::Maps => "maps",
+        };
+        if let Some(fraction) = progress.fraction() {
+            ui.add(egui::ProgressBar::new(fraction).desired_width(200.0));
+            ui.label(format!(
+                "Loading {phase} ({}/{})...",
+                progress.done,
+                progress.total.unwrap_or_default()
+            ));
+        } else {
+            ui.spinner();
+            ui.label(format!("Loading {phase}..."));
+        }
+    }
 }
 
 impl eframe::App for Application {
     fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
         ctx.options_mut(|opt| opt.max_passes = 2.try_into().unwrap());
+        Settings::get(ctx).apply_theme(ctx);
 
         //_debug_focus(ctx);
 
@@ -110,39 +165,63 @@ impl eframe::App for Application {
                     ApplicationUiState::NoOpenProject(startup_dialog)
                 }
             }
-            ApplicationUiState::LoadingProject(mut promise) => {
+            ApplicationUiState::LoadingProject(mut promise, progress) => {
+                let current_progress = *progress.lock().unwrap();
+                let mut cancel = false;
                 egui::Modal::new(Id::new("loading_project_spinner")).show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
-                        ui.spinner();
-                        ui.label("Loading project...");
+                        Self::show_load_progress(ui, current_progress);
+                        cancel = ui.button("Cancel").clicked();
                     });
                 });
-                if let Some(project) = promise.take_response() {
+                if cancel {
+                    promise.cancel();
+                    ApplicationUiState::NoOpenProject(StartupDialog::new(ctx, frame.storage()))
+                } else if let Some(project) = promise.take_response() {
                     match project {
-                        Ok(project) => ApplicationUiState::ProjectLoaded(Workspace::new(project)),
+                        Ok(project) => {
+                            if let Some(storage) = frame.storage_mut() {
+                                let mut recent_projects = RecentProjects::load(storage);
+                                recent_projects.add(&project.project_path, SystemTime::now());
+                                recent_projects.store(storage);
+                            }
+                            ApplicationUiState::ProjectLoaded(Box::new(Workspace::new(
+                                ctx, project,
+                            )))
+                        }
                         Err(e) => {
                             let message = format!("Error loading project: {e}");
                             ApplicationUiState::NoOpenProject(StartupDialog::with_error_message(
-                                ctx, message,
+                                ctx,
+                                frame.storage(),
+                                message,
                             ))
                         }
                     }
                 } else {
-                    ApplicationUiState::LoadingProject(promise)
+                    ApplicationUiState::LoadingProject(promise, progress)
                 }
             }
             ApplicationUiState::ProjectLoaded(mut workspace) => {
-                workspace.show(ctx);
-                ApplicationUiState::ProjectLoaded(workspace)
+                workspace.show(ctx, frame);
+                if let Some(project_path) = workspace.take_open_request() {
+                    ApplicationUiState::load_project(ctx, project_path)
+                } else if workspace.close_requested() {
+                    ApplicationUiState::NoOpenProject(StartupDialog::new(ctx, frame.storage()))
+                } else {
+                    ApplicationUiState::ProjectLoaded(workspace)
+                }
             }
             ApplicationUiState::Invalid => unreachable!(),
         }
     }
 
     fn clear_color(&self, visuals: &Visuals) -> [f32; 4] {
+        // Darker than windows so they stand out, but less so in light mode to not look dim.
+        let darken = if visuals.dark_mode { 0.5 } else { 0.15 };
         visuals
             .window_fill
-            .gamma_multiply(0.5)
+            .lerp_to_gamma(Color32::BLACK, darken)
             .to_normalized_gamma_f32()
     }
 }
@@ -1,60 +1,434 @@
-use crate::room::{Room, RoomIndex, RoomRef};
+use crate::diagnostic::{Diagnostic, DiagnosticLocation};
+use crate::hex_types::HexU8;
+use crate::project_source::open_project_source;
+use crate::room::{LevelDataEntry, MapDirection, Room, RoomIndex, RoomRef};
+use crate::smart_xml::{LoadMode, Map};
 use crate::tileset::{Tileset, TilesetIndex, TilesetKind, TilesetRef};
 use crate::{room, smart_xml, tileset};
 use slotmap::SlotMap;
-use std::collections::BTreeMap;
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Default)]
 pub struct ProjectData {
+    /// Root directory or archive the project was loaded from.
+    pub project_path: PathBuf,
+
     pub tilesets: SlotMap<TilesetRef, Tileset>,
     pub tileset_ids: BTreeMap<TilesetIndex, TilesetRef>,
     pub cre_tileset_ids: BTreeMap<TilesetIndex, TilesetRef>,
 
     pub rooms: SlotMap<RoomRef, Room>,
     pub room_ids: BTreeMap<RoomIndex, RoomRef>,
+
+    /// Pause screen maps, keyed by area index.
+    pub area_maps: BTreeMap<u8, Map>,
+
+    /// Problems found while loading that didn't prevent the project from opening. Those found in
+    /// a room's own data are kept in [`Room::diagnostics`] instead.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-pub fn validate_smart_project_path(project_path: &Path) -> Result<(), String> {
-    if !project_path.is_dir() {
-        return Err("Not a directory".into());
+impl ProjectData {
+    /// Title of the tileset, or a placeholder if it no longer exists.
+    pub fn tileset_display_name(&self, tileset: TilesetRef) -> Cow<'static, str> {
+        self.tilesets
+            .get(tileset)
+            .map_or("<UNKNOWN>".into(), |tileset| tileset.title().into())
+    }
+
+    /// Title of the room, or a placeholder if it no longer exists.
+    pub fn room_display_name(&self, room: RoomRef) -> Cow<'static, str> {
+        self.rooms
+            .get(room)
+            .map_or("<UNKNOWN>".into(), |room| room.title().into())
+    }
+
+    /// Every problem in the project, followed by those of each room in index order.
+    pub fn all_diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        let room_diagnostics = self
+            .room_ids
+            .values()
+            .filter_map(|&room_ref| self.rooms.get(room_ref))
+            .flat_map(|room| &room.diagnostics);
+        self.diagnostics.iter().chain(room_diagnostics)
+    }
+
+    /// Finds a room by its index in the project, e.g. to follow a door to its destination.
+    pub fn lookup_room_by_index(&self, area: u8, index: u8) -> Option<RoomRef> {
+        self.room_ids.get(&(area, index)).copied()
+    }
+
+    /// Re-reads a single room from the project files, discarding any unsaved changes to it. The
+    /// room keeps its `RoomRef`, so open editors and references from other rooms stay valid.
+    pub fn reload_room(&mut self, room_ref: RoomRef) -> anyhow::Result<()> {
+        let room = &self.rooms[room_ref];
+        let source = open_project_source(&self.project_path)?;
+        let smart_room = smart_xml::load_project_room(&*source, &room.source_path)?;
+        let index = (smart_room.area.into(), smart_room.index.into());
+        if room.index() != Some(index) {
+            anyhow::bail!(
+                "{} now contains room {},{}; reload the whole project instead",
+                room.source_path.display(),
+                HexU8(index.0),
+                HexU8(index.1)
+            );
+        }
+        self.rooms[room_ref] = room::load_from_smart(
+            index,
+            room.file_name.clone(),
+            room.source_path.clone(),
+            smart_room,
+            room_ref,
+        )?;
+        Ok(())
+    }
+
+    /// Re-reads a single tileset from the project files, discarding any unsaved changes to it.
+    /// Returns the problems found while loading it, which also replace the tileset's previous ones
+    /// in [`ProjectData::diagnostics`].
+    pub fn reload_tileset(
+        &mut self,
+        tileset_ref: TilesetRef,
+        mode: LoadMode,
+    ) -> anyhow::Result<Vec<Diagnostic>> {
+        let tileset = &self.tilesets[tileset_ref];
+        let Some(index) = tileset.index() else {
+            anyhow::bail!("{} wasn't loaded from the project files", tileset.title());
+        };
+        let source = open_project_source(&self.project_path)?;
+        let dirs = smart_tileset_dirs(tileset.kind);
+        let smart_tileset = smart_xml::load_project_tileset(&*source, dirs, index)?;
+        let mut warnings = Vec::new();
+        self.tilesets[tileset_ref] = tileset::load_from_smart(
+            tileset.kind,
+            index,
+            smart_tileset,
+            tileset_ref,
+            mode,
+            &mut warnings,
+        )?;
+        let location = DiagnosticLocation::Tileset(tileset_ref);
+        self.diagnostics.retain(|d| d.location != Some(location));
+        let start = self.diagnostics.len();
+        self.add_tileset_warnings(tileset_ref, warnings);
+        Ok(self.diagnostics[start..].to_vec())
+    }
+
+    fn add_tileset_warnings(&mut self, tileset_ref: TilesetRef, warnings: Vec<String>) {
+        let location = DiagnosticLocation::Tileset(tileset_ref);
+        self.diagnostics.extend(
+            warnings
+                .into_iter()
+                .map(|warning| Diagnostic::warning(warning).at(location)),
+        );
+    }
+
+    /// Location of the file a room was loaded from, if the project is a directory.
+    pub fn room_file_path(&self, room_ref: RoomRef) -> Option<PathBuf> {
+        let room = self.rooms.get(room_ref)?;
+        self.project_path
+            .is_dir()
+            .then(|| self.project_path.join(&room.source_path))
+    }
+
+    /// Location of the directory a tileset was exported to, if the project is a directory.
+    pub fn tileset_dir_path(&self, tileset_ref: TilesetRef) -> anyhow::Result<Option<PathBuf>> {
+        let tileset = &self.tilesets[tileset_ref];
+        let Some(dir) = tileset
+            .gfx_path
+            .parent()
+            .filter(|_| tileset.index().is_some())
+        else {
+            anyhow::bail!("{} wasn't loaded from the project files", tileset.title());
+        };
+        Ok(self
+            .project_path
+            .is_dir()
+            .then(|| self.project_path.join(dir)))
+    }
+
+    /// Counts the references to each tiletable block from the layer 1 of every room state, for
+    /// every tileset. Blocks are resolved the way the game would load them: SCE from the state's
+    /// graphics set, over the lowest numbered CRE, since vanilla has only one.
+    pub fn compute_block_usage(&self) -> HashMap<TilesetRef, Vec<u32>> {
+        let mut usage: HashMap<_, _> = self
+            .tilesets
+            .iter()
+            .map(|(tileset_ref, tileset)| (tileset_ref, vec![0; tileset.tiletable.len()]))
+            .collect();
+        let cre = self
+            .cre_tileset_ids
+            .values()
+            .next()
+            .and_then(|&tileset_ref| self.tilesets.get(tileset_ref));
+        for room in self.rooms.values() {
+            for state in &room.smart_room.states {
+                let Some(sce) = self
+                    .tileset_ids
+                    .get(&state.gfx_set.0)
+                    .and_then(|&tileset_ref| self.tilesets.get(tileset_ref))
+                else {
+                    continue;
+                };
+                let layout = tileset::detect_sources_layout(sce, cre);
+                for screen in &state.level_data.layer1.screens {
+                    for &value in &screen.data {
+                        let block = LevelDataEntry::from_xml(value).block_id();
+                        if let Some((tileset, i)) = layout.tiletable.lookup(block.into())
+                            && let Some(count) = usage
+                                .get_mut(&tileset.handle())
+                                .and_then(|counts| counts.get_mut(i))
+                        {
+                            *count += 1;
+                        }
+                    }
+                }
+            }
+        }
+        usage
+    }
+
+    /// Returns the rooms sharing an edge with `room_ref` on the `direction` side, ordered by index.
+    pub fn room_neighbors(&self, room_ref: RoomRef, direction: MapDirection) -> Vec<&Room> {
+        let Some(room) = self.rooms.get(room_ref) else {
+            return Vec::new();
+        };
+        let mut neighbors: Vec<_> = self
+            .rooms
+            .values()
+            .filter(|other| room.is_adjacent(other, direction))
+            .collect();
+        neighbors.sort_by_key(|other| other.index());
+        neighbors
+    }
+}
+
+/// Export and data directories of tilesets of `kind` in a SMART project.
+fn smart_tileset_dirs(kind: TilesetKind) -> [&'static str; 2] {
+    match kind {
+        TilesetKind::Sce => smart_xml::SCE_TILESET_DIRS,
+        TilesetKind::Cre => smart_xml::CRE_TILESET_DIRS,
+    }
+}
+
+/// Quotes `field` for a CSV file if it contains any special characters.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
     }
-    if !project_path.join("project.xml").exists() {
+}
+
+/// Writes a CSV table with a row of metadata for each room, ordered by index. Per-state values are
+/// taken from the first (default) state.
+pub fn export_rooms_csv(project: &ProjectData, writer: &mut dyn Write) -> anyhow::Result<()> {
+    writeln!(
+        writer,
+        "area,index,name,width_screens,height_screens,gfx_set,state_count,door_count,enemy_count,plm_count,file_name"
+    )?;
+    for (&(area, index), &room_ref) in &project.room_ids {
+        let Some(room) = project.rooms.get(room_ref) else {
+            continue;
+        };
+        let states = &room.smart_room.states;
+        let first_state = states.first();
+        let gfx_set = first_state.map_or(String::new(), |s| format!("{:02X}", s.gfx_set.0));
+        let enemy_count = first_state.map_or(0, |s| s.enemies.enemy.len());
+        let plm_count = first_state.map_or(0, |s| s.plms.len());
+        writeln!(
+            writer,
+            "{area:02X},{index:02X},{},{},{},{gfx_set},{},{},{enemy_count},{plm_count},{}",
+            csv_field(&room.name),
+            room.width_screens,
+            room.height_screens,
+            states.len(),
+            room.smart_room.doors.len(),
+            csv_field(&room.file_name),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn validate_smart_project_path(project_path: &Path) -> Result<(), String> {
+    let source = open_project_source(project_path).map_err(|e| format!("{e:#}"))?;
+    let exists = |path: &str| source.exists(Path::new(path)).unwrap_or(false);
+    if !exists("project.xml") {
         return Err("Does not contain project.xml".into());
     }
-    if !project_path.join("Export").exists() {
+    if !exists("Export") {
         return Err("Does not contain a Export/ directory".into());
     }
+    for subdir in ["Export/Rooms", "Export/Tileset/CRE", "Export/Tileset/SCE"] {
+        if !exists(subdir) {
+            return Err(format!(
+                "Does not contain a {subdir}/ directory. Was the project fully exported from SMART?"
+            ));
+        }
+    }
 
     Ok(())
 }
 
-pub fn load_smart_project(project_path: &Path) -> anyhow::Result<ProjectData> {
-    let mut project = ProjectData::default();
+/// Part of the project being loaded, reported by [`load_smart_project_with_progress`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LoadPhase {
+    Tilesets,
+    Rooms,
+    Maps,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LoadProgress {
+    pub phase: LoadPhase,
+    /// Items of the current phase processed so far.
+    pub done: usize,
+    /// Number of items in the current phase, if known.
+    pub total: Option<usize>,
+}
+
+impl LoadProgress {
+    fn started(phase: LoadPhase) -> Self {
+        Self {
+            phase,
+            done: 0,
+            total: None,
+        }
+    }
+
+    /// Completed fraction of the current phase, in 0..=1.
+    pub fn fraction(&self) -> Option<f32> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| self.done as f32 / total as f32)
+    }
+}
+
+pub fn load_smart_project(project_path: &Path, mode: LoadMode) -> anyhow::Result<ProjectData> {
+    load_smart_project_with_progress(project_path, mode, |_| {})
+}
+
+/// Loads a project like [`load_smart_project`], calling `progress` as each phase advances.
+pub fn load_smart_project_with_progress(
+    project_path: &Path,
+    mode: LoadMode,
+    progress: impl Fn(LoadProgress) + Send,
+) -> anyhow::Result<ProjectData> {
+    let mut project = ProjectData {
+        project_path: project_path.to_owned(),
+        ..Default::default()
+    };
 
-    let smart_tilesets = smart_xml::load_project_tilesets(project_path)?;
+    let source = open_project_source(project_path)?;
+    progress(LoadProgress::started(LoadPhase::Tilesets));
+    let smart_tilesets = smart_xml::load_project_tilesets(&*source)?;
     for (index, tileset) in smart_tilesets.sce {
         // TODO encapsulate the combination of SlotMap + BTreeMap for index
+        let mut warnings = Vec::new();
         let tileset_ref = project.tilesets.try_insert_with_key(|handle| {
-            tileset::load_from_smart(TilesetKind::Sce, index, tileset, handle)
+            tileset::load_from_smart(
+                TilesetKind::Sce,
+                index,
+                tileset,
+                handle,
+                mode,
+                &mut warnings,
+            )
         })?;
+        project.add_tileset_warnings(tileset_ref, warnings);
         project.tileset_ids.insert(index, tileset_ref);
     }
     for (index, tileset) in smart_tilesets.cre {
         // TODO encapsulate the combination of SlotMap + BTreeMap for index
+        let mut warnings = Vec::new();
         let tileset_ref = project.tilesets.try_insert_with_key(|handle| {
-            tileset::load_from_smart(TilesetKind::Cre, index, tileset, handle)
+            tileset::load_from_smart(
+                TilesetKind::Cre,
+                index,
+                tileset,
+                handle,
+                mode,
+                &mut warnings,
+            )
         })?;
+        project.add_tileset_warnings(tileset_ref, warnings);
         project.cre_tileset_ids.insert(index, tileset_ref);
     }
 
-    let smart_rooms = smart_xml::load_project_rooms(project_path)?;
-    for (index, (room_name, room)) in smart_rooms {
-        let room_ref = project
-            .rooms
-            .try_insert_with_key(|handle| room::load_from_smart(index, room_name, room, handle))?;
+    progress(LoadProgress::started(LoadPhase::Rooms));
+    let smart_rooms = smart_xml::load_project_rooms(&*source, mode, |done, total| {
+        progress(LoadProgress {
+            phase: LoadPhase::Rooms,
+            done,
+            total: Some(total),
+        });
+    })?;
+    for (index, room_file) in smart_rooms.rooms {
+        let room_ref = project.rooms.try_insert_with_key(|handle| {
+            room::load_from_smart(
+                index,
+                room_file.name,
+                room_file.path,
+                room_file.room,
+                handle,
+            )
+        })?;
         project.room_ids.insert(index, room_ref);
     }
+    // Duplicates point at the room that was kept in their place
+    for duplicate in &smart_rooms.duplicates {
+        let mut diagnostic = Diagnostic::error(duplicate.to_string());
+        if let Some(&room_ref) = project.room_ids.get(&duplicate.id) {
+            diagnostic = diagnostic.at(DiagnosticLocation::Room(room_ref));
+        }
+        project.diagnostics.push(diagnostic);
+    }
+
+    progress(LoadProgress::started(LoadPhase::Maps));
+    project.area_maps = smart_xml::load_project_area_maps(&*source)?;
 
     Ok(project)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("Landing Site"), "Landing Site");
+        assert_eq!(csv_field("Room, Copy"), "\"Room, Copy\"");
+        assert_eq!(csv_field("The \"Pit\""), "\"The \"\"Pit\"\"\"");
+    }
+
+    #[test]
+    fn test_export_rooms_csv() {
+        let smart_room =
+            smart_xml::tests::parse_room_xml(smart_xml::tests::SAMPLE_ROOM_XML).unwrap();
+        let mut project = ProjectData::default();
+        let room_ref = project.rooms.insert_with_key(|handle| {
+            room::load_from_smart(
+                (0x01, 0x1A),
+                "sample_room".into(),
+                "Export/Rooms/sample_room.xml".into(),
+                smart_room,
+                handle,
+            )
+            .unwrap()
+        });
+        project.room_ids.insert((0x01, 0x1A), room_ref);
+
+        let mut csv = Vec::new();
+        export_rooms_csv(&project, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "\
+area,index,name,width_screens,height_screens,gfx_set,state_count,door_count,enemy_count,plm_count,file_name
+01,1A,Sample Room,2,1,01,2,3,0,0,sample_room
+"
+        );
+    }
+}
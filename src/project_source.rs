@@ -0,0 +1,315 @@
+//! Access to the files of a SMART project, which can be either a directory or a zip archive of
+//! one. Paths are always relative to the project root, e.g. `Export/Rooms`.
+
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use zip::ZipArchive;
+
+/// Entry of a directory listed by [`ProjectSource::read_dir`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SourceEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Read-only view of the files of a project. Sources are shared between the threads loading the
+/// project in parallel.
+pub trait ProjectSource: Send + Sync {
+    /// Opens the file at `path` for reading. Fails with [`io::ErrorKind::NotFound`] if there's
+    /// none. By default the whole file is read upfront.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(Cursor::new(self.read(path)?)))
+    }
+    /// Reads the whole file at `path`. Fails with [`io::ErrorKind::NotFound`] if there's none.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    /// Lists the entries directly inside the directory at `path`.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<SourceEntry>>;
+    fn exists(&self, path: &Path) -> io::Result<bool>;
+}
+
+/// Opens the project at `path`, which can be a project directory or a `.smart`/`.zip` archive.
+pub fn open_project_source(path: &Path) -> anyhow::Result<Box<dyn ProjectSource>> {
+    if path.is_dir() {
+        return Ok(Box::new(FsSource::new(path)));
+    }
+    let is_archive = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("smart") || ext.eq_ignore_ascii_case("zip"));
+    if path.is_file() && is_archive {
+        let data: Arc<[u8]> = std::fs::read(path)?.into();
+        Ok(Box::new(ZipSource::new(Cursor::new(data))?))
+    } else {
+        Err(anyhow!("Not a directory or a .smart/.zip archive"))
+    }
+}
+
+/// Project extracted into a directory.
+pub struct FsSource {
+    root: PathBuf,
+}
+
+impl FsSource {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_owned(),
+        }
+    }
+}
+
+impl ProjectSource for FsSource {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(self.root.join(path))?))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<SourceEntry>> {
+        std::fs::read_dir(self.root.join(path))?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(SourceEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    is_dir: entry.file_type()?.is_dir(),
+                })
+            })
+            .collect()
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        std::fs::exists(self.root.join(path))
+    }
+}
+
+/// Project stored in a zip archive. The project can be at the root of the archive or inside a
+/// directory in it (as happens when zipping the project folder itself), which is found by looking
+/// for `project.xml`.
+///
+/// Each read works on its own clone of the archive, so files can be decompressed in parallel.
+/// `R` should be cheap to clone, like a `Cursor` over an `Arc<[u8]>`.
+pub struct ZipSource<R> {
+    archive: ZipArchive<R>,
+    /// Index in the archive of each file, by its normalized path relative to the project root.
+    files: BTreeMap<String, usize>,
+}
+
+impl<R: Read + Seek + Clone> ZipSource<R> {
+    pub fn new(reader: R) -> anyhow::Result<Self> {
+        let mut archive = ZipArchive::new(reader)?;
+        let names: Vec<(String, usize)> = (0..archive.len())
+            .map(|i| {
+                let file = archive.by_index_raw(i)?;
+                // Some tools write Windows separators
+                let name = file.name()?.replace('\\', "/");
+                Ok((name.trim_start_matches("./").to_owned(), i))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let root = names
+            .iter()
+            .filter_map(|(name, _)| name.strip_suffix("project.xml"))
+            .filter(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+            .min_by_key(|prefix| prefix.len())
+            .unwrap_or("")
+            .to_owned();
+        let files = names
+            .into_iter()
+            .filter(|(name, _)| !name.ends_with('/'))
+            .filter_map(|(name, i)| Some((name.strip_prefix(&root)?.to_owned(), i)))
+            .collect();
+        Ok(Self { archive, files })
+    }
+
+    /// Converts `path` to the form used as key in `files`.
+    fn key(path: &Path) -> String {
+        let parts: Vec<_> = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+        parts.join("/")
+    }
+
+    /// Files inside the directory `key`, with their paths relative to it.
+    fn files_in(&self, key: &str) -> impl Iterator<Item = &str> {
+        let prefix = if key.is_empty() {
+            String::new()
+        } else {
+            format!("{key}/")
+        };
+        let prefix_len = prefix.len();
+        self.files
+            .range(prefix.clone()..)
+            .map(|(name, _)| name.as_str())
+            .take_while(move |name| name.starts_with(&prefix))
+            .map(move |name| &name[prefix_len..])
+    }
+}
+
+impl<R: Read + Seek + Clone + Send + Sync> ProjectSource for ZipSource<R> {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let key = Self::key(path);
+        let &index = self.files.get(&key).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{key} not found in archive"),
+            )
+        })?;
+        let mut archive = self.archive.clone();
+        let mut file = archive.by_index(index).map_err(io::Error::other)?;
+        let mut contents = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<SourceEntry>> {
+        let key = Self::key(path);
+        let mut entries = BTreeMap::new();
+        for relative in self.files_in(&key) {
+            match relative.split_once('/') {
+                Some((dir, _)) => entries.insert(dir, true),
+                None => entries.insert(relative, false),
+            };
+        }
+        if entries.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{key}/ not found in archive"),
+            ));
+        }
+        Ok(entries
+            .into_iter()
+            .map(|(name, is_dir)| SourceEntry {
+                name: name.to_owned(),
+                is_dir,
+            })
+            .collect())
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        let key = Self::key(path);
+        Ok(self.files.contains_key(&key) || self.files_in(&key).next().is_some())
+    }
+}
+
+/// Project held in memory, for testing the loaders without touching the filesystem.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemorySource {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+#[cfg(test)]
+impl MemorySource {
+    pub fn with_file(mut self, path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+}
+
+#[cfg(test)]
+impl ProjectSource for MemorySource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<SourceEntry>> {
+        let mut entries = BTreeMap::new();
+        for file in self.files.keys() {
+            let Ok(relative) = file.strip_prefix(path) else {
+                continue;
+            };
+            let mut components = relative.components();
+            if let Some(first) = components.next() {
+                let name = first.as_os_str().to_string_lossy().into_owned();
+                entries.insert(name, components.next().is_some());
+            }
+        }
+        if entries.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        Ok(entries
+            .into_iter()
+            .map(|(name, is_dir)| SourceEntry { name, is_dir })
+            .collect())
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        Ok(self.files.keys().any(|file| file.starts_with(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn zip_with_files(files: &[&str]) -> Cursor<Vec<u8>> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for &name in files {
+            if name.ends_with('/') {
+                zip.add_directory(name, SimpleFileOptions::default())
+                    .unwrap();
+            } else {
+                zip.start_file(name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(name.as_bytes()).unwrap();
+            }
+        }
+        let mut cursor = zip.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn test_zip_source_nested_root() {
+        let zip = zip_with_files(&[
+            "MyProject/",
+            "MyProject/project.xml",
+            "MyProject/Export/Rooms/a.xml",
+            "MyProject/Export/Rooms/b.xml",
+            "MyProject/Export/Tileset/SCE/00/8x8tiles.gfx",
+            "Other/file.txt",
+        ]);
+        let source = ZipSource::new(zip).unwrap();
+
+        assert!(source.exists(Path::new("project.xml")).unwrap());
+        assert!(source.exists(Path::new("Export/Tileset")).unwrap());
+        assert!(!source.exists(Path::new("Other/file.txt")).unwrap());
+        assert_eq!(
+            source.read(Path::new("Export/Rooms/b.xml")).unwrap(),
+            b"MyProject/Export/Rooms/b.xml"
+        );
+        assert_eq!(
+            source.read(Path::new("missing.xml")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let file = |name: &str| SourceEntry {
+            name: name.into(),
+            is_dir: false,
+        };
+        assert_eq!(
+            source.read_dir(Path::new("Export/Rooms")).unwrap(),
+            [file("a.xml"), file("b.xml")]
+        );
+        assert_eq!(
+            source.read_dir(Path::new("Export/Tileset/SCE")).unwrap(),
+            [SourceEntry {
+                name: "00".into(),
+                is_dir: true
+            }]
+        );
+    }
+}
@@ -1,82 +1,955 @@
+use crate::diagnostic::{Diagnostic, DiagnosticLocation};
+use crate::gfx::{FieldOverflow, GridModel};
+use crate::hex_types::{HexU8, HexU16};
 use crate::smart_xml;
+use crate::undo::Edit;
 use bit_field::BitField;
+use egui::Color32;
 use heck::ToTitleCase;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Copy, Clone)]
+/// Layer 1 level data entry, in the format used by the vanilla game:
+///
+/// - 0-9: Block number in the tiletable
+/// - 10: Horizontal flip
+/// - 11: Vertical flip
+/// - 12-15: Block type
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct LevelDataEntry(pub u16);
 
 impl LevelDataEntry {
+    const BLOCK_ID_BITS: Range<usize> = 0..10;
+    const H_FLIP_BIT: usize = 10;
+    const V_FLIP_BIT: usize = 11;
+    const BLOCK_TYPE_BITS: Range<usize> = 12..16;
+
+    /// Converts from the layer 1 tile value representation used by the SMART XML.
+    pub fn from_xml(value: HexU16) -> Self {
+        Self(value.0)
+    }
+
+    /// Converts to the layer 1 tile value representation used by the SMART XML.
+    pub fn to_xml(self) -> HexU16 {
+        HexU16(self.0)
+    }
+
     /// Tile index into the tiletable.
     pub fn block_id(self) -> u16 {
-        self.0.get_bits(0..10)
+        self.0.get_bits(Self::BLOCK_ID_BITS)
     }
 
     pub fn h_flip(self) -> bool {
-        self.0.get_bit(11)
+        self.0.get_bit(Self::H_FLIP_BIT)
     }
 
     pub fn v_flip(self) -> bool {
-        self.0.get_bit(12)
+        self.0.get_bit(Self::V_FLIP_BIT)
     }
 
-    #[expect(unused)]
     pub fn block_type(self) -> u16 {
-        self.0.get_bits(12..)
+        self.0.get_bits(Self::BLOCK_TYPE_BITS)
+    }
+
+    pub fn typed_block_type(self) -> BlockType {
+        BlockType::from_bits(self.block_type())
     }
 
-    // TODO: Silently discards overflow
-    pub fn for_tile(tile: u16) -> Self {
-        Self(tile & ((1 << 10) - 1))
+    /// Entry showing block `tile` with every other field cleared. Fails for blocks past the 1024
+    /// that level data can address.
+    pub fn try_for_tile(tile: u16) -> Result<Self, FieldOverflow> {
+        let tile = FieldOverflow::check("Block", tile.into(), Self::BLOCK_ID_BITS)?;
+        Ok(Self(tile))
     }
 
-    #[expect(unused)]
     pub fn with_flips(mut self, h_flip: bool, v_flip: bool) -> Self {
-        self.0.set_bit(11, h_flip);
-        self.0.set_bit(12, v_flip);
+        self.0.set_bit(Self::H_FLIP_BIT, h_flip);
+        self.0.set_bit(Self::V_FLIP_BIT, v_flip);
         self
     }
 }
 
+/// Collision type of a block, as encoded in the top 4 bits of level data entries by the vanilla
+/// game. Most types are further parametrized by the block's BTS value.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BlockType {
+    Air,
+    Slope,
+    /// Air that also triggers the X-ray "spike air" reaction.
+    SpikeAir,
+    Treadmill,
+    ShootableAir,
+    /// Copies the block to the left (or right, with a negative BTS).
+    HorizontalExtension,
+    UnusedAir,
+    BombableAir,
+    Solid,
+    Door,
+    Spike,
+    Crumble,
+    Shootable,
+    /// Copies the block above (or below, with a negative BTS).
+    VerticalExtension,
+    Grapple,
+    Bombable,
+}
+
+impl BlockType {
+    const ALL: [BlockType; 16] = [
+        BlockType::Air,
+        BlockType::Slope,
+        BlockType::SpikeAir,
+        BlockType::Treadmill,
+        BlockType::ShootableAir,
+        BlockType::HorizontalExtension,
+        BlockType::UnusedAir,
+        BlockType::BombableAir,
+        BlockType::Solid,
+        BlockType::Door,
+        BlockType::Spike,
+        BlockType::Crumble,
+        BlockType::Shootable,
+        BlockType::VerticalExtension,
+        BlockType::Grapple,
+        BlockType::Bombable,
+    ];
+
+    /// Interprets the low 4 bits of `bits` as a block type.
+    pub fn from_bits(bits: u16) -> Self {
+        Self::ALL[usize::from(bits & 0xF)]
+    }
+
+    /// Color used to represent the block type in overlays.
+    pub fn color(self) -> Color32 {
+        match self {
+            BlockType::Air | BlockType::UnusedAir => Color32::TRANSPARENT,
+            BlockType::Slope => Color32::from_rgb(0x80, 0x80, 0x80),
+            BlockType::SpikeAir => Color32::from_rgb(0xFF, 0x80, 0x80),
+            BlockType::Treadmill => Color32::from_rgb(0x80, 0x40, 0x00),
+            BlockType::ShootableAir => Color32::from_rgb(0xFF, 0xC0, 0x40),
+            BlockType::HorizontalExtension | BlockType::VerticalExtension => {
+                Color32::from_rgb(0x40, 0x40, 0xFF)
+            }
+            BlockType::BombableAir => Color32::from_rgb(0xC0, 0x80, 0xFF),
+            BlockType::Solid => Color32::WHITE,
+            BlockType::Door => Color32::from_rgb(0x00, 0xC0, 0xFF),
+            BlockType::Spike => Color32::RED,
+            BlockType::Crumble => Color32::from_rgb(0xC0, 0x80, 0x40),
+            BlockType::Shootable => Color32::ORANGE,
+            BlockType::Grapple => Color32::GREEN,
+            BlockType::Bombable => Color32::from_rgb(0x80, 0x00, 0xFF),
+        }
+    }
+}
+
+/// Liquid shown by an FX1 entry, as selected by its `type` field.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Liquid {
+    Lava,
+    Acid,
+    Water,
+}
+
+impl Liquid {
+    /// Interprets an FX1 `type`. Other FX types (rain, fog, etc.) don't have a liquid surface.
+    pub fn from_fx_type(type_: u8) -> Option<Self> {
+        match type_ {
+            0x02 => Some(Liquid::Lava),
+            0x04 => Some(Liquid::Acid),
+            0x06 => Some(Liquid::Water),
+            _ => None,
+        }
+    }
+
+    /// Tint used to preview the liquid over the room. Only approximates the in-game look, which
+    /// depends on the FX palette and layer blending settings.
+    pub fn tint(self) -> Color32 {
+        match self {
+            Liquid::Lava => Color32::from_rgba_unmultiplied(0xFF, 0x60, 0x00, 0x80),
+            Liquid::Acid => Color32::from_rgba_unmultiplied(0x80, 0xFF, 0x00, 0x60),
+            Liquid::Water => Color32::from_rgba_unmultiplied(0x20, 0x60, 0xFF, 0x50),
+        }
+    }
+}
+
+/// Converts an FX1 surface position (`surfacestart` or `surfacenew`) to a Y coordinate in pixels
+/// from the top of the room. The value $FFFF means the FX has no surface.
+pub fn fx_surface_y(surface: HexU16) -> Option<u16> {
+    (surface.0 != 0xFFFF).then_some(surface.0)
+}
+
+/// Width and height of a room screen, in blocks.
+pub const SCREEN_BLOCKS: usize = 16;
+
+/// Layer 1 of a room state, presented as a single grid of blocks spanning all of its screens.
+pub struct Layer1Model<'r> {
+    pub level_data: &'r smart_xml::LevelData,
+}
+
+impl GridModel for Layer1Model<'_> {
+    type Item = LevelDataEntry;
+
+    fn dimensions(&self) -> [usize; 2] {
+        [
+            usize::from(self.level_data.width.0) * SCREEN_BLOCKS,
+            usize::from(self.level_data.height.0) * SCREEN_BLOCKS,
+        ]
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
+        layer_block(&self.level_data.layer1, x, y).map(LevelDataEntry::from_xml)
+    }
+}
+
+/// Layer 2 blocks of a room state, drawn behind layer 1. Empty for states that use a library
+/// background instead.
+pub struct Layer2Model<'r> {
+    pub level_data: &'r smart_xml::LevelData,
+}
+
+impl GridModel for Layer2Model<'_> {
+    type Item = LevelDataEntry;
+
+    fn dimensions(&self) -> [usize; 2] {
+        [
+            usize::from(self.level_data.width.0) * SCREEN_BLOCKS,
+            usize::from(self.level_data.height.0) * SCREEN_BLOCKS,
+        ]
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
+        let layer2 = self.level_data.layer2.as_ref()?;
+        layer_block(layer2, x, y).map(LevelDataEntry::from_xml)
+    }
+}
+
+impl Layer1Model<'_> {
+    /// BTS value of the block at (`x`, `y`). Further specifies the block type, e.g. the index of
+    /// the room door that a door block leads through.
+    pub fn bts(&self, x: usize, y: usize) -> Option<u8> {
+        layer_block(&self.level_data.bts, x, y).map(|HexU8(bts)| bts)
+    }
+}
+
+impl Layer1Model<'_> {
+    /// Copies the blocks in the `width`×`height` rectangle at `origin`. Blocks outside of the room
+    /// are read as 0.
+    pub fn region(&self, origin: [usize; 2], width: usize, height: usize) -> BlockSelection {
+        let [x0, y0] = origin;
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| [x0 + x, y0 + y]))
+            .map(|[x, y]| self.get(x, y).unwrap_or(LevelDataEntry(0)))
+            .collect();
+        BlockSelection {
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+fn layer_block<T: Copy>(layer: &smart_xml::LevelDataLayer<T>, x: usize, y: usize) -> Option<T> {
+    let [screen_x, screen_y] = [x / SCREEN_BLOCKS, y / SCREEN_BLOCKS];
+    // Screens aren't guaranteed to be stored in order, so look them up by their coordinates
+    let screen = layer.screens.iter().find(|screen| {
+        usize::from(screen.x.0) == screen_x && usize::from(screen.y.0) == screen_y
+    })?;
+    let i = (y % SCREEN_BLOCKS) * SCREEN_BLOCKS + x % SCREEN_BLOCKS;
+    screen.data.get(i).copied()
+}
+
+fn layer_block_mut<T>(
+    layer: &mut smart_xml::LevelDataLayer<T>,
+    x: usize,
+    y: usize,
+) -> Option<&mut T> {
+    let [screen_x, screen_y] = [x / SCREEN_BLOCKS, y / SCREEN_BLOCKS];
+    let screen = layer.screens.iter_mut().find(|screen| {
+        usize::from(screen.x.0) == screen_x && usize::from(screen.y.0) == screen_y
+    })?;
+    let i = (y % SCREEN_BLOCKS) * SCREEN_BLOCKS + x % SCREEN_BLOCKS;
+    screen.data.get_mut(i)
+}
+
+/// Rectangle of layer 1 blocks, in row-major order. Used to copy and paste parts of rooms.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BlockSelection {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<LevelDataEntry>,
+}
+
+impl BlockSelection {
+    pub fn filled(width: usize, height: usize, entry: LevelDataEntry) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![entry; width * height],
+        }
+    }
+
+    /// Mirrors the selection horizontally, also flipping each of the blocks.
+    pub fn flipped_h(&self) -> Self {
+        let data = self
+            .data
+            .chunks(self.width.max(1))
+            .flat_map(|row| row.iter().rev())
+            .map(|e| e.with_flips(!e.h_flip(), e.v_flip()))
+            .collect();
+        Self { data, ..*self }
+    }
+
+    /// Mirrors the selection vertically, also flipping each of the blocks.
+    pub fn flipped_v(&self) -> Self {
+        let data = self
+            .data
+            .chunks(self.width.max(1))
+            .rev()
+            .flatten()
+            .map(|e| e.with_flips(e.h_flip(), !e.v_flip()))
+            .collect();
+        Self { data, ..*self }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MapDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl MapDirection {
+    pub const ALL: [MapDirection; 4] = [
+        MapDirection::Left,
+        MapDirection::Right,
+        MapDirection::Up,
+        MapDirection::Down,
+    ];
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            MapDirection::Left => "⬅",
+            MapDirection::Right => "➡",
+            MapDirection::Up => "⬆",
+            MapDirection::Down => "⬇",
+        }
+    }
+}
+
 slotmap::new_key_type! { pub struct RoomRef; }
 pub type RoomIndex = (u8, u8);
 
+/// Parses a room index typed as an area and index in hex, e.g. `4,1F` or `$04 $1F`.
+pub fn parse_room_index(text: &str) -> Option<RoomIndex> {
+    let mut parts = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(HexU8::from_str);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(HexU8(area))), Some(Ok(HexU8(index))), None) => Some((area, index)),
+        _ => None,
+    }
+}
+
 pub struct Room {
     handle: RoomRef,
     index: Option<RoomIndex>,
+    /// Set when the room has been modified since it was loaded.
+    dirty: bool,
 
+    /// Stem of the file the room was loaded from in `Export/Rooms`, used to find it again on disk.
+    pub file_name: String,
+    /// File the room was loaded from, relative to the project root, for saving it back.
+    pub source_path: PathBuf,
+    /// Human-readable name shown in the UI. Derived from `file_name` when the room is loaded.
     pub name: String,
+    /// Position of the top-left screen of the room on the area map, in screens.
+    pub map_x: u8,
+    pub map_y: u8,
+    pub width_screens: u8,
+    pub height_screens: u8,
+
+    /// Full parsed room data from the SMART export, used by editors to access room states, doors,
+    /// PLMs, etc. Header fields that are modelled directly by `Room` take precedence over it.
+    pub smart_room: smart_xml::Room,
+    /// Problems found in `smart_room` while loading it.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl Room {
-    #[expect(unused)]
     pub fn handle(&self) -> RoomRef {
         self.handle
     }
 
-    #[expect(unused)]
     pub fn index(&self) -> Option<RoomIndex> {
         self.index
     }
 
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns true if `other` is in the same area and shares a map edge with this room on the
+    /// `direction` side.
+    pub fn is_adjacent(&self, other: &Room, direction: MapDirection) -> bool {
+        fn spans_overlap(a: u8, a_len: u8, b: u8, b_len: u8) -> bool {
+            u16::from(a) < u16::from(b) + u16::from(b_len)
+                && u16::from(b) < u16::from(a) + u16::from(a_len)
+        }
+        fn touches(a: u8, a_len: u8, b: u8) -> bool {
+            u16::from(a) + u16::from(a_len) == u16::from(b)
+        }
+
+        let same_area = matches!((self.index, other.index), (Some((a, _)), Some((b, _))) if a == b);
+        let overlaps_x = || {
+            spans_overlap(
+                self.map_x,
+                self.width_screens,
+                other.map_x,
+                other.width_screens,
+            )
+        };
+        let overlaps_y = || {
+            spans_overlap(
+                self.map_y,
+                self.height_screens,
+                other.map_y,
+                other.height_screens,
+            )
+        };
+        same_area
+            && self.handle != other.handle
+            && match direction {
+                MapDirection::Left => {
+                    touches(other.map_x, other.width_screens, self.map_x) && overlaps_y()
+                }
+                MapDirection::Right => {
+                    touches(self.map_x, self.width_screens, other.map_x) && overlaps_y()
+                }
+                MapDirection::Up => {
+                    touches(other.map_y, other.height_screens, self.map_y) && overlaps_x()
+                }
+                MapDirection::Down => {
+                    touches(self.map_y, self.height_screens, other.map_y) && overlaps_x()
+                }
+            }
+    }
+
     pub fn title(&self) -> String {
-        let print_name = self.name.to_title_case();
         if let Some((area, room)) = self.index {
-            format!("[{area:02X},{room:02X}] {print_name}")
+            format!("[{area:02X},{room:02X}] {}", self.name)
         } else {
-            format!("[??,??] {print_name}")
+            format!("[??,??] {}", self.name)
+        }
+    }
+
+    /// Plain text description of the room's header, states and doors, for pasting into bug reports
+    /// and documentation. Only depends on the room data, so it's stable across edits elsewhere.
+    pub fn text_summary(&self) -> String {
+        let smart_room = &self.smart_room;
+        let mut lines = vec![
+            self.title(),
+            format!(
+                "Size: {}×{} screens, at {},{} on the area map",
+                self.width_screens, self.height_screens, self.map_x, self.map_y
+            ),
+            format!("States: {}", smart_room.states.len()),
+        ];
+        for (i, state) in smart_room.states.iter().enumerate() {
+            let condition = match state.condition {
+                smart_xml::StateCondition::Default => "default".into(),
+                smart_xml::StateCondition::Short(code) => {
+                    let args: Vec<_> = state
+                        .condition_args
+                        .iter()
+                        .map(|arg| arg.value.to_string())
+                        .collect();
+                    if args.is_empty() {
+                        format!("condition {code}")
+                    } else {
+                        format!("condition {code} ({})", args.join(", "))
+                    }
+                }
+            };
+            lines.push(format!(
+                "  State {i} ({condition}): GFXset {}, music {}, {} PLMs, {} enemies",
+                state.gfx_set,
+                state.music,
+                state.plms.len(),
+                state.enemies.enemy.len(),
+            ));
+            if state.fx1s.is_empty() {
+                lines.push("    FX1: none".into());
+            }
+            for fx in &state.fx1s {
+                let trigger = if fx.default {
+                    "default".into()
+                } else if let Some(door) = fx.fromdoor {
+                    format!("from door {door}")
+                } else {
+                    "unconditional".into()
+                };
+                let liquid = Liquid::from_fx_type(fx.type_.0)
+                    .map_or(String::new(), |liquid| format!(" ({liquid:?})"));
+                let surface = fx_surface_y(fx.surfacestart).map_or("no surface".into(), |_| {
+                    format!("surface {}", fx.surfacestart)
+                });
+                lines.push(format!(
+                    "    FX1 ({trigger}): type {}{liquid}, {surface}",
+                    fx.type_
+                ));
+            }
+        }
+        lines.push(format!("Doors: {}", smart_room.doors.len()));
+        for (i, door) in smart_room.doors.iter().enumerate() {
+            match door {
+                smart_xml::DoorEntry::Elevator => lines.push(format!("  Door {i}: elevator")),
+                smart_xml::DoorEntry::Door(door) => lines.push(format!(
+                    "  Door {i}: to [{:02X},{:02X}]",
+                    door.toroom.area.0, door.toroom.index.0
+                )),
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Replaces the contents of one of the room's doors.
+pub struct DoorEdit {
+    pub door: usize,
+    pub before: smart_xml::Door,
+    pub after: smart_xml::Door,
+}
+
+impl DoorEdit {
+    fn set(room: &mut Room, door: usize, value: &smart_xml::Door) {
+        if let Some(smart_xml::DoorEntry::Door(target)) = room.smart_room.doors.get_mut(door) {
+            value.clone_into(target);
+            room.mark_dirty();
+        }
+    }
+}
+
+impl Edit<Room> for DoorEdit {
+    fn apply(&self, room: &mut Room) {
+        Self::set(room, self.door, &self.after);
+    }
+
+    fn revert(&self, room: &mut Room) {
+        Self::set(room, self.door, &self.before);
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        let can_merge = self.door == next.door;
+        if can_merge {
+            next.after.clone_into(&mut self.after);
+        }
+        can_merge
+    }
+}
+
+/// Overwrites a rectangle of layer 1 blocks of a room state. Blocks outside of the room are
+/// skipped.
+pub struct BlocksEdit {
+    pub state: usize,
+    pub origin: [usize; 2],
+    pub before: BlockSelection,
+    pub after: BlockSelection,
+}
+
+impl BlocksEdit {
+    fn write(room: &mut Room, state: usize, [x0, y0]: [usize; 2], blocks: &BlockSelection) {
+        let Some(state) = room.smart_room.states.get_mut(state) else {
+            return;
+        };
+        let layer1 = &mut state.level_data.layer1;
+        for (i, entry) in blocks.data.iter().enumerate() {
+            let [x, y] = [x0 + i % blocks.width, y0 + i / blocks.width];
+            if let Some(block) = layer_block_mut(layer1, x, y) {
+                *block = entry.to_xml();
+            }
+        }
+        room.mark_dirty();
+    }
+}
+
+impl Edit<Room> for BlocksEdit {
+    fn apply(&self, room: &mut Room) {
+        Self::write(room, self.state, self.origin, &self.after);
+    }
+
+    fn revert(&self, room: &mut Room) {
+        Self::write(room, self.state, self.origin, &self.before);
+    }
+}
+
+/// Changes the BTS byte of a single layer 1 block of a room state.
+pub struct BtsEdit {
+    pub state: usize,
+    /// Coordinates of the screen containing the block.
+    pub screen: [usize; 2],
+    /// Position of the block within its screen.
+    pub tile_x: usize,
+    pub tile_y: usize,
+    pub old_value: u8,
+    pub new_value: u8,
+}
+
+impl BtsEdit {
+    /// Creates an edit of the block at `[x, y]`, in room block coordinates.
+    pub fn new(state: usize, [x, y]: [usize; 2], old_value: u8, new_value: u8) -> Self {
+        Self {
+            state,
+            screen: [x / SCREEN_BLOCKS, y / SCREEN_BLOCKS],
+            tile_x: x % SCREEN_BLOCKS,
+            tile_y: y % SCREEN_BLOCKS,
+            old_value,
+            new_value,
+        }
+    }
+
+    fn write(&self, room: &mut Room, value: u8) {
+        let Some(state) = room.smart_room.states.get_mut(self.state) else {
+            return;
+        };
+        let [screen_x, screen_y] = self.screen;
+        let x = screen_x * SCREEN_BLOCKS + self.tile_x;
+        let y = screen_y * SCREEN_BLOCKS + self.tile_y;
+        if let Some(bts) = layer_block_mut(&mut state.level_data.bts, x, y) {
+            bts.0 = value;
+            room.mark_dirty();
+        }
+    }
+}
+
+impl Edit<Room> for BtsEdit {
+    fn apply(&self, room: &mut Room) {
+        self.write(room, self.new_value);
+    }
+
+    fn revert(&self, room: &mut Room) {
+        self.write(room, self.old_value);
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        let can_merge = (self.state, self.screen, self.tile_x, self.tile_y)
+            == (next.state, next.screen, next.tile_x, next.tile_y);
+        if can_merge {
+            self.new_value = next.new_value;
+        }
+        can_merge
+    }
+}
+
+/// Header fields of a room that aren't part of its map placement.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct RoomProperties {
+    pub upscroll: HexU8,
+    pub dnscroll: HexU8,
+    /// Bits of [`SpecialGfxFlags`](crate::snes_constants::SpecialGfxFlags).
+    pub special_gfx: HexU8,
+}
+
+impl RoomProperties {
+    pub fn of(room: &Room) -> Self {
+        let smart_room = &room.smart_room;
+        Self {
+            upscroll: smart_room.upscroll,
+            dnscroll: smart_room.dnscroll,
+            special_gfx: smart_room.special_gfx,
+        }
+    }
+}
+
+/// Changes the header fields in [`RoomProperties`].
+pub struct RoomPropertiesEdit {
+    pub before: RoomProperties,
+    pub after: RoomProperties,
+}
+
+impl RoomPropertiesEdit {
+    fn set(room: &mut Room, properties: RoomProperties) {
+        let smart_room = &mut room.smart_room;
+        smart_room.upscroll = properties.upscroll;
+        smart_room.dnscroll = properties.dnscroll;
+        smart_room.special_gfx = properties.special_gfx;
+        room.mark_dirty();
+    }
+}
+
+impl Edit<Room> for RoomPropertiesEdit {
+    fn apply(&self, room: &mut Room) {
+        Self::set(room, self.after);
+    }
+
+    fn revert(&self, room: &mut Room) {
+        Self::set(room, self.before);
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        self.after = next.after;
+        true
+    }
+}
+
+/// Any undoable change made by the room editor.
+pub enum RoomEdit {
+    Door(DoorEdit),
+    Blocks(BlocksEdit),
+    Bts(BtsEdit),
+    Properties(RoomPropertiesEdit),
+}
+
+impl Edit<Room> for RoomEdit {
+    fn apply(&self, room: &mut Room) {
+        match self {
+            RoomEdit::Door(edit) => edit.apply(room),
+            RoomEdit::Blocks(edit) => edit.apply(room),
+            RoomEdit::Bts(edit) => edit.apply(room),
+            RoomEdit::Properties(edit) => edit.apply(room),
+        }
+    }
+
+    fn revert(&self, room: &mut Room) {
+        match self {
+            RoomEdit::Door(edit) => edit.revert(room),
+            RoomEdit::Blocks(edit) => edit.revert(room),
+            RoomEdit::Bts(edit) => edit.revert(room),
+            RoomEdit::Properties(edit) => edit.revert(room),
+        }
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (RoomEdit::Door(edit), RoomEdit::Door(next)) => edit.merge(next),
+            (RoomEdit::Blocks(edit), RoomEdit::Blocks(next)) => edit.merge(next),
+            (RoomEdit::Bts(edit), RoomEdit::Bts(next)) => edit.merge(next),
+            (RoomEdit::Properties(edit), RoomEdit::Properties(next)) => edit.merge(next),
+            _ => false,
         }
     }
 }
 
 pub fn load_from_smart(
     index: RoomIndex,
-    room_name: String,
-    _room: smart_xml::Room,
+    file_name: String,
+    source_path: PathBuf,
+    room: smart_xml::Room,
     handle: RoomRef,
 ) -> anyhow::Result<Room> {
+    let diagnostics = room
+        .validate()
+        .iter()
+        .map(|warning| {
+            tracing::warn!("Room {file_name}: {warning}");
+            room_warning_diagnostic(warning, handle)
+        })
+        .collect();
+
     Ok(Room {
         handle,
         index: Some(index),
-        name: room_name,
+        dirty: false,
+        name: file_name.to_title_case(),
+        file_name,
+        source_path,
+        map_x: room.x.into(),
+        map_y: room.y.into(),
+        width_screens: room.width.into(),
+        height_screens: room.height.into(),
+        smart_room: room,
+        diagnostics,
     })
 }
+
+fn room_warning_diagnostic(warning: &smart_xml::RoomWarning, room: RoomRef) -> Diagnostic {
+    use smart_xml::RoomWarning;
+
+    let location = match *warning {
+        RoomWarning::AmbiguousDoorCode { door, .. } => DiagnosticLocation::Door { room, door },
+        RoomWarning::PlmArgWithScrollData { state, .. }
+        | RoomWarning::ScrollConstWithData { state } => {
+            DiagnosticLocation::RoomState { room, state }
+        }
+    };
+    Diagnostic::warning(warning.to_string()).at(location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_summary() {
+        let smart_room =
+            smart_xml::tests::parse_room_xml(smart_xml::tests::SAMPLE_ROOM_XML).unwrap();
+        let room = load_from_smart(
+            (0x01, 0x1A),
+            "sample_room".into(),
+            "Export/Rooms/sample_room.xml".into(),
+            smart_room,
+            RoomRef::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            room.text_summary(),
+            "\
+[01,1A] Sample Room
+Size: 2×1 screens, at 10,3 on the area map
+States: 2
+  State 0 (condition $E612 ($0C)): GFXset $01, music $0500, 0 PLMs, 0 enemies
+    FX1: none
+  State 1 (default): GFXset $01, music $0000, 2 PLMs, 1 enemies
+    FX1 (default): type $00, no surface
+Doors: 3
+  Door 0: to [01,19]
+  Door 1: elevator
+  Door 2: to [01,1B]"
+        );
+    }
+
+    #[test]
+    fn test_room_warning_diagnostic() {
+        use smart_xml::RoomWarning;
+
+        let room = RoomRef::default();
+        let diagnostic = room_warning_diagnostic(
+            &RoomWarning::AmbiguousDoorCode {
+                door: 2,
+                fields: vec!["Code", "address"],
+            },
+            room,
+        );
+        assert_eq!(
+            diagnostic.message,
+            "Door 2: door code has multiple mutually exclusive fields: Code, address"
+        );
+        assert_eq!(
+            diagnostic.location,
+            Some(DiagnosticLocation::Door { room, door: 2 })
+        );
+
+        let diagnostic = room_warning_diagnostic(
+            &RoomWarning::PlmArgWithScrollData { state: 1, plm: 3 },
+            room,
+        );
+        assert_eq!(
+            diagnostic.location,
+            Some(DiagnosticLocation::RoomState { room, state: 1 })
+        );
+    }
+
+    #[test]
+    fn test_parse_room_index() {
+        assert_eq!(parse_room_index("4,1F"), Some((0x04, 0x1F)));
+        assert_eq!(parse_room_index("$04 $1F"), Some((0x04, 0x1F)));
+        assert_eq!(parse_room_index(" 02 , 3a "), Some((0x02, 0x3A)));
+        assert_eq!(parse_room_index("04"), None);
+        assert_eq!(parse_room_index("04,1F,00"), None);
+        assert_eq!(parse_room_index("04,XY"), None);
+    }
+
+    #[test]
+    fn test_block_type_encoding() {
+        assert_eq!(LevelDataEntry(0x0000).typed_block_type(), BlockType::Air);
+        assert_eq!(LevelDataEntry(0x8123).typed_block_type(), BlockType::Solid);
+        assert_eq!(LevelDataEntry(0x9C00).typed_block_type(), BlockType::Door);
+        assert_eq!(
+            LevelDataEntry(0xF3FF).typed_block_type(),
+            BlockType::Bombable
+        );
+    }
+
+    #[test]
+    fn test_level_data_entry_fields() {
+        let decode = |value: u16| {
+            let entry = LevelDataEntry(value);
+            (
+                entry.block_id(),
+                entry.h_flip(),
+                entry.v_flip(),
+                entry.typed_block_type(),
+            )
+        };
+        assert_eq!(decode(0x8123), (0x123, false, false, BlockType::Solid));
+        assert_eq!(decode(0x8523), (0x123, true, false, BlockType::Solid));
+        assert_eq!(decode(0x8923), (0x123, false, true, BlockType::Solid));
+        assert_eq!(decode(0x1FFF), (0x3FF, true, true, BlockType::Slope));
+        // Flips don't leak into the block type, and vice versa
+        assert_eq!(decode(0x0C00), (0x000, true, true, BlockType::Air));
+        assert_eq!(decode(0xF000), (0x000, false, false, BlockType::Bombable));
+
+        let entry = LevelDataEntry(0x9055).with_flips(true, false);
+        assert_eq!(entry, LevelDataEntry(0x9455));
+        assert_eq!(entry.with_flips(false, true), LevelDataEntry(0x9855));
+    }
+
+    #[test]
+    fn test_level_data_entry_for_tile() {
+        assert_eq!(LevelDataEntry::try_for_tile(0), Ok(LevelDataEntry(0x0000)));
+        assert_eq!(
+            LevelDataEntry::try_for_tile(0x3FF),
+            Ok(LevelDataEntry(0x03FF))
+        );
+        let overflow = LevelDataEntry::try_for_tile(0x400).unwrap_err();
+        assert_eq!((overflow.value, overflow.limit), (0x400, 0x400));
+    }
+
+    #[test]
+    fn test_block_selection_flips() {
+        let selection = BlockSelection {
+            width: 2,
+            height: 2,
+            data: [1, 2, 3, 4].map(LevelDataEntry).to_vec(),
+        };
+
+        let flipped = selection.flipped_h();
+        assert_eq!(
+            flipped
+                .data
+                .iter()
+                .map(|e| e.block_id())
+                .collect::<Vec<_>>(),
+            [2, 1, 4, 3]
+        );
+        assert!(flipped.data.iter().all(|e| e.h_flip() && !e.v_flip()));
+        assert_eq!(flipped.flipped_h(), selection);
+
+        let flipped = selection.flipped_v();
+        assert_eq!(
+            flipped
+                .data
+                .iter()
+                .map(|e| e.block_id())
+                .collect::<Vec<_>>(),
+            [3, 4, 1, 2]
+        );
+        assert!(flipped.data.iter().all(|e| !e.h_flip() && e.v_flip()));
+        assert_eq!(flipped.flipped_v(), selection);
+    }
+
+    #[test]
+    fn test_bts_edit_merge() {
+        let mut edit = BtsEdit::new(0, [17, 3], 0x00, 0x01);
+        assert_eq!((edit.screen, edit.tile_x, edit.tile_y), ([1, 0], 1, 3));
+        assert!(edit.merge(&BtsEdit::new(0, [17, 3], 0x01, 0x02)));
+        assert_eq!((edit.old_value, edit.new_value), (0x00, 0x02));
+        assert!(!edit.merge(&BtsEdit::new(0, [18, 3], 0x00, 0x05)));
+        assert!(!edit.merge(&BtsEdit::new(1, [17, 3], 0x00, 0x05)));
+    }
+
+    #[test]
+    fn test_fx_liquid() {
+        assert_eq!(Liquid::from_fx_type(0x06), Some(Liquid::Water));
+        assert_eq!(Liquid::from_fx_type(0x0A), None);
+        assert_eq!(fx_surface_y(HexU16(0x00A8)), Some(0xA8));
+        assert_eq!(fx_surface_y(HexU16(0xFFFF)), None);
+    }
+}
@@ -1,16 +1,35 @@
-use crate::hex_types::{HexU8, HexU16, HexU24, HexValue};
+use crate::door_code;
+use crate::hex_types::{HexI16, HexU8, HexU16, HexU24, HexValue, SizedHexValue};
+use crate::project_source::ProjectSource;
 use anyhow::{Context, Result, anyhow};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::de::{DeserializeOwned, IntoDeserializer};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
+use std::fmt::Display;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{fs, io};
+use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
+macro_rules! make_list_wrapper {
+    ($fn_name:ident, $type:ty, $el_name:literal) => {
+        fn $fn_name<S: Serializer>(children: &$type, serializer: S) -> Result<S::Ok, S::Error> {
+            #[derive(Serialize)]
+            struct Holder<'a> {
+                #[serde(rename = $el_name)]
+                children: &'a $type,
+            }
+            Holder { children }.serialize(serializer)
+        }
+    };
+}
+
 macro_rules! make_list_unwrapper {
     ($fn_name:ident, $type:ty, $el_name:literal) => {
         fn $fn_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$type, D::Error> {
@@ -33,7 +52,21 @@ fn split_xml_whitespace<'de, T: DeserializeOwned, D: Deserializer<'de>>(
         .collect()
 }
 
-#[derive(Deserialize, Debug)]
+fn join_xml_whitespace<T: Display, S: Serializer>(
+    data: &[T],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut s = String::new();
+    for (i, x) in data.iter().enumerate() {
+        if i != 0 {
+            s.push(' ');
+        }
+        s += &x.to_string();
+    }
+    serializer.serialize_str(&s)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SaveInDoor {
     #[serde(rename = "@roomarea")]
     pub room_area: HexU8,
@@ -43,7 +76,7 @@ pub struct SaveInDoor {
     pub door_index: HexU8,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SaveRoom {
     pub saveindex: HexU8,
     pub indoor: SaveInDoor,
@@ -54,7 +87,7 @@ pub struct SaveRoom {
     pub samusy: HexU16,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ToRoom {
     #[serde(rename = "@area")]
     pub area: HexU8,
@@ -62,26 +95,53 @@ pub struct ToRoom {
     pub index: HexU8,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "RawCodeOp")]
 pub struct CodeOp {
     #[serde(rename = "@OP")]
     pub op: HexU8,
-    #[serde(rename = "@ARG")]
+    /// Sized by the op's addressing mode where it has a fixed width and the value fits in it, so
+    /// e.g. an `STA` address written as `05` stays a word on a round-trip.
+    #[serde(rename = "@ARG", skip_serializing_if = "Option::is_none")]
     pub arg: Option<HexValue>,
 }
 
-#[derive(Deserialize, Debug)]
+/// `CodeOp` as written in the file, before its argument is sized according to the op.
+#[derive(Deserialize)]
+struct RawCodeOp {
+    #[serde(rename = "@OP")]
+    op: HexU8,
+    #[serde(rename = "@ARG")]
+    arg: Option<String>,
+}
+
+impl TryFrom<RawCodeOp> for CodeOp {
+    type Error = std::num::ParseIntError;
+
+    fn try_from(raw: RawCodeOp) -> std::result::Result<Self, Self::Error> {
+        let arg = raw
+            .arg
+            .map(|arg| match door_code::operand_size(raw.op) {
+                Some(size) => HexValue::from_str_size_hint(&arg, size),
+                None => HexValue::from_str(&arg),
+            })
+            .transpose()?;
+        Ok(CodeOp { op: raw.op, arg })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DoorCode {
     // These three are mutually exclusive, but can't use an enum because Code repeats
     #[serde(rename = "Code", default)]
     pub ops: Vec<CodeOp>,
-    #[serde(rename = "ScrollData")]
+    #[serde(rename = "ScrollData", skip_serializing_if = "Option::is_none")]
     pub scroll_data: Option<ScrollDataChange>,
-    #[serde(rename = "$text")]
+    #[serde(rename = "$text", skip_serializing_if = "Option::is_none")]
     pub address: Option<HexU16>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Door {
     pub toroom: ToRoom,
     pub bitflag: HexU8,
@@ -94,20 +154,24 @@ pub struct Door {
     pub doorcode: DoorCode,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Fx1 {
-    #[serde(rename = "@default", default)]
+    #[serde(
+        rename = "@default",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
     pub default: bool,
-    #[serde(rename = "@roomarea")]
+    #[serde(rename = "@roomarea", skip_serializing_if = "Option::is_none")]
     pub roomarea: Option<HexU8>,
-    #[serde(rename = "@roomindex")]
+    #[serde(rename = "@roomindex", skip_serializing_if = "Option::is_none")]
     pub roomindex: Option<HexU8>,
-    #[serde(rename = "@fromdoor")]
+    #[serde(rename = "@fromdoor", skip_serializing_if = "Option::is_none")]
     pub fromdoor: Option<HexU8>,
 
     pub surfacestart: HexU16,
     pub surfacenew: HexU16,
-    pub surfacespeed: HexU16,
+    pub surfacespeed: HexI16,
     pub surfacedelay: HexU8,
     #[serde(rename = "type")]
     pub type_: HexU8,
@@ -122,7 +186,7 @@ pub struct Fx1 {
     pub paletteblend: HexU8,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Enemy {
     #[serde(rename = "ID")]
     pub id: HexU16,
@@ -137,7 +201,7 @@ pub struct Enemy {
     pub speed2: HexU16,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct EnemiesList {
     #[serde(rename = "@killcount")]
     pub kill_count: HexU8,
@@ -145,30 +209,75 @@ pub struct EnemiesList {
     pub enemy: Vec<Enemy>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct EnemyType {
     #[serde(rename = "GFX")]
     pub gfx: HexU16,
     pub palette: HexU16,
 }
 
-#[derive(Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub enum LayerType {
     Layer2,
     #[serde(rename = "BGData")]
     BgData,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ScrollData {
     // These are be mutually exclusive
-    #[serde(rename = "@const")]
+    #[serde(rename = "@const", skip_serializing_if = "Option::is_none")]
     pub const_: Option<HexU16>,
-    #[serde(default, rename = "$text", deserialize_with = "split_xml_whitespace")]
+    #[serde(
+        default,
+        rename = "$text",
+        deserialize_with = "split_xml_whitespace",
+        serialize_with = "join_xml_whitespace"
+    )]
     pub data: Vec<HexU8>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Scroll data of a room state, with `ScrollData`'s representation ambiguities resolved.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ScrollKind<'a> {
+    /// Pointer to scroll data shared with other rooms, or one of the special vanilla values.
+    Const(HexU16),
+    /// Scroll value for each screen of the room, in row-major order.
+    PerScreen(&'a [HexU8]),
+    /// Neither a constant nor any per-screen data is present.
+    Empty,
+}
+
+impl ScrollData {
+    /// Returns the scroll data in use. If both a constant and per-screen data are present, which
+    /// `Room::validate` warns about, the constant takes precedence.
+    pub fn resolve(&self) -> ScrollKind<'_> {
+        match (self.const_, self.data.as_slice()) {
+            (Some(const_), _) => ScrollKind::Const(const_),
+            (None, []) => ScrollKind::Empty,
+            (None, data) => ScrollKind::PerScreen(data),
+        }
+    }
+
+    fn is_conflicting(&self) -> bool {
+        self.const_.is_some() && !self.data.is_empty()
+    }
+}
+
+impl ScrollKind<'_> {
+    /// Scroll value of the screen with row-major index `screen`. Constants below $8000 set every
+    /// screen to their low byte, while others point to scroll data that isn't part of the export,
+    /// so they can't be resolved.
+    pub fn screen_value(&self, screen: usize) -> Option<u8> {
+        match *self {
+            ScrollKind::Const(HexU16(value)) => (value < 0x8000).then_some(value as u8),
+            ScrollKind::PerScreen(data) => data.get(screen).map(|&HexU8(value)| value),
+            ScrollKind::Empty => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ScrollDataChangeEntry {
     Change {
         #[serde(rename = "@screen")]
@@ -178,21 +287,22 @@ pub enum ScrollDataChangeEntry {
     },
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ScrollDataChange {
     #[serde(rename = "$value")]
     pub entries: Vec<ScrollDataChangeEntry>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Plm {
     #[serde(rename = "type")]
     pub type_: HexU16,
     pub x: HexU8,
     pub y: HexU8,
     // Mutually exclusive(?) with scroll_data
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub arg: Option<HexU16>,
-    #[serde(rename = "ScrollData")]
+    #[serde(rename = "ScrollData", skip_serializing_if = "Option::is_none")]
     pub scroll_data: Option<ScrollDataChange>,
 }
 
@@ -202,6 +312,18 @@ pub enum DataOrAddress {
     Address(HexU24),
 }
 
+impl Serialize for DataOrAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            DataOrAddress::Data(vals) => join_xml_whitespace(vals, serializer),
+            DataOrAddress::Address(addr) => addr.serialize(serializer),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for DataOrAddress {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -220,7 +342,7 @@ impl<'de> Deserialize<'de> for DataOrAddress {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum DecompSection {
     #[serde(rename = "GFX")]
     Gfx,
@@ -231,7 +353,7 @@ pub enum DecompSection {
     Tiles3,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum BgDataType {
     Copy,
@@ -242,21 +364,25 @@ pub enum BgDataType {
     DdbCopy,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct BgDataEntry {
     #[serde(rename = "@Type")]
     pub type_: BgDataType,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<DataOrAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dest: Option<HexU16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<HexU16>,
-    #[serde(rename = "Section")]
+    #[serde(rename = "Section", skip_serializing_if = "Option::is_none")]
     pub section: Option<DecompSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ddb: Option<HexU16>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Screen<T> {
     #[serde(rename = "@X")]
     pub x: HexU8,
@@ -264,19 +390,23 @@ pub struct Screen<T> {
     pub y: HexU8,
     #[serde(
         rename = "$text",
-        bound(deserialize = "T: DeserializeOwned"),
-        deserialize_with = "split_xml_whitespace"
+        bound(deserialize = "T: DeserializeOwned", serialize = "T: Display"),
+        deserialize_with = "split_xml_whitespace",
+        serialize_with = "join_xml_whitespace"
     )]
     pub data: Vec<T>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct LevelDataLayer<T> {
-    #[serde(rename = "Screen", bound(deserialize = "T: DeserializeOwned"))]
+    #[serde(
+        rename = "Screen",
+        bound(deserialize = "T: DeserializeOwned", serialize = "T: Display")
+    )]
     pub screens: Vec<Screen<T>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct LevelData {
     #[serde(rename = "@Width")]
     pub width: HexU8,
@@ -287,16 +417,44 @@ pub struct LevelData {
     pub layer1: LevelDataLayer<HexU16>,
     #[serde(rename = "BTS")]
     pub bts: LevelDataLayer<HexU8>,
-    #[serde(rename = "Layer2")]
+    #[serde(rename = "Layer2", skip_serializing_if = "Option::is_none")]
     pub layer2: Option<LevelDataLayer<HexU16>>,
 }
 
+impl LevelData {
+    fn empty() -> Self {
+        LevelData {
+            width: HexU8(0),
+            height: HexU8(0),
+            layer1: LevelDataLayer {
+                screens: Vec::new(),
+            },
+            bts: LevelDataLayer {
+                screens: Vec::new(),
+            },
+            layer2: None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum StateCondition {
     Default,
     Short(HexU16),
 }
 
+impl Serialize for StateCondition {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            StateCondition::Default => serializer.serialize_str("default"),
+            StateCondition::Short(x) => x.serialize(serializer),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for StateCondition {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -313,38 +471,51 @@ impl<'de> Deserialize<'de> for StateCondition {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct StateConditionArg {
     // Arg type information isn't available during parsing so the size of this parameter is unknown.
-    // This might be "byte" (u8), "short" (u16), or "long" (u24), although vanilla only uses byte
-    // arguments.
-    #[serde(rename = "$text")]
-    pub value: HexU24,
+    // This might be "byte" (u8), "short" (u16), or "long" (u24), but vanilla only uses byte
+    // arguments, so that's the width they're kept at unless they're too large for one.
+    #[serde(rename = "$text", with = "SizedHexValue::<1>")]
+    pub value: HexValue,
     // TODO: Subversion uses an arg type called "Door" which uses XML attributes
 }
 
 make_list_unwrapper!(unwrap_fx1_list, Vec<Fx1>, "FX1");
+make_list_wrapper!(wrap_fx1_list, Vec<Fx1>, "FX1");
 make_list_unwrapper!(unwrap_enemy_type_list, Vec<EnemyType>, "Enemy");
+make_list_wrapper!(wrap_enemy_type_list, Vec<EnemyType>, "Enemy");
 make_list_unwrapper!(unwrap_plm_list, Vec<Plm>, "PLM");
+make_list_wrapper!(wrap_plm_list, Vec<Plm>, "PLM");
 make_list_unwrapper!(unwrap_bg_data_list, Vec<BgDataEntry>, "Data");
+make_list_wrapper!(wrap_bg_data_list, Vec<BgDataEntry>, "Data");
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct RoomState {
     #[serde(rename = "@condition")]
     pub condition: StateCondition,
     #[serde(rename = "Arg", default)]
     pub condition_args: Vec<StateConditionArg>,
-    #[serde(rename = "LevelData")]
+    /// Read separately by [`parse_room`], streaming it instead of buffering it with the rest.
+    #[serde(rename = "LevelData", skip_deserializing, default = "LevelData::empty")]
     pub level_data: LevelData,
 
     #[serde(rename = "GFXset")]
     pub gfx_set: HexU8,
     pub music: HexU16,
-    #[serde(rename = "FX1s", deserialize_with = "unwrap_fx1_list")]
+    #[serde(
+        rename = "FX1s",
+        deserialize_with = "unwrap_fx1_list",
+        serialize_with = "wrap_fx1_list"
+    )]
     pub fx1s: Vec<Fx1>,
     #[serde(rename = "Enemies")]
     pub enemies: EnemiesList,
-    #[serde(rename = "EnemyTypes", deserialize_with = "unwrap_enemy_type_list")]
+    #[serde(
+        rename = "EnemyTypes",
+        deserialize_with = "unwrap_enemy_type_list",
+        serialize_with = "wrap_enemy_type_list"
+    )]
     pub enemy_types: Vec<EnemyType>,
 
     pub layer2_type: LayerType,
@@ -356,24 +527,35 @@ pub struct RoomState {
     #[serde(rename = "FX2")]
     pub fx2: HexU16,
 
-    #[serde(rename = "PLMs", deserialize_with = "unwrap_plm_list")]
+    #[serde(
+        rename = "PLMs",
+        deserialize_with = "unwrap_plm_list",
+        serialize_with = "wrap_plm_list"
+    )]
     pub plms: Vec<Plm>,
-    #[serde(rename = "BGData", deserialize_with = "unwrap_bg_data_list")]
+    #[serde(
+        rename = "BGData",
+        deserialize_with = "unwrap_bg_data_list",
+        serialize_with = "wrap_bg_data_list"
+    )]
     pub bg_data: Vec<BgDataEntry>,
     pub layer1_2: HexU16,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum DoorEntry {
     Elevator,
     Door(Door),
 }
 
 make_list_unwrapper!(unwrap_saves_list, Vec<SaveRoom>, "SaveRoom");
+make_list_wrapper!(wrap_saves_list, Vec<SaveRoom>, "SaveRoom");
 make_list_unwrapper!(unwrap_door_entry_list, Vec<DoorEntry>, "$value");
+make_list_wrapper!(wrap_door_entry_list, Vec<DoorEntry>, "$value");
 make_list_unwrapper!(unwrap_room_state_list, Vec<RoomState>, "State");
+make_list_wrapper!(wrap_room_state_list, Vec<RoomState>, "State");
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Room {
     pub index: HexU8,
     pub area: HexU8,
@@ -386,15 +568,112 @@ pub struct Room {
     #[serde(rename = "specialGFX")]
     pub special_gfx: HexU8, // bitflags
 
-    #[serde(rename = "Saves", deserialize_with = "unwrap_saves_list")]
+    #[serde(
+        rename = "Saves",
+        deserialize_with = "unwrap_saves_list",
+        serialize_with = "wrap_saves_list"
+    )]
     pub saves: Vec<SaveRoom>,
-    #[serde(rename = "Doors", deserialize_with = "unwrap_door_entry_list")]
+    #[serde(
+        rename = "Doors",
+        deserialize_with = "unwrap_door_entry_list",
+        serialize_with = "wrap_door_entry_list"
+    )]
     pub doors: Vec<DoorEntry>,
-    #[serde(rename = "States", deserialize_with = "unwrap_room_state_list")]
+    #[serde(
+        rename = "States",
+        deserialize_with = "unwrap_room_state_list",
+        serialize_with = "wrap_room_state_list"
+    )]
     pub states: Vec<RoomState>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Inconsistency found in parsed room data, such as fields that are supposed to be mutually
+/// exclusive being present at the same time.
+#[derive(Debug, Eq, PartialEq)]
+pub enum RoomWarning {
+    /// More than one of a door code's ops, scroll data and address are present.
+    AmbiguousDoorCode {
+        door: usize,
+        fields: Vec<&'static str>,
+    },
+    /// A PLM has both an argument and scroll data.
+    PlmArgWithScrollData { state: usize, plm: usize },
+    /// A room state's scroll data has both a constant and per-screen data.
+    ScrollConstWithData { state: usize },
+}
+
+impl Display for RoomWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RoomWarning::AmbiguousDoorCode { door, fields } => write!(
+                f,
+                "Door {door}: door code has multiple mutually exclusive fields: {}",
+                fields.join(", ")
+            ),
+            RoomWarning::PlmArgWithScrollData { state, plm } => write!(
+                f,
+                "State {state}, PLM {plm}: has both an arg and scroll data"
+            ),
+            RoomWarning::ScrollConstWithData { state } => write!(
+                f,
+                "State {state}: scroll data has both a constant and per-screen data"
+            ),
+        }
+    }
+}
+
+impl DoorCode {
+    /// Names of the mutually exclusive door code fields that are present.
+    fn present_fields(&self) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if !self.ops.is_empty() {
+            fields.push("Code");
+        }
+        if self.scroll_data.is_some() {
+            fields.push("ScrollData");
+        }
+        if self.address.is_some() {
+            fields.push("address");
+        }
+        fields
+    }
+}
+
+impl Room {
+    /// Checks constraints that can't be expressed in the deserialized types.
+    pub fn validate(&self) -> Vec<RoomWarning> {
+        let mut warnings = Vec::new();
+        for (door_i, door) in self.doors.iter().enumerate() {
+            let DoorEntry::Door(door) = door else {
+                continue;
+            };
+            let fields = door.doorcode.present_fields();
+            if fields.len() > 1 {
+                warnings.push(RoomWarning::AmbiguousDoorCode {
+                    door: door_i,
+                    fields,
+                });
+            }
+        }
+        for (state_i, state) in self.states.iter().enumerate() {
+            if state.scroll_data.is_conflicting() {
+                warnings.push(RoomWarning::ScrollConstWithData { state: state_i });
+            }
+            for (plm_i, plm) in state.plms.iter().enumerate() {
+                if plm.arg.is_some() && plm.scroll_data.is_some() {
+                    warnings.push(RoomWarning::PlmArgWithScrollData {
+                        state: state_i,
+                        plm: plm_i,
+                    });
+                }
+            }
+        }
+        warnings
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct Label {
     pub x: HexU16,
@@ -402,40 +681,69 @@ pub struct Label {
     pub gfx: HexU16,
 }
 make_list_unwrapper!(unwrap_label_list, Vec<Label>, "Label");
+make_list_wrapper!(wrap_label_list, Vec<Label>, "Label");
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct Icon {
     pub x: HexU16,
     pub y: HexU16,
 }
 make_list_unwrapper!(unwrap_icon_list, Vec<Icon>, "Icon");
+make_list_wrapper!(wrap_icon_list, Vec<Icon>, "Icon");
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct Map {
-    #[serde(deserialize_with = "split_xml_whitespace")]
+    #[serde(
+        deserialize_with = "split_xml_whitespace",
+        serialize_with = "join_xml_whitespace"
+    )]
     pub tile_data: Vec<HexU16>,
-    #[serde(deserialize_with = "split_xml_whitespace")]
+    #[serde(
+        deserialize_with = "split_xml_whitespace",
+        serialize_with = "join_xml_whitespace"
+    )]
     pub area_name: Vec<HexU16>,
-    #[serde(deserialize_with = "split_xml_whitespace")]
+    #[serde(
+        deserialize_with = "split_xml_whitespace",
+        serialize_with = "join_xml_whitespace"
+    )]
     pub map_station_data: Vec<HexU8>,
 
-    #[serde(deserialize_with = "unwrap_label_list")]
+    #[serde(
+        deserialize_with = "unwrap_label_list",
+        serialize_with = "wrap_label_list"
+    )]
     pub area_labels: Vec<Label>,
-    #[serde(deserialize_with = "unwrap_icon_list")]
+    #[serde(
+        deserialize_with = "unwrap_icon_list",
+        serialize_with = "wrap_icon_list"
+    )]
     pub boss_icons: Vec<Icon>,
-    #[serde(deserialize_with = "unwrap_icon_list")]
+    #[serde(
+        deserialize_with = "unwrap_icon_list",
+        serialize_with = "wrap_icon_list"
+    )]
     pub missile_icons: Vec<Icon>,
-    #[serde(deserialize_with = "unwrap_icon_list")]
+    #[serde(
+        deserialize_with = "unwrap_icon_list",
+        serialize_with = "wrap_icon_list"
+    )]
     pub energy_icons: Vec<Icon>,
-    #[serde(deserialize_with = "unwrap_icon_list")]
+    #[serde(
+        deserialize_with = "unwrap_icon_list",
+        serialize_with = "wrap_icon_list"
+    )]
     pub map_icons: Vec<Icon>,
-    #[serde(deserialize_with = "unwrap_icon_list")]
+    #[serde(
+        deserialize_with = "unwrap_icon_list",
+        serialize_with = "wrap_icon_list"
+    )]
     pub save_icons: Vec<Icon>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct TilesetMetadata {
     pub name: String,
@@ -447,6 +755,12 @@ pub struct Tileset {
     pub gfx: Vec<u8>,
     pub tiletable: Vec<u16>,
     pub palette: Vec<u16>, // Empty for CRE
+
+    /// Files the data was read from, relative to the project root. `palette_path` is the palette
+    /// file that was found, or where a new one would be created if there's none.
+    pub gfx_path: PathBuf,
+    pub tiletable_path: PathBuf,
+    pub palette_path: PathBuf,
 }
 
 pub struct TilesetsInfo {
@@ -454,59 +768,290 @@ pub struct TilesetsInfo {
     pub sce: BTreeMap<u8, Tileset>,
 }
 
-#[tracing::instrument]
-fn read_xml_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+#[tracing::instrument(skip(source))]
+fn read_xml_file<T: DeserializeOwned>(source: &dyn ProjectSource, path: &Path) -> Result<T> {
     debug!("parsing file");
-    let file = BufReader::new(File::open(path)?);
+    let file = BufReader::new(source.open(path)?);
     let parsed = quick_xml::de::from_reader(file)?;
     Ok(parsed)
 }
 
-#[tracing::instrument]
-pub fn load_project_rooms(project_path: &Path) -> Result<BTreeMap<(u8, u8), (String, Room)>> {
-    use std::collections::btree_map::Entry;
+/// Layer of a `<LevelData>` element that `<Screen>`s are being read into.
+#[derive(Copy, Clone)]
+enum LevelDataLayerKind {
+    Layer1,
+    Bts,
+    Layer2,
+}
 
-    let mut rooms = BTreeMap::new();
+fn parse_hex_attribute<T: FromStr>(start: &BytesStart, name: &str) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let attribute = start
+        .try_get_attribute(name)?
+        .ok_or_else(|| anyhow!("missing {name} attribute"))?;
+    Ok(attribute.unescape_value()?.parse()?)
+}
 
-    for entry in fs::read_dir(project_path.join("Export/Rooms"))
-        .context("listing Export/Rooms/ directory")?
-    {
-        let entry = entry.context("reading Export/Rooms entry")?;
-        let path = entry.path();
-        if path.extension() != Some("xml".as_ref()) || entry.file_type()?.is_dir() {
-            continue;
+fn parse_screen<T: FromStr>(x: HexU8, y: HexU8, text: &str) -> Result<Screen<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let data = text
+        .split_ascii_whitespace()
+        .map(|s| s.parse())
+        .collect::<Result<_, _>>()?;
+    Ok(Screen { x, y, data })
+}
+
+/// Parses a `<LevelData>` element with a pull parser, converting each `<Screen>` as soon as its
+/// text has been read instead of buffering the whole element first. Events before the element's
+/// start tag are skipped, so `reader` can be positioned anywhere before it.
+fn parse_level_data_streaming<R: BufRead>(reader: &mut Reader<R>) -> Result<LevelData> {
+    let mut buf = Vec::new();
+    let mut level_data = loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) if start.name().as_ref() == b"LevelData" => {
+                break LevelData {
+                    width: parse_hex_attribute(&start, "Width")?,
+                    height: parse_hex_attribute(&start, "Height")?,
+                    ..LevelData::empty()
+                };
+            }
+            Event::Eof => return Err(anyhow!("no LevelData element found")),
+            _ => {}
         }
+    };
+
+    let mut layer = None;
+    // Position and text of the `<Screen>` being read
+    let mut screen: Option<(HexU8, HexU8, String)> = None;
+    let push_screen = |level_data: &mut LevelData,
+                       layer: Option<LevelDataLayerKind>,
+                       (x, y, text): (HexU8, HexU8, String)|
+     -> Result<()> {
+        match layer {
+            Some(LevelDataLayerKind::Layer1) => {
+                level_data.layer1.screens.push(parse_screen(x, y, &text)?);
+            }
+            Some(LevelDataLayerKind::Bts) => {
+                level_data.bts.screens.push(parse_screen(x, y, &text)?);
+            }
+            Some(LevelDataLayerKind::Layer2) => {
+                let layer2 = level_data.layer2.get_or_insert_with(|| LevelDataLayer {
+                    screens: Vec::new(),
+                });
+                layer2.screens.push(parse_screen(x, y, &text)?);
+            }
+            None => return Err(anyhow!("Screen outside of a LevelData layer")),
+        }
+        Ok(())
+    };
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => match start.name().as_ref() {
+                b"Layer1" => layer = Some(LevelDataLayerKind::Layer1),
+                b"BTS" => layer = Some(LevelDataLayerKind::Bts),
+                b"Layer2" => {
+                    layer = Some(LevelDataLayerKind::Layer2);
+                    level_data.layer2 = Some(LevelDataLayer {
+                        screens: Vec::new(),
+                    });
+                }
+                b"Screen" => {
+                    let x = parse_hex_attribute(&start, "X")?;
+                    let y = parse_hex_attribute(&start, "Y")?;
+                    screen = Some((x, y, String::new()));
+                }
+                name => {
+                    let name = String::from_utf8_lossy(name);
+                    return Err(anyhow!("unexpected {name} element in LevelData"));
+                }
+            },
+            Event::Empty(start) => match start.name().as_ref() {
+                b"Layer1" | b"BTS" => {}
+                b"Layer2" => {
+                    level_data.layer2 = Some(LevelDataLayer {
+                        screens: Vec::new(),
+                    })
+                }
+                b"Screen" => {
+                    let x = parse_hex_attribute(&start, "X")?;
+                    let y = parse_hex_attribute(&start, "Y")?;
+                    push_screen(&mut level_data, layer, (x, y, String::new()))?;
+                }
+                name => {
+                    let name = String::from_utf8_lossy(name);
+                    return Err(anyhow!("unexpected {name} element in LevelData"));
+                }
+            },
+            Event::Text(text) => {
+                if let Some((_, _, screen_text)) = &mut screen {
+                    screen_text.push_str(&text.decode()?);
+                }
+            }
+            Event::End(end) => match end.name().as_ref() {
+                b"Screen" => {
+                    if let Some(screen) = screen.take() {
+                        push_screen(&mut level_data, layer, screen)?;
+                    }
+                }
+                b"Layer1" | b"BTS" | b"Layer2" => layer = None,
+                _ => break,
+            },
+            Event::Eof => return Err(anyhow!("unexpected end of file inside LevelData")),
+            _ => {}
+        }
+    }
+    Ok(level_data)
+}
+
+/// Parses a room file, calling `open` once for each of two passes over it: one deserializing
+/// everything but the level data, and another streaming the level data of each state with
+/// [`parse_level_data_streaming`], so that it doesn't need to be buffered whole.
+fn parse_room<R: BufRead>(mut open: impl FnMut() -> Result<R>) -> Result<Room> {
+    let mut room: Room = quick_xml::de::from_reader(open()?)?;
+    let mut reader = Reader::from_reader(open()?);
+    for state in &mut room.states {
+        state.level_data = parse_level_data_streaming(&mut reader)?;
+    }
+    Ok(room)
+}
+
+#[tracing::instrument(skip(source))]
+fn read_room_file(source: &dyn ProjectSource, path: &Path) -> Result<Room> {
+    debug!("parsing room file");
+    parse_room(|| Ok(BufReader::new(source.open(path)?)))
+}
+
+/// How problems that don't prevent the rest of the project from being used are handled.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum LoadMode {
+    /// Fail the whole load.
+    Strict,
+    /// Skip the offending data and report it alongside the result.
+    Lenient,
+}
+
+/// Room file skipped because an earlier one already used its `(area, index)` id.
+#[derive(Debug, Eq, PartialEq)]
+pub struct DuplicateRoom {
+    pub id: (u8, u8),
+    pub kept_name: String,
+    pub skipped_name: String,
+}
+
+impl Display for DuplicateRoom {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (area_index, room_index) = self.id;
+        write!(
+            f,
+            "Duplicate rooms with id ({},{}): \"{}\" and \"{}\"",
+            HexU8(area_index),
+            HexU8(room_index),
+            self.kept_name,
+            self.skipped_name
+        )
+    }
+}
+
+/// A room loaded from `Export/Rooms`, along with the file it was read from.
+pub struct RoomFile {
+    /// File name of the room, without the extension.
+    pub name: String,
+    /// Path of the file, relative to the project root.
+    pub path: PathBuf,
+    pub room: Room,
+}
+
+#[derive(Default)]
+pub struct RoomsInfo {
+    pub rooms: BTreeMap<(u8, u8), RoomFile>,
+    /// Rooms left out of `rooms` when loading in [`LoadMode::Lenient`].
+    pub duplicates: Vec<DuplicateRoom>,
+}
 
-        let room_name = path.file_stem().unwrap().to_string_lossy().into_owned();
-        let room: Room = read_xml_file(&path)?;
+impl RoomsInfo {
+    fn insert(&mut self, room_file: RoomFile, mode: LoadMode) -> Result<()> {
+        use std::collections::btree_map::Entry;
 
-        match rooms.entry((room.area.into(), room.index.into())) {
+        let room = &room_file.room;
+        match self.rooms.entry((room.area.into(), room.index.into())) {
             Entry::Vacant(e) => {
-                e.insert((room_name, room));
+                e.insert(room_file);
             }
             Entry::Occupied(e) => {
-                let &(area_index, room_index) = e.key();
-                let old_name = &e.get().0;
-                return Err(anyhow!(
-                    "Duplicate rooms with id ({},{}): \"{old_name}\" and \"{room_name}\"",
-                    HexU8(area_index),
-                    HexU8(room_index)
-                ));
+                let duplicate = DuplicateRoom {
+                    id: *e.key(),
+                    kept_name: e.get().name.clone(),
+                    skipped_name: room_file.name,
+                };
+                match mode {
+                    LoadMode::Strict => return Err(anyhow!("{duplicate}")),
+                    LoadMode::Lenient => {
+                        warn!("{duplicate}, skipping the latter");
+                        self.duplicates.push(duplicate);
+                    }
+                }
             }
         }
+        Ok(())
+    }
+}
+
+const ROOMS_DIR: &str = "Export/Rooms";
+/// Directories of the exported CRE tileset files, and of their metadata.
+pub const CRE_TILESET_DIRS: [&str; 2] = ["Export/Tileset/CRE", "Data/Tileset/CRE"];
+/// Directories of the exported SCE tileset files, and of their metadata.
+pub const SCE_TILESET_DIRS: [&str; 2] = ["Export/Tileset/SCE", "Data/Tileset/SCE"];
+
+/// Loads a single room file, given its path relative to the project root, e.g. to pick up changes
+/// made by SMART.
+pub fn load_project_room(source: &dyn ProjectSource, path: &Path) -> Result<Room> {
+    read_room_file(source, path)
+}
+
+/// Loads every room in `Export/Rooms`, calling `progress` with the number of rooms loaded so far
+/// and the total after each one.
+#[tracing::instrument(skip(source, progress))]
+pub fn load_project_rooms(
+    source: &dyn ProjectSource,
+    mode: LoadMode,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<RoomsInfo> {
+    let mut rooms = RoomsInfo::default();
+
+    let rooms_path = Path::new(ROOMS_DIR);
+    let room_paths: Vec<_> = source
+        .read_dir(rooms_path)
+        .context("listing Export/Rooms/ directory")?
+        .into_iter()
+        .map(|entry| (rooms_path.join(&entry.name), entry.is_dir))
+        .filter(|(path, is_dir)| path.extension() == Some("xml".as_ref()) && !is_dir)
+        .map(|(path, _)| path)
+        .collect();
+    let total = room_paths.len();
+    for (i, path) in room_paths.into_iter().enumerate() {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let room = read_room_file(source, &path)?;
+        rooms.insert(RoomFile { name, path, room }, mode)?;
+        progress(i + 1, total);
     }
-    info!("Loaded {} rooms from SMART", rooms.len());
+    info!("Loaded {} rooms from SMART", rooms.rooms.len());
     Ok(rooms)
 }
 
-#[tracing::instrument]
-pub fn load_project_area_maps(project_path: &Path) -> Result<BTreeMap<u8, Map>> {
+#[tracing::instrument(skip(source))]
+pub fn load_project_area_maps(source: &dyn ProjectSource) -> Result<BTreeMap<u8, Map>> {
     let mut maps = BTreeMap::new();
 
     for area_id in 0..8 {
-        let path = project_path.join(format!("Export/Maps/areamap.{area_id}.xml"));
-        if fs::exists(&path)? {
-            let map = read_xml_file(&path)?;
+        let path = PathBuf::from(format!("Export/Maps/areamap.{area_id}.xml"));
+        if source.exists(&path)? {
+            let map = read_xml_file(source, &path)?;
             maps.insert(area_id, map);
         }
     }
@@ -514,18 +1059,23 @@ pub fn load_project_area_maps(project_path: &Path) -> Result<BTreeMap<u8, Map>>
     Ok(maps)
 }
 
-#[tracing::instrument]
-pub fn load_project_tilesets(project_path: &Path) -> Result<TilesetsInfo> {
-    Ok(TilesetsInfo {
-        cre: load_tilesets_from_dir(
-            &project_path.join("Export/Tileset/CRE"),
-            &project_path.join("Data/Tileset/CRE"),
-        )?,
-        sce: load_tilesets_from_dir(
-            &project_path.join("Export/Tileset/SCE"),
-            &project_path.join("Data/Tileset/SCE"),
-        )?,
-    })
+#[tracing::instrument(skip(source))]
+pub fn load_project_tilesets(source: &dyn ProjectSource) -> Result<TilesetsInfo> {
+    let start = Instant::now();
+    let (cre, sce) = rayon::join(
+        || load_tilesets_from_dir(source, CRE_TILESET_DIRS),
+        || load_tilesets_from_dir(source, SCE_TILESET_DIRS),
+    );
+    let tilesets = TilesetsInfo {
+        cre: cre?,
+        sce: sce?,
+    };
+    info!(
+        "Loaded {} tilesets from SMART in {:.2?}",
+        tilesets.cre.len() + tilesets.sce.len(),
+        start.elapsed()
+    );
+    Ok(tilesets)
 }
 
 fn rgb_to_snes([r, g, b]: [u8; 3]) -> u16 {
@@ -540,11 +1090,21 @@ fn rgb_palette_to_snes(contents: &[u8]) -> Vec<u16> {
     entries.iter().copied().map(rgb_to_snes).collect()
 }
 
-fn detect_and_load_palette(base_filepath: &Path) -> Result<Vec<u16>> {
-    let try_extensions = |exts: &[&str]| {
+/// Loads the palette from whichever supported file format exists at `base_filepath`. Also returns
+/// the path of the file it was read from, or of a new TPL file if there's none.
+fn detect_and_load_palette(
+    source: &dyn ProjectSource,
+    base_filepath: &Path,
+) -> Result<(Vec<u16>, PathBuf)> {
+    let mut found_path = base_filepath.with_extension("tpl");
+    let mut try_extensions = |exts: &[&str]| {
         for ext in exts {
-            match fs::read(base_filepath.with_extension(ext)) {
-                Ok(c) => return Ok(Some(c)),
+            let path = base_filepath.with_extension(ext);
+            match source.read(&path) {
+                Ok(c) => {
+                    found_path = path;
+                    return Ok(Some(c));
+                }
                 Err(e) if e.kind() == io::ErrorKind::NotFound => {}
                 Err(e) => return Err(e),
             }
@@ -553,7 +1113,7 @@ fn detect_and_load_palette(base_filepath: &Path) -> Result<Vec<u16>> {
     };
 
     // try TPL, PAL, (RAW, SNES, BIN)
-    if let Some(contents) = try_extensions(&["tpl"])? {
+    let palette = if let Some(contents) = try_extensions(&["tpl"])? {
         let Some((header, entries)) = contents.split_at_checked(4) else {
             return Err(anyhow!("Invalid TPL file: missing header"));
         };
@@ -561,52 +1121,602 @@ fn detect_and_load_palette(base_filepath: &Path) -> Result<Vec<u16>> {
             return Err(anyhow!("Invalid TPL file: wrong magic"));
         }
         match header[3] {
-            0 => Ok(rgb_palette_to_snes(entries)),         // RGB format
-            2 => Ok(bytemuck::cast_slice(entries).into()), // SNES format
-            _ => Err(anyhow!("Invalid TPL file: unsupported format")),
+            0 => rgb_palette_to_snes(entries),          // RGB format
+            2 => bytemuck::pod_collect_to_vec(entries), // SNES format
+            _ => return Err(anyhow!("Invalid TPL file: unsupported format")),
         }
     } else if let Some(contents) = try_extensions(&["pal"])? {
-        Ok(rgb_palette_to_snes(&contents))
+        rgb_palette_to_snes(&contents)
     } else if let Some(contents) = try_extensions(&["raw", "snes", "bin"])? {
-        Ok(bytemuck::cast_vec(contents))
+        reinterpret_vec(contents)
     } else {
-        Ok(Vec::new())
-    }
+        Vec::new()
+    };
+    Ok((palette, found_path))
+}
+
+fn load_tileset(
+    source: &dyn ProjectSource,
+    export_path: &Path,
+    data_path: &Path,
+    file_name: &str,
+) -> Result<Tileset> {
+    let tileset_path = export_path.join(file_name);
+    let gfx_path = tileset_path.join("8x8tiles.gfx");
+    let tiletable_path = tileset_path.join("16x16tiles.ttb");
+    let gfx_data = source.read(&gfx_path)?;
+    let ttb_data = source.read(&tiletable_path)?;
+    let (palette_data, palette_path) =
+        detect_and_load_palette(source, &tileset_path.join("palette"))?;
+
+    let metadata_path = data_path.join(file_name).with_extension("xml");
+    let metadata = if source.exists(&metadata_path)? {
+        Some(read_xml_file(source, &metadata_path)?)
+    } else {
+        None
+    };
+
+    Ok(Tileset {
+        metadata,
+        gfx: gfx_data,
+        tiletable: reinterpret_vec(ttb_data),
+        palette: palette_data,
+        gfx_path,
+        tiletable_path,
+        palette_path,
+    })
+}
+
+/// Loads the single tileset with id `tileset_id` from the directories in `dirs`, which are one of
+/// [`CRE_TILESET_DIRS`] or [`SCE_TILESET_DIRS`].
+pub fn load_project_tileset(
+    source: &dyn ProjectSource,
+    [export_path, data_path]: [&str; 2],
+    tileset_id: u8,
+) -> Result<Tileset> {
+    let dir = find_tileset_dir(source, export_path, tileset_id)?;
+    let name = dir.file_name().unwrap().to_string_lossy();
+    load_tileset(source, Path::new(export_path), Path::new(data_path), &name)
+}
+
+/// Finds the directory a tileset is exported to in `export_path`, relative to the project root.
+/// Its name is the tileset id in hex, but isn't necessarily zero-padded.
+pub fn find_tileset_dir(
+    source: &dyn ProjectSource,
+    export_path: &str,
+    tileset_id: u8,
+) -> Result<PathBuf> {
+    let export_path = Path::new(export_path);
+    let entry = source
+        .read_dir(export_path)?
+        .into_iter()
+        .find(|entry| HexU8::from_str(&entry.name).ok() == Some(HexU8(tileset_id)))
+        .ok_or_else(|| anyhow!("Tileset {tileset_id:02X} not found in {export_path:?}"))?;
+    Ok(export_path.join(entry.name))
+}
+
+fn load_tilesets_from_dir(
+    source: &dyn ProjectSource,
+    [export_path, data_path]: [&str; 2],
+) -> Result<BTreeMap<u8, Tileset>> {
+    let (export_path, data_path) = (Path::new(export_path), Path::new(data_path));
+    let entries = source.read_dir(export_path)?;
+    // Tilesets are independent of each other, so their files are read in parallel and only
+    // collected into the map afterwards.
+    let loaded: Vec<_> = entries
+        .par_iter()
+        .filter_map(|entry| {
+            let HexU8(tileset_id) = HexU8::from_str(&entry.name).ok()?;
+            Some((
+                tileset_id,
+                load_tileset(source, export_path, data_path, &entry.name),
+            ))
+        })
+        .collect();
+    loaded
+        .into_iter()
+        .map(|(tileset_id, tileset)| Ok((tileset_id, tileset?)))
+        .collect()
+}
+
+fn reinterpret_vec<T: bytemuck::Pod, U: bytemuck::Pod>(v: Vec<T>) -> Vec<U> {
+    bytemuck::try_cast_vec(v).unwrap_or_else(|(_, v)| bytemuck::pod_collect_to_vec(&v))
 }
 
-fn load_tilesets_from_dir(export_path: &Path, data_path: &Path) -> Result<BTreeMap<u8, Tileset>> {
-    let mut tilesets = BTreeMap::new();
-    for e in export_path.read_dir()? {
-        let file_name = e?.file_name();
-        let Ok(HexU8(tileset_id)) = HexU8::from_str(&file_name.to_string_lossy()) else {
-            continue;
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::project_source::MemorySource;
+
+    pub(crate) const SAMPLE_ROOM_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Room>
+  <index>1A</index>
+  <area>01</area>
+  <x>0A</x>
+  <y>03</y>
+  <width>02</width>
+  <height>01</height>
+  <upscroll>70</upscroll>
+  <dnscroll>A0</dnscroll>
+  <specialGFX>00</specialGFX>
+  <Saves>
+    <SaveRoom>
+      <saveindex>00</saveindex>
+      <indoor roomarea="01" roomindex="19" doorindex="00" />
+      <unused>0000</unused>
+      <unused>0000</unused>
+      <screenx>0000</screenx>
+      <screeny>0000</screeny>
+      <samusx>0080</samusx>
+      <samusy>0040</samusy>
+    </SaveRoom>
+  </Saves>
+  <Doors>
+    <Door>
+      <toroom area="01" index="19" />
+      <bitflag>00</bitflag>
+      <direction>04</direction>
+      <tilex>01</tilex>
+      <tiley>06</tiley>
+      <screenx>00</screenx>
+      <screeny>00</screeny>
+      <distance>8000</distance>
+      <doorcode>
+        <Code OP="09" ARG="05" />
+        <Code OP="0A" />
+      </doorcode>
+    </Door>
+    <Elevator />
+    <Door>
+      <toroom area="01" index="1B" />
+      <bitflag>40</bitflag>
+      <direction>05</direction>
+      <tilex>0E</tilex>
+      <tiley>06</tiley>
+      <screenx>00</screenx>
+      <screeny>00</screeny>
+      <distance>8000</distance>
+      <doorcode>
+        <ScrollData>
+          <Change screen="00" scroll="02" />
+          <Change screen="01" scroll="01" />
+        </ScrollData>
+      </doorcode>
+    </Door>
+  </Doors>
+  <States>
+    <State condition="E612">
+      <Arg>0C</Arg>
+      <LevelData Width="02" Height="01">
+        <Layer1>
+          <Screen X="00" Y="00">8000 8001 0000</Screen>
+          <Screen X="01" Y="00">0002 C003 0000</Screen>
+        </Layer1>
+        <BTS>
+          <Screen X="00" Y="00">00 01 02</Screen>
+          <Screen X="01" Y="00">00 00 FF</Screen>
+        </BTS>
+      </LevelData>
+      <GFXset>01</GFXset>
+      <music>0500</music>
+      <FX1s />
+      <Enemies killcount="00" />
+      <EnemyTypes />
+      <layer2_type>BGData</layer2_type>
+      <layer2_xscroll>00</layer2_xscroll>
+      <layer2_yscroll>00</layer2_yscroll>
+      <ScrollData const="0001" />
+      <roomvar>0000</roomvar>
+      <FX2>0000</FX2>
+      <PLMs />
+      <BGData>
+        <Data Type="DECOMP">
+          <SOURCE>$B89E00</SOURCE>
+          <DEST>4000</DEST>
+        </Data>
+        <Data Type="COPY">
+          <SOURCE>0338 0338 0338</SOURCE>
+          <DEST>4800</DEST>
+          <SIZE>0006</SIZE>
+        </Data>
+      </BGData>
+      <layer1_2>91C9</layer1_2>
+    </State>
+    <State condition="default">
+      <LevelData Width="02" Height="01">
+        <Layer1>
+          <Screen X="00" Y="00">8000 8001 0000</Screen>
+          <Screen X="01" Y="00">0002 C003 0000</Screen>
+        </Layer1>
+        <BTS>
+          <Screen X="00" Y="00">00 01 02</Screen>
+          <Screen X="01" Y="00">00 00 FF</Screen>
+        </BTS>
+        <Layer2>
+          <Screen X="00" Y="00">0010 0011 0012</Screen>
+          <Screen X="01" Y="00">0013 0014 0015</Screen>
+        </Layer2>
+      </LevelData>
+      <GFXset>01</GFXset>
+      <music>0000</music>
+      <FX1s>
+        <FX1 default="true">
+          <surfacestart>FFFF</surfacestart>
+          <surfacenew>FFFF</surfacenew>
+          <surfacespeed>0000</surfacespeed>
+          <surfacedelay>00</surfacedelay>
+          <type>00</type>
+          <transparency1_A>02</transparency1_A>
+          <transparency2_B>02</transparency2_B>
+          <liquidflags_C>00</liquidflags_C>
+          <paletteflags>00</paletteflags>
+          <animationflags>00</animationflags>
+          <paletteblend>00</paletteblend>
+        </FX1>
+      </FX1s>
+      <Enemies killcount="01">
+        <Enemy>
+          <ID>EEFF</ID>
+          <X>0080</X>
+          <Y>00B0</Y>
+          <tilemap>0000</tilemap>
+          <special>2000</special>
+          <gfx>0000</gfx>
+          <speed>0000</speed>
+          <speed2>0000</speed2>
+        </Enemy>
+      </Enemies>
+      <EnemyTypes>
+        <Enemy>
+          <GFX>EEFF</GFX>
+          <palette>0001</palette>
+        </Enemy>
+      </EnemyTypes>
+      <layer2_type>Layer2</layer2_type>
+      <layer2_xscroll>C1</layer2_xscroll>
+      <layer2_yscroll>C1</layer2_yscroll>
+      <ScrollData>01 02</ScrollData>
+      <roomvar>0000</roomvar>
+      <FX2>0000</FX2>
+      <PLMs>
+        <PLM>
+          <type>B76F</type>
+          <x>05</x>
+          <y>06</y>
+          <arg>0001</arg>
+        </PLM>
+        <PLM>
+          <type>B703</type>
+          <x>08</x>
+          <y>09</y>
+          <ScrollData>
+            <Change screen="00" scroll="00" />
+          </ScrollData>
+        </PLM>
+      </PLMs>
+      <BGData />
+      <layer1_2>0000</layer1_2>
+    </State>
+  </States>
+</Room>
+"#;
+
+    pub(crate) fn parse_room_xml(xml: &str) -> Result<Room> {
+        parse_room(|| Ok(xml.as_bytes()))
+    }
+
+    #[test]
+    fn test_room_xml_round_trip() {
+        let parsed: Room = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        assert_eq!(parsed.doors.len(), 3);
+        assert_eq!(parsed.states.len(), 2);
+        assert_eq!(parsed.states[1].plms.len(), 2);
+        let serialized = quick_xml::se::to_string_with_root("Room", &parsed).unwrap();
+        let reparsed = parse_room_xml(&serialized).unwrap();
+
+        assert_eq!(format!("{parsed:?}"), format!("{reparsed:?}"));
+    }
+
+    #[test]
+    fn test_parse_level_data_streaming() {
+        // Matches what serde reads from the same element
+        let start = SAMPLE_ROOM_XML.find("<LevelData").unwrap();
+        let end = SAMPLE_ROOM_XML.find("</LevelData>").unwrap() + "</LevelData>".len();
+        let deserialized: LevelData =
+            quick_xml::de::from_str(&SAMPLE_ROOM_XML[start..end]).unwrap();
+        let mut reader = Reader::from_reader(SAMPLE_ROOM_XML.as_bytes());
+        let streamed = parse_level_data_streaming(&mut reader).unwrap();
+        assert_eq!(format!("{streamed:?}"), format!("{deserialized:?}"));
+
+        let parsed = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        assert_eq!(parsed.states[0].level_data.layer1.screens.len(), 2);
+        for state in &parsed.states[1..] {
+            let streamed = parse_level_data_streaming(&mut reader).unwrap();
+            assert_eq!(format!("{streamed:?}"), format!("{:?}", state.level_data));
+        }
+        assert!(parse_level_data_streaming(&mut reader).is_err());
+
+        let xml = r#"<LevelData Width="01" Height="01"><Screen X="00" Y="00" /></LevelData>"#;
+        let result = parse_level_data_streaming(&mut Reader::from_reader(xml.as_bytes()));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Screen outside of a LevelData layer"
+        );
+    }
+
+    #[test]
+    fn test_validate_sample_room() {
+        let parsed: Room = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        assert_eq!(parsed.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_validate_conflicting_door_code() {
+        let mut parsed: Room = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        let DoorEntry::Door(door) = &mut parsed.doors[0] else {
+            panic!("expected a door");
         };
+        door.doorcode.address = Some(HexU16(0xE4A0));
+        let DoorEntry::Door(door) = &mut parsed.doors[2] else {
+            panic!("expected a door");
+        };
+        door.doorcode.address = Some(HexU16(0xE4A0));
+
+        assert_eq!(
+            parsed.validate(),
+            vec![
+                RoomWarning::AmbiguousDoorCode {
+                    door: 0,
+                    fields: vec!["Code", "address"],
+                },
+                RoomWarning::AmbiguousDoorCode {
+                    door: 2,
+                    fields: vec!["ScrollData", "address"],
+                },
+            ]
+        );
+    }
 
-        let tileset_path = export_path.join(&file_name);
-        let gfx_data = fs::read(tileset_path.join("8x8tiles.gfx"))?;
-        let ttb_data = fs::read(tileset_path.join("16x16tiles.ttb"))?;
-        let palette_data = detect_and_load_palette(&tileset_path.join("palette"))?;
+    #[test]
+    fn test_validate_conflicting_plm_arg() {
+        let mut parsed: Room = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        parsed.states[1].plms[1].arg = Some(HexU16(0x0001));
 
-        let metadata_path = data_path.join(&file_name).with_extension("xml");
-        let metadata = if fs::exists(&metadata_path)? {
-            Some(read_xml_file(&metadata_path)?)
-        } else {
-            None
+        assert_eq!(
+            parsed.validate(),
+            vec![RoomWarning::PlmArgWithScrollData { state: 1, plm: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_scroll_data_resolve() {
+        let mut parsed: Room = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        assert_eq!(
+            parsed.states[0].scroll_data.resolve(),
+            ScrollKind::Const(HexU16(0x0001))
+        );
+        assert_eq!(
+            parsed.states[1].scroll_data.resolve(),
+            ScrollKind::PerScreen(&[HexU8(0x01), HexU8(0x02)])
+        );
+
+        parsed.states[1].scroll_data.data.clear();
+        assert_eq!(parsed.states[1].scroll_data.resolve(), ScrollKind::Empty);
+
+        assert_eq!(ScrollKind::Const(HexU16(0x0001)).screen_value(5), Some(1));
+        assert_eq!(ScrollKind::Const(HexU16(0x91F5)).screen_value(0), None);
+        assert_eq!(
+            ScrollKind::PerScreen(&[HexU8(0x00), HexU8(0x02)]).screen_value(1),
+            Some(2)
+        );
+        assert_eq!(ScrollKind::PerScreen(&[HexU8(0x00)]).screen_value(1), None);
+    }
+
+    #[test]
+    fn test_validate_conflicting_scroll_data() {
+        let mut parsed: Room = parse_room_xml(SAMPLE_ROOM_XML).unwrap();
+        parsed.states[1].scroll_data.const_ = Some(HexU16(0x0000));
+
+        assert_eq!(
+            parsed.states[1].scroll_data.resolve(),
+            ScrollKind::Const(HexU16(0x0000))
+        );
+        assert_eq!(
+            parsed.validate(),
+            vec![RoomWarning::ScrollConstWithData { state: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_load_project_rooms() {
+        let other_room = SAMPLE_ROOM_XML.replace("<index>1A</index>", "<index>1B</index>");
+        let source = MemorySource::default()
+            .with_file("Export/Rooms/FirstRoom.xml", SAMPLE_ROOM_XML)
+            .with_file("Export/Rooms/SecondRoom.xml", other_room)
+            .with_file("Export/Rooms/notes.txt", "not a room")
+            .with_file("Export/Rooms/Backup/FirstRoom.xml", SAMPLE_ROOM_XML);
+
+        let mut reported = Vec::new();
+        let info = load_project_rooms(&source, LoadMode::Strict, |done, total| {
+            reported.push((done, total));
+        })
+        .unwrap();
+        assert_eq!(reported, [(1, 2), (2, 2)]);
+        let names: Vec<_> = info.rooms.values().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["FirstRoom", "SecondRoom"]);
+        let paths: Vec<_> = info.rooms.values().map(|r| r.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("Export/Rooms/FirstRoom.xml"),
+                Path::new("Export/Rooms/SecondRoom.xml")
+            ]
+        );
+        assert!(info.rooms.contains_key(&(0x01, 0x1B)));
+
+        assert!(load_project_area_maps(&source).unwrap().is_empty());
+        assert!(load_project_rooms(&MemorySource::default(), LoadMode::Strict, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn test_load_tilesets_from_dir() {
+        let mut tpl = b"TPL\x02".to_vec();
+        tpl.extend_from_slice(&[0x1F, 0x00, 0xE0, 0x03]);
+        let source = MemorySource::default()
+            .with_file("Export/Tileset/SCE/00/8x8tiles.gfx", vec![0xAA; 64])
+            .with_file(
+                "Export/Tileset/SCE/00/16x16tiles.ttb",
+                [0x34, 0x12, 0x00, 0x80],
+            )
+            .with_file("Export/Tileset/SCE/00/palette.tpl", tpl)
+            .with_file(
+                "Data/Tileset/SCE/00.xml",
+                "<Tileset><Name>Crateria</Name></Tileset>",
+            )
+            .with_file("Export/Tileset/SCE/1A/8x8tiles.gfx", [])
+            .with_file("Export/Tileset/SCE/1A/16x16tiles.ttb", [])
+            .with_file("Export/Tileset/SCE/Backup/8x8tiles.gfx", []);
+
+        let tilesets = load_tilesets_from_dir(&source, SCE_TILESET_DIRS).unwrap();
+        assert_eq!(tilesets.keys().copied().collect::<Vec<_>>(), [0x00, 0x1A]);
+
+        let tileset = &tilesets[&0x00];
+        assert_eq!(tileset.metadata.as_ref().unwrap().name, "Crateria");
+        assert_eq!(tileset.gfx.len(), 64);
+        assert_eq!(tileset.tiletable, [0x1234, 0x8000]);
+        assert_eq!(tileset.palette, [0x001F, 0x03E0]);
+
+        let tileset = &tilesets[&0x1A];
+        assert!(tileset.metadata.is_none());
+        assert!(tileset.gfx.is_empty() && tileset.tiletable.is_empty());
+        assert!(tileset.palette.is_empty());
+
+        let tileset = load_project_tileset(&source, SCE_TILESET_DIRS, 0x1A).unwrap();
+        assert!(tileset.gfx.is_empty());
+        assert!(load_project_tileset(&source, SCE_TILESET_DIRS, 0x1B).is_err());
+        assert_eq!(
+            find_tileset_dir(&source, "Export/Tileset/SCE", 0x00).unwrap(),
+            Path::new("Export/Tileset/SCE/00")
+        );
+    }
+
+    #[test]
+    fn test_detect_and_load_palette() {
+        let load = |file: &str, contents: &[u8]| {
+            let source = MemorySource::default().with_file(file, contents);
+            detect_and_load_palette(&source, Path::new("palette")).map(|(palette, _)| palette)
         };
 
-        tilesets.insert(
-            tileset_id,
-            Tileset {
-                metadata,
-                gfx: gfx_data,
-                tiletable: reinterpret_vec(ttb_data),
-                palette: palette_data,
-            },
+        // RGB: 8 bits per component, of which the low 3 are discarded
+        let rgb = [0xF8, 0x00, 0x00, 0x00, 0xF8, 0x00, 0x08, 0x10, 0xF8];
+        let expected = [0x001F, 0x03E0, 0x7C41];
+        assert_eq!(
+            load("palette.tpl", &[b"TPL\x00", &rgb[..]].concat()).unwrap(),
+            expected
+        );
+        assert_eq!(load("palette.pal", &rgb).unwrap(), expected);
+        // SNES: little-endian BGR555 words
+        let snes = [0x1F, 0x00, 0x41, 0x7C];
+        assert_eq!(
+            load("palette.tpl", &[b"TPL\x02", &snes[..]].concat()).unwrap(),
+            [0x001F, 0x7C41]
         );
+        assert_eq!(load("palette.snes", &snes).unwrap(), [0x001F, 0x7C41]);
+
+        assert!(load("palette.tpl", b"TPL").is_err());
+        assert!(load("palette.tpl", b"TPX\x00").is_err());
+        assert!(load("palette.tpl", b"TPL\x01").is_err());
+        assert!(load("other.tpl", b"TPL\x00").unwrap().is_empty());
+
+        let source = MemorySource::default().with_file("palette.pal", rgb);
+        let (_, path) = detect_and_load_palette(&source, Path::new("palette")).unwrap();
+        assert_eq!(path, Path::new("palette.pal"));
+        let (_, path) =
+            detect_and_load_palette(&MemorySource::default(), Path::new("palette")).unwrap();
+        assert_eq!(path, Path::new("palette.tpl"));
     }
-    Ok(tilesets)
-}
 
-fn reinterpret_vec<T: bytemuck::Pod, U: bytemuck::Pod>(v: Vec<T>) -> Vec<U> {
-    bytemuck::try_cast_vec(v).unwrap_or_else(|(_, v)| bytemuck::pod_collect_to_vec(&v))
+    #[test]
+    fn test_code_op_arg_size() {
+        let parse = |xml: &str| quick_xml::de::from_str::<CodeOp>(xml).unwrap().arg;
+        // STA absolute takes a word, even if written with fewer digits
+        assert_eq!(
+            parse(r#"<Code OP="8D" ARG="05" />"#),
+            Some(HexValue::Word(HexU16(0x0005)))
+        );
+        assert_eq!(
+            parse(r#"<Code OP="22" ARG="8000" />"#),
+            Some(HexValue::Long(HexU24(0x008000)))
+        );
+        // Immediate and unknown ops keep the width they were written with
+        assert_eq!(
+            parse(r#"<Code OP="A9" ARG="05" />"#),
+            Some(HexValue::Byte(HexU8(0x05)))
+        );
+        assert_eq!(
+            parse(r#"<Code OP="42" ARG="0005" />"#),
+            Some(HexValue::Word(HexU16(0x0005)))
+        );
+        assert_eq!(parse(r#"<Code OP="6B" />"#), None);
+        // Arguments too wide for the op keep the width they were written with
+        let op = quick_xml::de::from_str::<CodeOp>(r#"<Code OP="85" ARG="0100" />"#).unwrap();
+        assert_eq!(op.arg, Some(HexValue::Word(HexU16(0x0100))));
+        let serialized = quick_xml::se::to_string_with_root("Code", &op).unwrap();
+        assert_eq!(serialized, r#"<Code OP="$85" ARG="$0100"/>"#);
+    }
+
+    #[test]
+    fn test_reinterpret_vec() {
+        // Byte vectors aren't aligned for u16, so these have to be copied
+        let words: Vec<u16> = reinterpret_vec(vec![0x34u8, 0x12, 0xCD, 0xAB]);
+        assert_eq!(words, [0x1234, 0xABCD]);
+        let bytes: Vec<u8> = reinterpret_vec(words);
+        assert_eq!(bytes, [0x34, 0x12, 0xCD, 0xAB]);
+    }
+
+    fn sample_room_file(name: &str) -> RoomFile {
+        RoomFile {
+            name: name.into(),
+            path: Path::new(ROOMS_DIR).join(format!("{name}.xml")),
+            room: parse_room_xml(SAMPLE_ROOM_XML).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_rooms_strict() {
+        let mut rooms = RoomsInfo::default();
+        rooms
+            .insert(sample_room_file("first"), LoadMode::Strict)
+            .unwrap();
+        let err = rooms
+            .insert(sample_room_file("second"), LoadMode::Strict)
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Duplicate rooms with id ($01,$1A): \"first\" and \"second\""
+        );
+    }
+
+    #[test]
+    fn test_duplicate_rooms_lenient() {
+        let mut rooms = RoomsInfo::default();
+        rooms
+            .insert(sample_room_file("first"), LoadMode::Lenient)
+            .unwrap();
+        rooms
+            .insert(sample_room_file("second"), LoadMode::Lenient)
+            .unwrap();
+
+        assert_eq!(rooms.rooms.len(), 1);
+        assert_eq!(rooms.rooms[&(0x01, 0x1A)].name, "first");
+        assert_eq!(
+            rooms.duplicates,
+            vec![DuplicateRoom {
+                id: (0x01, 0x1A),
+                kept_name: "first".into(),
+                skipped_name: "second".into(),
+            }]
+        );
+    }
 }
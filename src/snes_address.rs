@@ -0,0 +1,54 @@
+use crate::hex_types::{HexU16, HexU24};
+
+/// Bank containing the enemy species headers, which `EnemyType::gfx` points into.
+pub const ENEMY_HEADER_BANK: u8 = 0xA0;
+
+/// Translates a LoROM SNES address into an offset in an unheadered ROM file. Returns `None` for
+/// addresses that don't map to ROM (e.g. RAM or I/O registers).
+pub fn lorom_to_pc(addr: HexU24) -> Option<usize> {
+    let bank = usize::from(addr.bank());
+    let offset = usize::from(addr.offset().0);
+    // Banks $7E-$7F are WRAM, and the bottom half of every bank maps RAM/IO instead of ROM,
+    // except for banks $40-$6F and $C0-$FF, where it mirrors the top half.
+    if matches!(bank, 0x7E..=0x7F) {
+        return None;
+    }
+    if offset < 0x8000 && !matches!(bank, 0x40..=0x6F | 0xC0..=0xFF) {
+        return None;
+    }
+    Some(((bank & 0x7F) << 15) | (offset & 0x7FFF))
+}
+
+/// Translates an offset in an unheadered ROM file into its canonical LoROM SNES address (in the
+/// FastROM banks $80-$FF).
+#[cfg_attr(not(test), expect(unused))]
+pub fn pc_to_lorom(pc: usize) -> Option<HexU24> {
+    let bank = u8::try_from(pc >> 15).ok().filter(|&b| b < 0x80)?;
+    let offset = HexU16((pc & 0x7FFF) as u16 | 0x8000);
+    Some(HexU24::from_bank_offset(bank | 0x80, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lorom_to_pc() {
+        assert_eq!(lorom_to_pc(HexU24(0x808000)), Some(0x000000));
+        assert_eq!(lorom_to_pc(HexU24(0x008000)), Some(0x000000));
+        assert_eq!(lorom_to_pc(HexU24(0x8FFFFF)), Some(0x07FFFF));
+        assert_eq!(lorom_to_pc(HexU24(0xA0E63F)), Some(0x10663F));
+        assert_eq!(lorom_to_pc(HexU24(0xC00000)), Some(0x200000));
+        assert_eq!(lorom_to_pc(HexU24(0x7E0000)), None);
+        assert_eq!(lorom_to_pc(HexU24(0x802100)), None);
+    }
+
+    #[test]
+    fn test_pc_to_lorom_round_trip() {
+        for pc in [0x000000, 0x007FFF, 0x008000, 0x10663F, 0x3FFFFF] {
+            let addr = pc_to_lorom(pc).unwrap();
+            assert_eq!(lorom_to_pc(addr), Some(pc));
+        }
+        assert_eq!(pc_to_lorom(0x400000), None);
+    }
+}
@@ -0,0 +1,212 @@
+//! Meaning of the values used by the vanilla game's engine, for displaying them in a readable form.
+
+use crate::hex_types::HexU8;
+use crate::room::BlockType;
+use bitflags::bitflags;
+use std::fmt::{Display, Formatter};
+
+/// What a block's BTS value means, which depends on the block's type.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BtsInterpretation {
+    /// Zero BTS on a block type that doesn't use it.
+    None,
+    /// Non-zero BTS on a block type that doesn't use it.
+    Unused(u8),
+    /// Index of the room door the block leads through.
+    DoorIndex(u8),
+    Slope {
+        shape: u8,
+        h_flip: bool,
+        v_flip: bool,
+    },
+    /// Signed offset added to the block's index to find the block being copied. Negative values
+    /// copy from the left or above, positive ones from the right or below.
+    Extension(i8),
+    CrumbleVariant(u8),
+    ShootableVariant(u8),
+    BombableVariant(u8),
+    SpikeVariant(u8),
+    GrappleVariant(u8),
+    TreadmillVariant(u8),
+}
+
+pub fn interpret_bts(block_type: BlockType, bts: u8) -> BtsInterpretation {
+    use BtsInterpretation as I;
+
+    match block_type {
+        BlockType::Door => I::DoorIndex(bts),
+        BlockType::Slope => I::Slope {
+            shape: bts & 0x1F,
+            h_flip: bts & 0x40 != 0,
+            v_flip: bts & 0x80 != 0,
+        },
+        BlockType::HorizontalExtension | BlockType::VerticalExtension => I::Extension(bts as i8),
+        BlockType::Crumble => I::CrumbleVariant(bts),
+        BlockType::Shootable | BlockType::ShootableAir => I::ShootableVariant(bts),
+        BlockType::Bombable | BlockType::BombableAir => I::BombableVariant(bts),
+        BlockType::Spike | BlockType::SpikeAir => I::SpikeVariant(bts),
+        BlockType::Grapple => I::GrappleVariant(bts),
+        BlockType::Treadmill => I::TreadmillVariant(bts),
+        BlockType::Air | BlockType::UnusedAir | BlockType::Solid => match bts {
+            0 => I::None,
+            _ => I::Unused(bts),
+        },
+    }
+}
+
+/// Size of the destructible block variants, which repeat every 4 values.
+fn block_size_name(variant: u8) -> &'static str {
+    ["1×1", "2×1", "1×2", "2×2"][usize::from(variant & 0x3)]
+}
+
+fn reform_name(variant: u8) -> &'static str {
+    if variant & 0x4 == 0 {
+        "reforms"
+    } else {
+        "doesn't reform"
+    }
+}
+
+impl Display for BtsInterpretation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::None => write!(f, "No effect"),
+            Self::Unused(bts) => write!(f, "Unused ({})", HexU8(bts)),
+            Self::DoorIndex(door) => write!(f, "Door {}", HexU8(door)),
+            Self::Slope {
+                shape,
+                h_flip,
+                v_flip,
+            } => {
+                write!(f, "Slope shape {}", HexU8(shape))?;
+                match (h_flip, v_flip) {
+                    (false, false) => Ok(()),
+                    (true, false) => write!(f, ", flipped horizontally"),
+                    (false, true) => write!(f, ", flipped vertically"),
+                    (true, true) => write!(f, ", flipped both ways"),
+                }
+            }
+            Self::Extension(0) => write!(f, "Copies itself"),
+            Self::Extension(offset) => {
+                let direction = if offset < 0 { "back" } else { "forward" };
+                write!(f, "Copies the block {} {direction}", offset.unsigned_abs())
+            }
+            Self::CrumbleVariant(variant @ 0x00..=0x07) => write!(
+                f,
+                "Crumble {}, {}",
+                block_size_name(variant),
+                reform_name(variant)
+            ),
+            Self::CrumbleVariant(0x0E) => write!(f, "Speed Booster, reforms"),
+            Self::CrumbleVariant(0x0F) => write!(f, "Speed Booster, doesn't reform"),
+            Self::ShootableVariant(variant @ 0x00..=0x07) => write!(
+                f,
+                "Shot {}, {}",
+                block_size_name(variant),
+                reform_name(variant)
+            ),
+            Self::ShootableVariant(0x08) => write!(f, "Power Bomb, reforms"),
+            Self::ShootableVariant(0x09) => write!(f, "Power Bomb, doesn't reform"),
+            Self::ShootableVariant(0x0A) => write!(f, "Super Missile, reforms"),
+            Self::ShootableVariant(0x0B) => write!(f, "Super Missile, doesn't reform"),
+            Self::BombableVariant(variant @ 0x00..=0x07) => write!(
+                f,
+                "Bomb {}, {}",
+                block_size_name(variant),
+                reform_name(variant)
+            ),
+            Self::CrumbleVariant(variant)
+            | Self::ShootableVariant(variant)
+            | Self::BombableVariant(variant)
+            | Self::SpikeVariant(variant)
+            | Self::GrappleVariant(variant)
+            | Self::TreadmillVariant(variant) => write!(f, "Variant {}", HexU8(variant)),
+        }
+    }
+}
+
+/// Names of the areas of the vanilla game, indexed by area id.
+const AREA_NAMES: [&str; 8] = [
+    "Crateria",
+    "Brinstar",
+    "Norfair",
+    "Wrecked Ship",
+    "Maridia",
+    "Tourian",
+    "Ceres",
+    "Debug",
+];
+
+/// Name of area `area`, or `None` if the vanilla game doesn't have it.
+pub fn area_name(area: u8) -> Option<&'static str> {
+    AREA_NAMES.get(usize::from(area)).copied()
+}
+
+bitflags! {
+    /// Bits of a room's special GFX byte, which controls how the common room elements (CRE) are
+    /// loaded when entering the room.
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    pub struct SpecialGfxFlags: u8 {
+        /// Load the CRE again, after a room that overwrote it.
+        const RELOAD_CRE = 1 << 1;
+        /// Tileset is large enough to take up the space of the CRE, which isn't loaded.
+        const EXTRA_LARGE_TILESET = 1 << 2;
+
+        // Bits without a known meaning are kept as they are
+        const _ = !0;
+    }
+}
+
+impl SpecialGfxFlags {
+    /// Readable name of bit `bit` (0–7).
+    pub fn bit_label(bit: u32) -> String {
+        let flag = Self::from_bits_retain(1 << bit);
+        if flag == Self::RELOAD_CRE {
+            "Reload CRE".into()
+        } else if flag == Self::EXTRA_LARGE_TILESET {
+            "Extra large tileset".into()
+        } else {
+            format!("Bit {bit}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpret_bts() {
+        let describe = |block_type, bts| interpret_bts(block_type, bts).to_string();
+        assert_eq!(describe(BlockType::Door, 0x03), "Door $03");
+        assert_eq!(
+            describe(BlockType::Slope, 0x52),
+            "Slope shape $12, flipped horizontally"
+        );
+        assert_eq!(
+            describe(BlockType::HorizontalExtension, 0xFF),
+            "Copies the block 1 back"
+        );
+        assert_eq!(
+            describe(BlockType::VerticalExtension, 0x02),
+            "Copies the block 2 forward"
+        );
+        assert_eq!(
+            describe(BlockType::Crumble, 0x05),
+            "Crumble 2×1, doesn't reform"
+        );
+        assert_eq!(
+            describe(BlockType::Shootable, 0x0A),
+            "Super Missile, reforms"
+        );
+        assert_eq!(describe(BlockType::Solid, 0x00), "No effect");
+        assert_eq!(describe(BlockType::Air, 0x10), "Unused ($10)");
+    }
+
+    #[test]
+    fn test_special_gfx_bit_label() {
+        assert_eq!(SpecialGfxFlags::bit_label(1), "Reload CRE");
+        assert_eq!(SpecialGfxFlags::bit_label(2), "Extra large tileset");
+        assert_eq!(SpecialGfxFlags::bit_label(7), "Bit 7");
+    }
+}
@@ -1,12 +1,54 @@
 use crate::gfx::{Palette, Snes4BppTile, TilemapEntry};
+use crate::hex_types::HexU16;
 use crate::smart_xml;
+use crate::smart_xml::LoadMode;
 use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::array;
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::PathBuf;
+use tracing::warn;
 
+/// Block of 2×2 tiles, stored in row-major order.
 #[derive(Copy, Clone)]
 pub struct TiletableEntry(pub [TilemapEntry; 4]);
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+impl TiletableEntry {
+    /// Returns the block as it's shown when flipped as a whole, the way level data can flip it.
+    fn flipped(self, h_flip: bool, v_flip: bool) -> Self {
+        let mut flags = 0;
+        if h_flip {
+            flags |= TilemapEntry::H_FLIP_FLAG;
+        }
+        if v_flip {
+            flags |= TilemapEntry::V_FLIP_FLAG;
+        }
+        let swap = usize::from(h_flip) | usize::from(v_flip) << 1;
+        Self(array::from_fn(|i| TilemapEntry(self.0[i ^ swap].0 ^ flags)))
+    }
+
+    /// Smallest of the flipped variants of the block, which is the same for all blocks that are
+    /// flips of each other.
+    fn canonical(self) -> [u16; 4] {
+        [(false, false), (true, false), (false, true), (true, true)]
+            .map(|(h_flip, v_flip)| self.flipped(h_flip, v_flip).0.map(|tile| tile.0))
+            .into_iter()
+            .min()
+            .unwrap()
+    }
+
+    /// Whether both blocks show the same tiles, possibly after flipping one of them. Blocks that
+    /// only look the same because their tiles happen to contain the same graphics aren't detected.
+    #[cfg_attr(not(test), expect(unused))]
+    pub fn same_visual(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum TilesetKind {
     /// Main, area-specific tileset data
     Sce,
@@ -17,6 +59,7 @@ pub enum TilesetKind {
 slotmap::new_key_type! { pub struct TilesetRef; }
 pub type TilesetIndex = u8;
 
+#[derive(Clone)]
 pub struct Tileset {
     handle: TilesetRef,
     index: Option<TilesetIndex>,
@@ -24,8 +67,17 @@ pub struct Tileset {
     pub kind: TilesetKind,
 
     pub palette: Palette,
-    pub gfx: Vec<Snes4BppTile>,
+    /// Raw GFX data, validated to be a whole number of tiles. Tiles are stored in the same
+    /// format, so [`Tileset::gfx`] views it in place.
+    gfx_data: Vec<u8>,
     pub tiletable: Vec<TiletableEntry>,
+
+    /// Files the tileset was loaded from, relative to the project root, for saving it back.
+    pub gfx_path: PathBuf,
+    #[cfg_attr(not(test), expect(unused))]
+    pub tiletable_path: PathBuf,
+    #[cfg_attr(not(test), expect(unused))]
+    pub palette_path: PathBuf,
 }
 
 impl Tileset {
@@ -33,11 +85,18 @@ impl Tileset {
         self.handle
     }
 
-    #[expect(unused)]
     pub fn index(&self) -> Option<TilesetIndex> {
         self.index
     }
 
+    pub fn gfx(&self) -> &[Snes4BppTile] {
+        bytemuck::cast_slice(&self.gfx_data)
+    }
+
+    pub fn gfx_len(&self) -> usize {
+        self.gfx_data.len() / size_of::<Snes4BppTile>()
+    }
+
     pub fn title(&self) -> String {
         if let Some(index) = self.index {
             format!("[{index:02X}] {}", self.name)
@@ -46,6 +105,31 @@ impl Tileset {
         }
     }
 
+    /// First 8 hex digits of the SHA-256 hash of the raw GFX data. Stable fingerprint that can be
+    /// used to check whether two tilesets have the same graphics.
+    pub fn gfx_fingerprint(&self) -> String {
+        Sha256::digest(&self.gfx_data)[..4]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// Pairs of tiletable indices `(original, duplicate)` of blocks that are the
+    /// [same visual](TiletableEntry::same_visual) as an earlier one, which is the `original`.
+    pub fn find_duplicate_blocks(&self) -> Vec<(usize, usize)> {
+        let mut first_seen = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (i, entry) in self.tiletable.iter().enumerate() {
+            match first_seen.entry(entry.canonical()) {
+                Entry::Occupied(original) => duplicates.push((*original.get(), i)),
+                Entry::Vacant(slot) => {
+                    slot.insert(i);
+                }
+            }
+        }
+        duplicates
+    }
+
     pub fn display_cmp(&self, o: &Self) -> Ordering {
         self.kind
             .cmp(&o.kind)
@@ -55,43 +139,71 @@ impl Tileset {
     }
 }
 
+/// Converts a tileset loaded from SMART. Incomplete trailing gfx tiles and tiletable entries, and
+/// palettes with more lines than tiles can address, are errors in [`LoadMode::Strict`]. In
+/// [`LoadMode::Lenient`] they're reported in `warnings` instead: the former are padded with blank
+/// data, and the palette is discarded.
 pub fn load_from_smart(
     kind: TilesetKind,
     index: u8,
     tileset: smart_xml::Tileset,
     handle: TilesetRef,
+    mode: LoadMode,
+    warnings: &mut Vec<String>,
 ) -> anyhow::Result<Tileset> {
     let name = tileset
         .metadata
         .map_or("Unnamed Tileset".into(), |meta| meta.name);
 
+    let mut report = |problem: String, recovery: &str| match mode {
+        LoadMode::Strict => Err(anyhow!(problem)),
+        LoadMode::Lenient => {
+            warn!("{problem}, {recovery}");
+            warnings.push(problem);
+            Ok(())
+        }
+    };
+
     let mut palette = Palette::from(tileset.palette);
     if !palette.is_empty()
-        && let Err(()) =
-            palette.truncate_checked(Palette::LINE_4BPP_LEN * TilemapEntry::ADDRESSABLE_PALETTES)
+        && let Err(color) = palette.truncate_checked(Palette::ADDRESSABLE_4BPP_LEN)
     {
-        return Err(anyhow!(
-            "Tileset {index:02X} palette has too many (non-blank) lines"
-        ));
+        // An empty palette marks the tileset as unusable: it's left out of the tileset list, and
+        // rooms using it render with placeholder colors.
+        let line = color / Palette::LINE_4BPP_LEN;
+        report(
+            format!(
+                "Tileset {index:02X} palette has too many (non-blank) lines: color {color:#X} on \
+                 line {line} is in use"
+            ),
+            "discarding it",
+        )?;
+        palette = Palette(Vec::new());
     }
 
-    let (tile_bytes, rest) = tileset.gfx.as_chunks();
-    if !rest.is_empty() {
-        return Err(anyhow!(
-            "Tileset {index:02X} gfx not evenly divisible as tiles"
-        ));
+    let mut gfx_data = tileset.gfx;
+    let excess = gfx_data.len() % size_of::<Snes4BppTile>();
+    if excess != 0 {
+        report(
+            format!("Tileset {index:02X} gfx not evenly divisible as tiles"),
+            "padding with blank data",
+        )?;
+        gfx_data.resize(gfx_data.len() + size_of::<Snes4BppTile>() - excess, 0);
     }
-    let gfx = tile_bytes.iter().map(Snes4BppTile::from_bytes).collect();
 
-    let (tiletable_entries, rest) = tileset.tiletable.as_chunks::<4>();
-    if !rest.is_empty() {
-        return Err(anyhow!(
-            "Tileset {index:02X} tiletable has truncated trailing entry"
-        ));
+    let mut tiletable_data = tileset.tiletable;
+    let excess = tiletable_data.len() % 4;
+    if excess != 0 {
+        report(
+            format!("Tileset {index:02X} tiletable has truncated trailing entry"),
+            "padding with blank data",
+        )?;
+        tiletable_data.resize(tiletable_data.len() + 4 - excess, 0);
     }
+    let (tiletable_entries, _) = tiletable_data.as_chunks::<4>();
     let tiletable = tiletable_entries
         .iter()
-        .map(|tiles| TiletableEntry(tiles.map(TilemapEntry)))
+        .map(|tiles| TiletableEntry(tiles.map(|v| TilemapEntry::from_xml(HexU16(v)))))
         .collect();
 
     Ok(Tileset {
@@ -100,19 +212,28 @@ pub fn load_from_smart(
         kind,
         name,
         palette,
-        gfx,
+        gfx_data,
         tiletable,
+        gfx_path: tileset.gfx_path,
+        tiletable_path: tileset.tiletable_path,
+        palette_path: tileset.palette_path,
     })
 }
 
+/// Region of an [`OverlaidLayout`] whose items come from a single tileset.
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
 pub struct OverlaidLayoutEntry<Ref> {
+    /// Index in the combined layout of the tileset's first item.
     pub base: usize,
+    /// Number of items the tileset provides.
     pub size: usize,
     pub tileset: Ref,
 }
 
-/// Stack of tileset objects that can be edited together. e.g. SCE+CRE
+/// Stack of regions of VRAM tiles (or tiletable entries) loaded from different physical tilesets,
+/// e.g. SCE+CRE, so that they can be viewed and edited together. The game loads them one after
+/// the other into the same memory, so entries later in the stack cover earlier ones where they
+/// overlap, and item indices are resolved to the topmost tileset covering them.
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
 pub struct OverlaidLayout<Ref> {
     pub entries: Vec<OverlaidLayoutEntry<Ref>>,
@@ -130,6 +251,7 @@ impl<Ref> OverlaidLayout<Ref>
 where
     Ref: Copy,
 {
+    /// Finds the topmost tileset providing item `i`, and the index of the item within it.
     pub fn lookup(&self, i: usize) -> Option<(Ref, usize)> {
         self.entries
             .iter()
@@ -137,6 +259,7 @@ where
             .map(|e| (e.tileset, i - e.base))
     }
 
+    /// Lowest and highest (exclusive) indices covered by any tileset.
     pub fn valid_range(&self) -> Option<(usize, usize)> {
         self.entries
             .iter()
@@ -159,20 +282,22 @@ where
     }
 }
 
+/// Tileset data as it is laid out in VRAM and RAM while a room is loaded: the gfx and tiletables
+/// of the SCE and CRE tilesets combined, and the tileset the palette comes from.
 #[derive(Clone, Hash, Eq, PartialEq)]
-pub struct LoadedTilesetLayout<Ref> {
+pub struct TilesetVramLayout<Ref> {
     pub gfx: OverlaidLayout<Ref>,
     pub tiletable: OverlaidLayout<Ref>,
     // palette_source could be made an OverlaidLayout too, but it's not necessary right now.
     pub palette_source: Ref,
 }
 
-impl<Ref> LoadedTilesetLayout<Ref>
+impl<Ref> TilesetVramLayout<Ref>
 where
     Ref: Copy,
 {
-    pub fn map_refs<T>(&self, mut f: impl FnMut(Ref) -> T) -> LoadedTilesetLayout<T> {
-        LoadedTilesetLayout {
+    pub fn map_refs<T>(&self, mut f: impl FnMut(Ref) -> T) -> TilesetVramLayout<T> {
+        TilesetVramLayout {
             gfx: self.gfx.map_ref(&mut f),
             tiletable: self.tiletable.map_ref(&mut f),
             palette_source: f(self.palette_source),
@@ -180,25 +305,30 @@ where
     }
 }
 
+/// Guesses whether `sce` is loaded by the Ceres tileset code, which doesn't load a CRE tiletable.
+pub fn is_ceres_tileset(sce: &Tileset) -> bool {
+    // A tiletable with more than 0x300 entries would overflow the vanilla buffer, so it's a good
+    // guess on if it's expecting the Ceres tileset loading code.
+    sce.tiletable.len() > 0x300
+}
+
 pub fn detect_sources_layout<'p>(
     selected_sce: &'p Tileset,
     selected_cre: Option<&'p Tileset>,
-) -> LoadedTilesetLayout<&'p Tileset> {
-    // A tiletable with more than 0x300 entries would overflow the vanilla buffer, so it's a good
-    // guess on if it's expecting the Ceres tileset loading code.
-    let is_ceres_tileset = selected_sce.tiletable.len() > 0x300;
+) -> TilesetVramLayout<&'p Tileset> {
+    let is_ceres_tileset = is_ceres_tileset(selected_sce);
 
     let mut gfx_layout = OverlaidLayout::default();
     if let Some(selected_cre) = selected_cre {
         gfx_layout.entries.push(OverlaidLayoutEntry {
             base: 0x280,
-            size: selected_cre.gfx.len(),
+            size: selected_cre.gfx_len(),
             tileset: selected_cre,
         });
     }
     gfx_layout.entries.push(OverlaidLayoutEntry {
         base: 0x0,
-        size: selected_sce.gfx.len(),
+        size: selected_sce.gfx_len(),
         tileset: selected_sce,
     });
 
@@ -218,9 +348,233 @@ pub fn detect_sources_layout<'p>(
         tileset: selected_sce,
     });
 
-    LoadedTilesetLayout {
+    TilesetVramLayout {
         gfx: gfx_layout,
         tiletable: ttb_layout,
         palette_source: selected_sce,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn smart_tileset(palette_len: usize, gfx_len: usize) -> smart_xml::Tileset {
+        smart_xml::Tileset {
+            metadata: None,
+            gfx: vec![0; gfx_len],
+            tiletable: vec![0x0001, 0x4002, 0x8003, 0xC004],
+            palette: vec![0; palette_len],
+            gfx_path: PathBuf::from("Export/Tileset/SCE/05/8x8tiles.gfx"),
+            tiletable_path: PathBuf::from("Export/Tileset/SCE/05/16x16tiles.ttb"),
+            palette_path: PathBuf::from("Export/Tileset/SCE/05/palette.tpl"),
+        }
+    }
+
+    #[test]
+    fn test_load_from_smart() {
+        let mut warnings = Vec::new();
+        let tileset = load_from_smart(
+            TilesetKind::Sce,
+            0x05,
+            smart_tileset(16 * 16, 2 * size_of::<Snes4BppTile>()),
+            TilesetRef::default(),
+            LoadMode::Strict,
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(tileset.name, "Unnamed Tileset");
+        assert_eq!(tileset.index(), Some(0x05));
+        assert_eq!(tileset.gfx_len(), 2);
+        assert_eq!(tileset.tiletable.len(), 1);
+        // Blank lines past the addressable ones are dropped
+        assert_eq!(tileset.palette.line_count(), 8);
+        assert_eq!(
+            tileset.tiletable_path,
+            Path::new("Export/Tileset/SCE/05/16x16tiles.ttb")
+        );
+        assert_eq!(
+            tileset.palette_path,
+            Path::new("Export/Tileset/SCE/05/palette.tpl")
+        );
+    }
+
+    fn load(smart: smart_xml::Tileset, mode: LoadMode) -> (anyhow::Result<Tileset>, Vec<String>) {
+        let mut warnings = Vec::new();
+        let result = load_from_smart(
+            TilesetKind::Cre,
+            0x05,
+            smart,
+            TilesetRef::default(),
+            mode,
+            &mut warnings,
+        );
+        (result, warnings)
+    }
+
+    #[test]
+    fn test_load_from_smart_strict() {
+        let mut smart = smart_tileset(16 * 16, 0);
+        smart.palette[8 * 16] = 0x7FFF;
+        let (result, _) = load(smart, LoadMode::Strict);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Tileset 05 palette has too many (non-blank) lines: color 0x80 on line 8 is in use"
+        );
+
+        let (result, _) = load(
+            smart_tileset(0, size_of::<Snes4BppTile>() + 1),
+            LoadMode::Strict,
+        );
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Tileset 05 gfx not evenly divisible as tiles"
+        );
+
+        let mut smart = smart_tileset(0, 0);
+        smart.tiletable.push(0x0005);
+        let (result, _) = load(smart, LoadMode::Strict);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Tileset 05 tiletable has truncated trailing entry"
+        );
+    }
+
+    #[test]
+    fn test_load_from_smart_lenient() {
+        let mut smart = smart_tileset(0, size_of::<Snes4BppTile>() + 1);
+        smart.tiletable.extend([0x0005, 0x0006]);
+        let (result, warnings) = load(smart, LoadMode::Lenient);
+        let tileset = result.unwrap();
+        assert_eq!(
+            warnings,
+            [
+                "Tileset 05 gfx not evenly divisible as tiles",
+                "Tileset 05 tiletable has truncated trailing entry",
+            ]
+        );
+        assert_eq!(tileset.gfx_len(), 2);
+        assert_eq!(tileset.tiletable.len(), 2);
+        let padded = tileset.tiletable[1].0.map(|tile| tile.to_xml().0);
+        assert_eq!(padded, [0x0005, 0x0006, 0x0000, 0x0000]);
+    }
+
+    #[test]
+    fn test_load_from_smart_palette_too_long() {
+        let smart = smart_xml::Tileset {
+            palette: vec![0x7FFF; 9 * 16],
+            ..smart_tileset(0, size_of::<Snes4BppTile>())
+        };
+        let (result, warnings) = load(smart, LoadMode::Lenient);
+        let tileset = result.unwrap();
+        assert_eq!(
+            warnings,
+            ["Tileset 05 palette has too many (non-blank) lines: color 0x80 on line 8 is in use"]
+        );
+        assert!(tileset.palette.is_empty());
+        assert_eq!(tileset.gfx_len(), 1);
+    }
+
+    #[test]
+    fn test_find_duplicate_blocks() {
+        let mut smart = smart_tileset(0, size_of::<Snes4BppTile>());
+        smart.tiletable = vec![
+            0x0001, 0x0002, 0x0003, 0x0004, // Original
+            0x4002, 0x4001, 0x4004, 0x4003, // Original flipped horizontally
+            0x0001, 0x0002, 0x0003, 0x0005, // Different tile
+            0xC004, 0xC003, 0xC002, 0xC001, // Original flipped both ways
+        ];
+        let (result, _) = load(smart, LoadMode::Strict);
+        let tileset = result.unwrap();
+        let [a, b, c, d] = array::from_fn(|i| &tileset.tiletable[i]);
+        assert!(a.same_visual(b));
+        assert!(b.same_visual(d));
+        assert!(!a.same_visual(c));
+        assert_eq!(tileset.find_duplicate_blocks(), [(0, 1), (0, 3)]);
+    }
+
+    fn layout_tileset(kind: TilesetKind, gfx_tiles: usize, blocks: usize) -> Tileset {
+        let smart = smart_xml::Tileset {
+            tiletable: vec![0; blocks * 4],
+            ..smart_tileset(0, gfx_tiles * size_of::<Snes4BppTile>())
+        };
+        let mut warnings = Vec::new();
+        load_from_smart(
+            kind,
+            0x05,
+            smart,
+            TilesetRef::default(),
+            LoadMode::Strict,
+            &mut warnings,
+        )
+        .unwrap()
+    }
+
+    /// Summarizes layout entries as `(base, size, kind)`, in overlay order.
+    fn layout_entries(layout: &OverlaidLayout<&Tileset>) -> Vec<(usize, usize, TilesetKind)> {
+        layout
+            .entries
+            .iter()
+            .map(|entry| (entry.base, entry.size, entry.tileset.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_sources_layout_sce_and_cre() {
+        let sce = layout_tileset(TilesetKind::Sce, 0x200, 0x100);
+        let cre = layout_tileset(TilesetKind::Cre, 0x180, 0x100);
+        let layout = detect_sources_layout(&sce, Some(&cre));
+        assert_eq!(
+            layout_entries(&layout.gfx),
+            [
+                (0x280, 0x180, TilesetKind::Cre),
+                (0x0, 0x200, TilesetKind::Sce)
+            ]
+        );
+        assert_eq!(
+            layout_entries(&layout.tiletable),
+            [
+                (0x0, 0x100, TilesetKind::Cre),
+                (0x100, 0x100, TilesetKind::Sce)
+            ]
+        );
+        assert!(std::ptr::eq(layout.palette_source, &sce));
+    }
+
+    #[test]
+    fn test_detect_sources_layout_ceres() {
+        let sce = layout_tileset(TilesetKind::Sce, 0x200, 0x301);
+        let cre = layout_tileset(TilesetKind::Cre, 0x180, 0x100);
+        assert!(is_ceres_tileset(&sce));
+        let layout = detect_sources_layout(&sce, Some(&cre));
+        assert_eq!(
+            layout_entries(&layout.tiletable),
+            [(0x0, 0x301, TilesetKind::Sce)]
+        );
+        // The CRE gfx is still loaded, only its tiletable is replaced
+        assert_eq!(
+            layout_entries(&layout.gfx),
+            [
+                (0x280, 0x180, TilesetKind::Cre),
+                (0x0, 0x200, TilesetKind::Sce)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_sources_layout_sce_only() {
+        let sce = layout_tileset(TilesetKind::Sce, 0x200, 0x100);
+        let layout = detect_sources_layout(&sce, None);
+        assert_eq!(
+            layout_entries(&layout.gfx),
+            [(0x0, 0x200, TilesetKind::Sce)]
+        );
+        assert_eq!(
+            layout_entries(&layout.tiletable),
+            [(0x100, 0x100, TilesetKind::Sce)]
+        );
+        assert!(std::ptr::eq(layout.palette_source, &sce));
+    }
+}
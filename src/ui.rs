@@ -1,4 +1,12 @@
+mod clipboard;
+mod debounce;
+mod file_manager;
 mod measurer;
+mod project_drop;
+mod project_watcher;
 pub mod promise;
+pub mod recent_projects;
+pub mod settings;
 mod tile_view;
+mod toasts;
 pub mod views;
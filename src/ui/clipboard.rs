@@ -0,0 +1,17 @@
+use egui::ColorImage;
+use std::borrow::Cow;
+
+/// Places `image` on the system clipboard as an RGBA image.
+pub fn copy_image(image: &ColorImage) -> Result<(), arboard::Error> {
+    let bytes = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect();
+    let [width, height] = image.size;
+    arboard::Clipboard::new()?.set_image(arboard::ImageData {
+        width,
+        height,
+        bytes: Cow::Owned(bytes),
+    })
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+/// Delays an action until its input has stopped changing for a while, e.g. to validate a text
+/// field once the user stops typing instead of on every keystroke. Times are in seconds, as in
+/// egui's `InputState::time`.
+pub struct Debounce {
+    delay: f64,
+    due: Option<f64>,
+}
+
+impl Debounce {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay: delay.as_secs_f64(),
+            due: None,
+        }
+    }
+
+    /// Notes that the input changed at time `now`, restarting the delay.
+    pub fn trigger(&mut self, now: f64) {
+        self.due = Some(now + self.delay);
+    }
+
+    /// Makes the next `poll` fire regardless of the delay.
+    pub fn trigger_now(&mut self, now: f64) {
+        self.due = Some(now);
+    }
+
+    /// Returns whether the delay since the last trigger has passed, at most once per trigger.
+    pub fn poll(&mut self, now: f64) -> bool {
+        if self.due.is_some_and(|due| now >= due) {
+            self.due = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Time left until `poll` fires, if it's waiting to.
+    pub fn remaining(&self, now: f64) -> Option<Duration> {
+        self.due
+            .map(|due| Duration::from_secs_f64((due - now).max(0.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debounce_rapid_edits() {
+        let mut debounce = Debounce::new(Duration::from_millis(200));
+        let mut validated = Vec::new();
+        let mut text = String::new();
+        // A keystroke every 50ms, with frames in between
+        for (i, c) in "C:\\hack".chars().enumerate() {
+            let now = i as f64 * 0.05;
+            text.push(c);
+            debounce.trigger(now);
+            for frame_time in [now, now + 0.025] {
+                if debounce.poll(frame_time) {
+                    validated.push(text.clone());
+                }
+            }
+        }
+        assert!(validated.is_empty());
+
+        let last_edit = 6.0 * 0.05;
+        assert!(!debounce.poll(last_edit + 0.1));
+        assert!(debounce.remaining(last_edit + 0.1).is_some());
+        if debounce.poll(last_edit + 0.2) {
+            validated.push(text.clone());
+        }
+        assert!(!debounce.poll(last_edit + 1.0));
+        assert_eq!(validated, ["C:\\hack"]);
+        assert_eq!(debounce.remaining(last_edit + 1.0), None);
+
+        debounce.trigger_now(5.0);
+        assert!(debounce.poll(5.0));
+    }
+}
@@ -0,0 +1,31 @@
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+
+/// Shows `path` in the system file manager. On Windows and macOS the file or directory is
+/// selected in its parent directory, elsewhere directories are opened and files show their parent.
+pub fn reveal(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("explorer");
+        command.arg("/select,").arg(path);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        let mut command = Command::new("xdg-open");
+        command.arg(dir);
+        command
+    };
+    let mut child = command.spawn()?;
+    // Exit codes aren't meaningful (explorer always fails), but the process still needs reaping
+    thread::spawn(move || child.wait());
+    Ok(())
+}
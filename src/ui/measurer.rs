@@ -1,8 +1,18 @@
 use bitflags::bitflags;
 use egui::{Id, InnerResponse, Rect, Ui, Vec2};
-
+use std::hash::Hash;
+
+/// Remembers the rect taken up by some content on the previous pass, so that the layout before it
+/// can be sized to fit.
+///
+/// Measurements are only known after the content has been laid out once, so querying a measurer
+/// before then returns `None` and requests a discard of the pass, to lay it out again with the
+/// measurement available. A discard is also requested whenever the parts of the rect that were
+/// queried (e.g. only the height) change. [`Measurer::persisted`] avoids the first discard, and
+/// the flicker it can cause, by starting from the rect measured in a previous run.
 pub struct Measurer {
     id: Id,
+    persist: bool,
 }
 
 bitflags! {
@@ -30,17 +40,44 @@ fn rect_changed(a: Rect, b: Rect, consider: UseFlags) -> bool {
 }
 
 impl Measurer {
+    #[expect(unused)]
     pub fn new(ui: &mut Ui) -> Self {
         let id = ui.next_auto_id();
         ui.skip_ahead_auto_ids(1);
 
-        Measurer { id }
+        Measurer { id, persist: false }
+    }
+
+    /// Creates a measurer with a fixed id instead of the next auto id, so that it stays the same
+    /// when widgets before it come and go, and several can be used in the same scope.
+    pub fn with_id(id_salt: impl Hash) -> Self {
+        Measurer {
+            id: Id::new(id_salt),
+            persist: false,
+        }
+    }
+
+    /// Also stores the measured rect in persisted memory, and starts from it when there's no
+    /// measurement from this run yet. Only useful with ids that are stable across runs, like the
+    /// ones from [`Measurer::with_id`].
+    pub fn persisted(self) -> Self {
+        Self {
+            persist: true,
+            ..self
+        }
+    }
+
+    fn persisted_id(&self) -> Option<Id> {
+        self.persist.then(|| self.id.with("persisted_rect"))
     }
 
     fn query(&self, ui: &mut Ui, used_bits: UseFlags) -> Option<Rect> {
+        let persisted_id = self.persisted_id();
         if let Some(r) = ui.data_mut(|data| {
+            let seed = persisted_id.and_then(|id| data.get_persisted::<Rect>(id));
             let s = data.get_temp_mut_or_default::<MeasurerState>(self.id);
             s.used |= used_bits;
+            s.previous_rect = s.previous_rect.or(seed);
             s.previous_rect
         }) {
             Some(r)
@@ -50,7 +87,7 @@ impl Measurer {
         }
     }
 
-    #[expect(unused)]
+    #[cfg_attr(not(test), expect(unused))]
     pub fn query_width(&self, ui: &mut Ui) -> Option<f32> {
         self.query(ui, UseFlags::WIDTH).map(|r| r.width())
     }
@@ -59,7 +96,7 @@ impl Measurer {
         self.query(ui, UseFlags::HEIGHT).map(|r| r.height())
     }
 
-    #[expect(unused)]
+    #[cfg_attr(not(test), expect(unused))]
     pub fn query_size(&self, ui: &mut Ui) -> Option<Vec2> {
         self.query(ui, UseFlags::SIZE).map(|r| r.size())
     }
@@ -77,11 +114,18 @@ impl Measurer {
         let inner_r = ui.scope(add_content);
         let new_rect = inner_r.response.rect;
 
+        let persisted_id = self.persisted_id();
         let invalidated = ui.data_mut(|data| {
             let s = data.get_temp_mut_or_default::<MeasurerState>(self.id);
             let previous = s.previous_rect.replace(new_rect);
-
-            !s.used.is_empty() && previous.is_none_or(|prev| rect_changed(new_rect, prev, s.used))
+            let used = s.used;
+            if let Some(id) = persisted_id
+                && previous != Some(new_rect)
+            {
+                data.insert_persisted(id, new_rect);
+            }
+
+            !used.is_empty() && previous.is_none_or(|prev| rect_changed(new_rect, prev, used))
         });
         if invalidated {
             ui.ctx().request_discard("Used Measurer changed");
@@ -90,3 +134,78 @@ impl Measurer {
         inner_r
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_id_independent() {
+        let ctx = egui::Context::default();
+        let mut heights = (None, None);
+        for _ in 0..3 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let short = Measurer::with_id("test_short");
+                    let tall = Measurer::with_id("test_tall");
+                    heights = (short.query_height(ui), tall.query_height(ui));
+                    short.measure(ui, |ui| ui.allocate_space(Vec2::new(10.0, 20.0)));
+                    tall.measure(ui, |ui| ui.allocate_space(Vec2::new(10.0, 50.0)));
+                });
+            });
+        }
+        assert_eq!(heights, (Some(20.0), Some(50.0)));
+    }
+
+    /// Runs a pass that queries the width and size of a measurer before measuring a 30×40 block,
+    /// and returns the queried values.
+    fn run_width_pass(ctx: &egui::Context, persisted: bool) -> (Option<f32>, Option<Vec2>) {
+        let mut result = (None, None);
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let mut measurer = Measurer::with_id("test_width");
+                if persisted {
+                    measurer = measurer.persisted();
+                }
+                let width = measurer.query_width(ui);
+                let size = measurer.query_size(ui);
+                measurer.measure(ui, |ui| ui.allocate_space(Vec2::new(30.0, 40.0)));
+                result = (width, size);
+            });
+        });
+        result
+    }
+
+    /// Context that runs a single pass per frame even if a discard is requested, so that the
+    /// passes can be checked one at a time.
+    fn single_pass_context() -> egui::Context {
+        let ctx = egui::Context::default();
+        ctx.options_mut(|options| options.max_passes = 1.try_into().unwrap());
+        ctx
+    }
+
+    #[test]
+    fn test_query_width_and_size() {
+        let ctx = single_pass_context();
+        assert_eq!(run_width_pass(&ctx, false), (None, None));
+        assert_eq!(
+            run_width_pass(&ctx, false),
+            (Some(30.0), Some(Vec2::new(30.0, 40.0)))
+        );
+    }
+
+    #[test]
+    fn test_persisted_seed() {
+        let ctx = single_pass_context();
+        run_width_pass(&ctx, true);
+        // Temporary state is lost between runs, while persisted state is restored
+        ctx.data_mut(|data| data.remove_by_type::<MeasurerState>());
+        assert_eq!(
+            run_width_pass(&ctx, true),
+            (Some(30.0), Some(Vec2::new(30.0, 40.0)))
+        );
+
+        ctx.data_mut(|data| data.remove_by_type::<MeasurerState>());
+        assert_eq!(run_width_pass(&ctx, false), (None, None));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::project::validate_smart_project_path;
+use crate::ui::promise::{EguiWaker, Promise};
+use blocking::{Task, unblock};
+use egui::{Align2, Context, FontId, Id, LayerId, Order, StrokeKind};
+use std::path::PathBuf;
+
+/// Opens projects dropped onto the window. Paths are checked with
+/// [`validate_smart_project_path`] in the background, both while hovering, to highlight the window
+/// when the drop would be accepted, and once dropped.
+pub struct ProjectDropTarget {
+    /// Path being dragged over the window, whose check is in `hovered_check`.
+    hovered: Option<PathBuf>,
+    hovered_check: Promise<Task<Result<(), String>>>,
+    /// Check of the last dropped path.
+    dropped: Promise<Task<(PathBuf, Result<(), String>)>>,
+}
+
+impl ProjectDropTarget {
+    pub fn new(ctx: &Context) -> Self {
+        let waker = EguiWaker::for_context(ctx);
+        Self {
+            hovered: None,
+            hovered_check: Promise::new(waker.clone()),
+            dropped: Promise::new(waker),
+        }
+    }
+
+    /// Handles drag and drop input for this frame. Returns the dropped path once it's been
+    /// checked, along with why it isn't a project, if so.
+    pub fn show(&mut self, ctx: &Context) -> Option<(PathBuf, Result<(), String>)> {
+        let (is_hovering, hovered, dropped) = ctx.input(|i| {
+            (
+                !i.raw.hovered_files.is_empty(),
+                i.raw.hovered_files.first().and_then(|f| f.path.clone()),
+                i.raw.dropped_files.first().and_then(|f| f.path.clone()),
+            )
+        });
+
+        if hovered != self.hovered {
+            self.hovered.clone_from(&hovered);
+            match hovered {
+                Some(path) => self
+                    .hovered_check
+                    .launch(unblock(move || validate_smart_project_path(&path))),
+                None => {
+                    self.hovered_check.cancel();
+                    self.hovered_check.take_response();
+                }
+            }
+        }
+        if is_hovering {
+            self.paint_highlight(ctx);
+        }
+
+        if let Some(path) = dropped {
+            self.dropped.launch(unblock(move || {
+                let result = validate_smart_project_path(&path);
+                (path, result)
+            }));
+        }
+        self.dropped.take_response()
+    }
+
+    fn paint_highlight(&mut self, ctx: &Context) {
+        let (text, color) = match self.hovered_check.response() {
+            Some(Ok(())) => (
+                "Drop to open project".to_owned(),
+                ctx.style().visuals.selection.stroke.color,
+            ),
+            Some(Err(e)) => (
+                format!("Not a project: {e}"),
+                ctx.style().visuals.error_fg_color,
+            ),
+            // Still being checked, or a drag the platform doesn't give a path for
+            None => return,
+        };
+        let painter = ctx.layer_painter(LayerId::new(Order::Tooltip, Id::new("project_drop")));
+        let rect = ctx.content_rect();
+        painter.rect_filled(rect, 0.0, color.gamma_multiply(0.1));
+        painter.rect_stroke(rect.shrink(2.0), 0.0, (4.0, color), StrokeKind::Inside);
+        painter.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::proportional(24.0),
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{DroppedFile, RawInput};
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_drop_invalid_path() {
+        let ctx = Context::default();
+        let mut target = ProjectDropTarget::new(&ctx);
+        let mut input = RawInput::default();
+        input.dropped_files.push(DroppedFile {
+            path: Some(PathBuf::from("/nonexistent/smded/project")),
+            ..Default::default()
+        });
+
+        let mut result = None;
+        let _ = ctx.run(input, |ctx| result = target.show(ctx));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while result.is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            let _ = ctx.run(RawInput::default(), |ctx| result = target.show(ctx));
+        }
+        let (path, validation) = result.take().unwrap();
+        assert_eq!(path, Path::new("/nonexistent/smded/project"));
+        assert!(validation.is_err());
+        // The drop is only reported once
+        let _ = ctx.run(RawInput::default(), |ctx| result = target.show(ctx));
+        assert!(result.is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::ui::promise::EguiWaker;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Watches the project's exported data for changes made by other programs, like SMART. Changes
+/// come in bursts (an export rewrites every file), so they're only reported once no more have come
+/// in for a while. Watching stops when this is dropped.
+pub struct ProjectWatcher {
+    _watcher: RecommendedWatcher,
+    /// Time of the last change not yet reported by `poll`.
+    last_change: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ProjectWatcher {
+    const DEBOUNCE: Duration = Duration::from_millis(500);
+    const WATCHED_DIRS: [&str; 3] = ["Export/Rooms", "Export/Tileset", "Export/Maps"];
+
+    pub fn new(ctx: &egui::Context, project_path: &Path) -> notify::Result<Self> {
+        let last_change = Arc::new(Mutex::new(None));
+        let waker = EguiWaker::for_context(ctx);
+        let mut watcher = notify::recommended_watcher({
+            let last_change = last_change.clone();
+            move |event: notify::Result<notify::Event>| match event {
+                Ok(event) => {
+                    let is_change = match event.kind {
+                        EventKind::Create(_) | EventKind::Remove(_) => true,
+                        // Reading the files while loading can touch their access times
+                        EventKind::Modify(kind) => !matches!(kind, ModifyKind::Metadata(_)),
+                        _ => false,
+                    };
+                    if is_change {
+                        *last_change.lock().unwrap() = Some(Instant::now());
+                        waker.wake_by_ref();
+                    }
+                }
+                Err(e) => tracing::warn!("Error watching project directory: {e}"),
+            }
+        })?;
+        for dir in Self::WATCHED_DIRS {
+            let path = project_path.join(dir);
+            if path.is_dir() {
+                watcher.watch(&path, RecursiveMode::Recursive)?;
+            }
+        }
+        Ok(Self {
+            _watcher: watcher,
+            last_change,
+        })
+    }
+
+    /// Returns whether the project changed on disk since the last time this returned `true`. While
+    /// changes are still coming in, returns `false` and schedules a repaint to check again later.
+    pub fn poll(&self, ctx: &egui::Context) -> bool {
+        let mut last_change = self.last_change.lock().unwrap();
+        let Some(time) = *last_change else {
+            return false;
+        };
+        let elapsed = time.elapsed();
+        if elapsed < Self::DEBOUNCE {
+            ctx.request_repaint_after(Self::DEBOUNCE - elapsed);
+            return false;
+        }
+        *last_change = None;
+        true
+    }
+}
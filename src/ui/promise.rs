@@ -1,26 +1,20 @@
-use egui::Id;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task;
 use std::task::{Poll, Waker};
 
+/// Waker that requests a repaint of the egui context it was created for.
 pub struct EguiWaker(egui::Context);
 
 impl EguiWaker {
+    /// Creates a new waker for `ctx`.
+    ///
+    /// Wakers are intentionally not cached in the context's data: since the waker holds a strong
+    /// reference to the context, doing so would create a reference cycle keeping the context (and
+    /// everything stored in it) alive after it's dropped. Creating one is only an allocation, and
+    /// it's only done when launching a `Promise`.
     pub fn for_context(ctx: &egui::Context) -> Waker {
-        if let Some(egui_waker) = ctx.data(|data| data.get_temp::<Arc<EguiWaker>>(Id::NULL)) {
-            Waker::from(egui_waker)
-        } else {
-            let egui_waker = Self::new(ctx.clone());
-            ctx.data_mut(|data| {
-                data.insert_temp(Id::NULL, egui_waker.clone());
-            });
-            Waker::from(egui_waker)
-        }
-    }
-
-    fn new(ctx: egui::Context) -> Arc<EguiWaker> {
-        Arc::new(EguiWaker(ctx))
+        Waker::from(Arc::new(EguiWaker(ctx.clone())))
     }
 }
 impl task::Wake for EguiWaker {
@@ -59,10 +53,21 @@ where
         slf
     }
 
+    /// Starts waiting on `future`, cancelling the previously pending one, if any, like
+    /// [`Promise::cancel`]. The previous result stays available until the new one arrives.
     pub fn launch(&mut self, future: F) {
+        self.cancel();
         self.future = Some(future);
     }
 
+    /// Drops the pending future, if any, discarding its result. Returns whether one was pending.
+    ///
+    /// For a `blocking::Task`, this only cancels the task if it hasn't started running yet. A
+    /// closure that's already running on the thread pool runs to completion in the background.
+    pub fn cancel(&mut self) -> bool {
+        self.future.take().is_some()
+    }
+
     pub fn is_pending(&self) -> bool {
         self.future.is_some()
     }
@@ -91,3 +96,43 @@ where
         self.last_result.take()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Future that never completes, counting how many instances have been dropped.
+    struct DropRecorder(Arc<AtomicUsize>);
+
+    impl Future for DropRecorder {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _cx: &mut task::Context) -> Poll<()> {
+            Poll::Pending
+        }
+    }
+
+    impl Drop for DropRecorder {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_cancel_drops_future() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut promise = Promise::launched(Waker::noop().clone(), DropRecorder(drops.clone()));
+        assert!(promise.response().is_none());
+        assert_eq!(drops.load(Ordering::Relaxed), 0);
+
+        promise.launch(DropRecorder(drops.clone()));
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+        assert!(promise.is_pending());
+
+        assert!(promise.cancel());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+        assert!(!promise.is_pending());
+        assert!(!promise.cancel());
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Key of the list in the eframe storage. Stored separately from the egui memory so that it
+/// survives resetting the UI state.
+const RECENT_PROJECTS_KEY: &str = "recent_projects_v1";
+const MAX_RECENT_PROJECTS: usize = 10;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RecentProject {
+    pub path: PathBuf,
+    pub last_opened: SystemTime,
+}
+
+impl RecentProject {
+    /// Name of the project folder or archive.
+    pub fn name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.to_string_lossy().into_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
+}
+
+/// Projects opened previously, most recently opened first.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct RecentProjects(pub Vec<RecentProject>);
+
+impl RecentProjects {
+    pub fn load(storage: &dyn eframe::Storage) -> Self {
+        let Some(json) = storage.get_string(RECENT_PROJECTS_KEY) else {
+            return Self::default();
+        };
+        let mut recent: Self = serde_json::from_str(&json).unwrap_or_else(|e| {
+            tracing::warn!("Discarding unreadable recent projects list: {e}");
+            Self::default()
+        });
+        recent.0.sort_by_key(|project| Reverse(project.last_opened));
+        recent
+    }
+
+    pub fn store(&self, storage: &mut dyn eframe::Storage) {
+        match serde_json::to_string(self) {
+            Ok(json) => storage.set_string(RECENT_PROJECTS_KEY, json),
+            Err(e) => tracing::error!("Failed to save recent projects list: {e}"),
+        }
+    }
+
+    /// Moves `path` to the top of the list, adding it if needed.
+    pub fn add(&mut self, path: &Path, now: SystemTime) {
+        self.remove(path);
+        self.0.insert(
+            0,
+            RecentProject {
+                path: path.to_owned(),
+                last_opened: now,
+            },
+        );
+        self.0.truncate(MAX_RECENT_PROJECTS);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.0.retain(|project| project.path != path);
+    }
+}
+
+/// Describes how long ago something happened in the largest whole unit, e.g. "5 minutes ago".
+pub fn format_time_ago(elapsed: Duration) -> String {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let secs = elapsed.as_secs();
+    for (unit_secs, unit) in UNITS {
+        let count = secs / unit_secs;
+        if count > 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            return format!("{count} {unit}{plural} ago");
+        }
+    }
+    "Just now".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_time_ago() {
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+        assert_eq!(format_time_ago(Duration::from_secs(59)), "Just now");
+        assert_eq!(format_time_ago(minutes(1)), "1 minute ago");
+        assert_eq!(format_time_ago(minutes(5)), "5 minutes ago");
+        assert_eq!(format_time_ago(minutes(3 * 60 + 59)), "3 hours ago");
+        assert_eq!(format_time_ago(minutes(2 * 24 * 60)), "2 days ago");
+        assert_eq!(format_time_ago(minutes(400 * 24 * 60)), "1 year ago");
+    }
+
+    #[test]
+    fn test_recent_projects_add() {
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut recent = RecentProjects::default();
+        recent.add(Path::new("/hacks/first"), t(1));
+        recent.add(Path::new("/hacks/second.smart"), t(2));
+        recent.add(Path::new("/hacks/first"), t(3));
+        let paths: Vec<_> = recent.0.iter().map(|p| p.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [Path::new("/hacks/first"), Path::new("/hacks/second.smart")]
+        );
+        assert_eq!(recent.0[0].last_opened, t(3));
+        assert_eq!(recent.0[1].name(), "second.smart");
+
+        let json = serde_json::to_string(&recent).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RecentProjects>(&json).unwrap(),
+            recent
+        );
+
+        for i in 0..20 {
+            recent.add(&PathBuf::from(format!("/hacks/{i}")), t(10 + i));
+        }
+        assert_eq!(recent.0.len(), MAX_RECENT_PROJECTS);
+        assert_eq!(recent.0[0].path, Path::new("/hacks/19"));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::smart_xml::LoadMode;
+use crate::ui::tile_view;
+use egui::{Context, Id, ThemePreference};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_KEY: &str = concat!(module_path!(), "::settings");
+
+/// Overlays enabled in newly opened room editors.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomOverlays {
+    pub scroll: bool,
+    pub doors: bool,
+    pub fx: bool,
+    pub enemies: bool,
+    pub bts: bool,
+}
+
+/// User preferences, edited in the `SettingsWindow`. Stored in the egui memory, which eframe saves
+/// along with the rest of the app state.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How projects are loaded, both on startup and when reloading them.
+    pub load_mode: LoadMode,
+    /// Offer to reload the project when its files change on disk.
+    pub watch_project: bool,
+    /// Zoom level of newly opened tileset editors.
+    pub default_zoom: u8,
+    pub room_overlays: RoomOverlays,
+    /// Number of frames that rendered textures are kept for after they were last shown.
+    pub texture_cache_max_age: u32,
+    /// `System` follows the OS preference, also when it changes while running.
+    pub theme: ThemePreference,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            load_mode: LoadMode::Lenient,
+            watch_project: true,
+            default_zoom: 2,
+            room_overlays: RoomOverlays::default(),
+            texture_cache_max_age: tile_view::DEFAULT_TEXTURE_CACHE_MAX_AGE,
+            theme: ThemePreference::System,
+        }
+    }
+}
+
+impl Settings {
+    pub fn get(ctx: &Context) -> Self {
+        ctx.data_mut(|data| data.get_persisted(Id::new(SETTINGS_KEY)))
+            .unwrap_or_default()
+    }
+
+    /// Makes egui use the theme picked in the settings, if it isn't already.
+    pub fn apply_theme(&self, ctx: &Context) {
+        if ctx.options(|opt| opt.theme_preference) != self.theme {
+            ctx.set_theme(self.theme);
+        }
+    }
+
+    pub fn store(self, ctx: &Context) {
+        ctx.data_mut(|data| data.insert_persisted(Id::new(SETTINGS_KEY), self));
+    }
+}
@@ -1,24 +1,37 @@
 mod cache;
 
-use crate::gfx::{GridModel, Palette, Snes4BppTile, TILE_SIZE, TilemapEntry};
-use crate::room::LevelDataEntry;
-use crate::tileset::{LoadedTilesetLayout, OverlaidLayout, Tileset, TiletableEntry};
+use crate::gfx::{
+    GridModel, Palette, PaletteLine4Bpp, Snes4BppTile, SubGridModel, TILE_SIZE, TilemapEntry,
+};
+use crate::hex_types::HexU16;
+use crate::room::{Layer1Model, Layer2Model, LevelDataEntry, Room, RoomRef, SCREEN_BLOCKS};
+use crate::smart_xml::{LayerType, RoomState};
+use crate::tileset::{OverlaidLayout, Tileset, TilesetVramLayout, TiletableEntry};
 use crate::ui::tile_view::cache::{TileCacheKey, TileTextureCache};
 use crate::util::IteratorArrayExt;
+use egui::cache::{ComputerMut, FrameCache};
 use egui::emath::GuiRounding;
 use egui::{
     Color32, ColorImage, Mesh, Rect, Response, Sense, TextureFilter, TextureHandle, TextureOptions,
     Ui, Vec2, pos2,
 };
-use std::{iter, mem};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{array, hint, mem};
+
+pub use cache::{CacheStats, DEFAULT_MAX_AGE as DEFAULT_TEXTURE_CACHE_MAX_AGE};
 
 struct FullTilesetGfxModel {
     len: usize,
     palette_index: usize,
 }
 
+/// Number of tiles per row in the GFX view rendered by [`get_tileset_gfx_texture`].
+pub const GFX_TILES_PER_ROW: usize = 16;
+
 impl FullTilesetGfxModel {
-    const TILES_PER_ROW: usize = 16;
+    const TILES_PER_ROW: usize = GFX_TILES_PER_ROW;
 }
 
 impl GridModel for FullTilesetGfxModel {
@@ -30,8 +43,12 @@ impl GridModel for FullTilesetGfxModel {
 
     fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
         let tile_id = Self::TILES_PER_ROW * y + x;
-        (tile_id < self.len)
-            .then(|| TilemapEntry::for_tile(tile_id).with_palette(self.palette_index))
+        if tile_id >= self.len {
+            return None;
+        }
+        TilemapEntry::try_for_tile(tile_id)
+            .and_then(|entry| entry.try_with_palette(self.palette_index))
+            .ok()
     }
 }
 
@@ -47,20 +64,24 @@ pub fn get_tileset_gfx_texture(
         palette_line,
     };
     TileTextureCache::get_or_insert_with(ctx, cache_key, |ctx, cache_key| {
-        let palette_line = &palette_source.palette.as_4bpp_lines()[usize::from(palette_line)];
-        let palette = iter::once(palette_line.map(Color32::from))
+        let palette = palette_source
+            .palette
+            .to_4bpp_color32_lines()
+            .skip(usize::from(palette_line))
+            .take(1)
             .collect_to_array_padded(|| [Color32::MAGENTA; Palette::LINE_4BPP_LEN]);
 
         let (size, pixels) = Snes4BppTile::tiles_to_image(
             |tile_id| {
                 let (tileset, offset) = gfx_layout.lookup(tile_id)?;
-                tileset.gfx.get(offset)
+                tileset.gfx().get(offset)
             },
             &palette,
             &FullTilesetGfxModel {
                 len: gfx_layout.valid_range().map_or(0, |(_, end)| end),
                 palette_index: 0,
             },
+            false,
         );
         let image = ColorImage::new(size, pixels);
 
@@ -75,12 +96,12 @@ pub fn get_tileset_gfx_texture(
     })
 }
 
-struct BlockTilemapModel<'tileset, Model, F> {
-    blocks: &'tileset Model,
+struct BlockTilemapModel<Model, F> {
+    blocks: Model,
     tiletable_get: F,
 }
 
-impl<Model, F> GridModel for BlockTilemapModel<'_, Model, F>
+impl<Model, F> GridModel for BlockTilemapModel<Model, F>
 where
     Model: GridModel<Item = LevelDataEntry>,
     F: Fn(usize) -> Option<TiletableEntry>,
@@ -117,22 +138,124 @@ where
     }
 }
 
-fn tiletable_to_image(
-    layout: &LoadedTilesetLayout<&Tileset>,
-    model: &impl GridModel<Item = LevelDataEntry>,
-) -> ([usize; 2], Vec<Color32>) {
-    let palettes_c32: [_; TilemapEntry::ADDRESSABLE_PALETTES] = layout
+fn layout_palettes(
+    layout: &TilesetVramLayout<&Tileset>,
+) -> [PaletteLine4Bpp<Color32>; TilemapEntry::ADDRESSABLE_PALETTES] {
+    layout
         .palette_source
         .palette
         .to_4bpp_color32_lines()
-        .collect_to_array_padded(|| [Color32::MAGENTA; Palette::LINE_4BPP_LEN]);
+        .collect_to_array_padded(|| [Color32::MAGENTA; Palette::LINE_4BPP_LEN])
+}
+
+/// Rotation of a range of palette lines, to preview palette animations. Line `first_line + i` of
+/// the range is drawn with the colors of line `first_line + (i + phase) % line_count`.
+#[derive(Copy, Clone, Default, Hash, Eq, PartialEq, Debug)]
+pub struct PaletteRotation {
+    pub first_line: u8,
+    pub line_count: u8,
+    pub phase: u8,
+}
+
+impl PaletteRotation {
+    fn apply(self, palettes: &mut [PaletteLine4Bpp<Color32>]) {
+        let start = usize::from(self.first_line);
+        let end = (start + usize::from(self.line_count)).min(palettes.len());
+        if let Some(lines) = palettes.get_mut(start..end)
+            && !lines.is_empty()
+        {
+            lines.rotate_left(usize::from(self.phase) % lines.len());
+        }
+    }
+
+    /// Suffix for the debugging names of textures rendered with this rotation.
+    pub(super) fn name_suffix(self) -> String {
+        if self == Self::default() {
+            String::new()
+        } else {
+            format!("-rot{}+{}@{}", self.first_line, self.line_count, self.phase)
+        }
+    }
+}
 
+/// Settings of the palette animation preview, which rotates a range of palette lines over time.
+pub struct PaletteAnimation {
+    pub enabled: bool,
+    /// First and last palette line to rotate.
+    pub lines: [u8; 2],
+    /// Steps per second.
+    pub fps: f32,
+}
+
+impl Default for PaletteAnimation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lines: [0, TilemapEntry::ADDRESSABLE_PALETTES as u8 - 1],
+            fps: 4.0,
+        }
+    }
+}
+
+impl PaletteAnimation {
+    pub fn show_controls(&mut self, ui: &mut Ui) {
+        let max_line = TilemapEntry::ADDRESSABLE_PALETTES as u8 - 1;
+        let [first, last] = &mut self.lines;
+        ui.label("Lines");
+        ui.add(egui::DragValue::new(first).range(0..=*last));
+        ui.label("to");
+        ui.add(egui::DragValue::new(last).range(*first..=max_line));
+        ui.add(
+            egui::DragValue::new(&mut self.fps)
+                .range(0.5..=60.0)
+                .speed(0.1)
+                .suffix(" fps"),
+        );
+    }
+
+    /// Number of animation steps elapsed, scheduling a repaint for the next one.
+    pub fn current_step(&self, ctx: &egui::Context) -> usize {
+        let time = ctx.input(|i| i.time);
+        ctx.request_repaint_after(Duration::from_secs_f32(1.0 / self.fps));
+        (time * f64::from(self.fps)) as usize
+    }
+
+    /// Rotation to render with this frame. The identity while disabled.
+    pub fn current_rotation(&self, ctx: &egui::Context) -> PaletteRotation {
+        if !self.enabled {
+            return PaletteRotation::default();
+        }
+        let [first_line, last_line] = self.lines;
+        let line_count = last_line.saturating_sub(first_line) + 1;
+        PaletteRotation {
+            first_line,
+            line_count,
+            phase: (self.current_step(ctx) % usize::from(line_count)) as u8,
+        }
+    }
+}
+
+fn tiletable_to_image(
+    layout: &TilesetVramLayout<&Tileset>,
+    model: &impl GridModel<Item = LevelDataEntry>,
+) -> ([usize; 2], Vec<Color32>) {
+    render_tiletable(layout, model, false, PaletteRotation::default())
+}
+
+fn render_tiletable(
+    layout: &TilesetVramLayout<&Tileset>,
+    model: &impl GridModel<Item = LevelDataEntry>,
+    transparency: bool,
+    rotation: PaletteRotation,
+) -> ([usize; 2], Vec<Color32>) {
+    let mut palettes = layout_palettes(layout);
+    rotation.apply(&mut palettes);
     Snes4BppTile::tiles_to_image(
         |tile_id| {
             let (tileset, offset) = layout.gfx.lookup(tile_id)?;
-            tileset.gfx.get(offset)
+            tileset.gfx().get(offset)
         },
-        &palettes_c32,
+        &palettes,
         &BlockTilemapModel {
             blocks: model,
             tiletable_get: |i| {
@@ -140,6 +263,7 @@ fn tiletable_to_image(
                 tileset.tiletable.get(offset).copied()
             },
         },
+        transparency,
     )
 }
 
@@ -147,8 +271,11 @@ struct FullTiletableModel {
     len: usize,
 }
 
+/// Number of blocks per row in the tiletable view rendered by [`get_tileset_ttb_texture`].
+pub const TILETABLE_BLOCKS_PER_ROW: usize = 32;
+
 impl FullTiletableModel {
-    const BLOCKS_PER_ROW: usize = 32;
+    const BLOCKS_PER_ROW: usize = TILETABLE_BLOCKS_PER_ROW;
 }
 
 impl GridModel for FullTiletableModel {
@@ -163,24 +290,31 @@ impl GridModel for FullTiletableModel {
 
     fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
         let tile_id = Self::BLOCKS_PER_ROW * y + x;
-        (tile_id < self.len).then(|| LevelDataEntry::for_tile(tile_id as u16))
+        if tile_id >= self.len {
+            return None;
+        }
+        LevelDataEntry::try_for_tile(tile_id.try_into().ok()?).ok()
     }
 }
 
 pub fn get_tileset_ttb_texture(
     ctx: &egui::Context,
-    layout: &LoadedTilesetLayout<&Tileset>,
+    layout: &TilesetVramLayout<&Tileset>,
+    rotation: PaletteRotation,
 ) -> TextureHandle {
-    let cache_key = TileCacheKey::LoadedTilesetLayout {
+    let cache_key = TileCacheKey::TilesetVramLayout {
         layout: layout.map_refs(Tileset::handle),
+        rotation,
     };
     TileTextureCache::get_or_insert_with(ctx, cache_key, |ctx, cache_key| {
         let texture_name = cache_key.texture_name();
-        let (size, pixels) = tiletable_to_image(
+        let (size, pixels) = render_tiletable(
             layout,
             &FullTiletableModel {
                 len: layout.tiletable.valid_range().map_or(0, |(_, end)| end),
             },
+            false,
+            rotation,
         );
         let image = ColorImage::new(size, pixels);
 
@@ -195,10 +329,347 @@ pub fn get_tileset_ttb_texture(
     })
 }
 
+/// Cache key for per-layout computations. Hashes by tileset handle, like `TileCacheKey`.
+#[derive(Copy, Clone)]
+struct LayoutKey<'a>(&'a TilesetVramLayout<&'a Tileset>);
+
+impl Hash for LayoutKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.map_refs(Tileset::handle).hash(state);
+    }
+}
+
+#[derive(Default)]
+struct BlockBrightnessComputer;
+
+impl ComputerMut<LayoutKey<'_>, Arc<[f32]>> for BlockBrightnessComputer {
+    fn compute(&mut self, LayoutKey(layout): LayoutKey) -> Arc<[f32]> {
+        const BLOCK_SIZE: usize = TILE_SIZE * 2;
+
+        let len = layout.tiletable.valid_range().map_or(0, |(_, end)| end);
+        let ([width, _], pixels) = tiletable_to_image(layout, &FullTiletableModel { len });
+        (0..len)
+            .map(|block| {
+                let [block_x, block_y] = [
+                    block % TILETABLE_BLOCKS_PER_ROW,
+                    block / TILETABLE_BLOCKS_PER_ROW,
+                ];
+                let mut sum = 0.0;
+                for y in 0..BLOCK_SIZE {
+                    let row_start = (block_y * BLOCK_SIZE + y) * width + block_x * BLOCK_SIZE;
+                    for &pixel in &pixels[row_start..row_start + BLOCK_SIZE] {
+                        // Transparent pixels are shown over a dark background, so count as black
+                        let [r, g, b, a] = pixel.to_array();
+                        if a != 0 {
+                            sum +=
+                                0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+                        }
+                    }
+                }
+                sum / (255.0 * (BLOCK_SIZE * BLOCK_SIZE) as f32)
+            })
+            .collect()
+    }
+}
+
+/// Average perceived brightness (0 to 1) of each block of the tiletable, in the same order as the
+/// texture returned by [`get_tileset_ttb_texture`].
+pub fn get_tiletable_block_brightness(
+    ctx: &egui::Context,
+    layout: &TilesetVramLayout<&Tileset>,
+) -> Arc<[f32]> {
+    ctx.memory_mut(|mem| {
+        mem.caches
+            .cache::<FrameCache<Arc<[f32]>, BlockBrightnessComputer>>()
+            .get(LayoutKey(layout))
+    })
+}
+
+/// Discards the textures rendered for `room`. Must be called after modifying its level data.
+pub fn invalidate_room_textures(ctx: &egui::Context, room: RoomRef) {
+    TileTextureCache::invalidate_room(ctx, room);
+}
+
+/// Discards all cached textures. Must be called when replacing the project data, since the new
+/// rooms and tilesets can reuse the handles of the old ones.
+pub fn clear_textures(ctx: &egui::Context) {
+    TileTextureCache::clear(ctx);
+}
+
+/// Sets how many frames cached textures are kept for after they were last drawn.
+pub fn set_texture_cache_max_age(ctx: &egui::Context, max_age: u32) {
+    TileTextureCache::set_max_age(ctx, max_age);
+}
+
+pub fn texture_cache_stats(ctx: &egui::Context) -> CacheStats {
+    TileTextureCache::stats(ctx)
+}
+
+/// Fraction of the layer 1 scrolling that layer 2 moves by, from a `layer2_xscroll`/`yscroll` value.
+fn layer2_scroll_factor(scroll: u8) -> f32 {
+    match scroll {
+        // Scrolls together with layer 1
+        0x00 => 1.0,
+        // Doesn't scroll
+        0x01 => 0.0,
+        _ => f32::from(scroll & 0xFE) / 256.0,
+    }
+}
+
+/// Size of a room screen, in pixels.
+pub const SCREEN_PIXELS: usize = SCREEN_BLOCKS * TILE_SIZE * 2;
+
+/// Renders screen `screen` of the state's layer 2 as it'd be seen behind that screen of layer 1,
+/// with the camera at the screen's top-left corner, and draws it behind the transparent pixels of
+/// `layer1`.
+fn composite_layer2(
+    layer1: &mut [Color32],
+    layout: &TilesetVramLayout<&Tileset>,
+    state: &RoomState,
+    screen: [usize; 2],
+    rotation: PaletteRotation,
+) {
+    let factors = [state.layer2_xscroll, state.layer2_yscroll].map(|s| layer2_scroll_factor(s.0));
+    // Position of the camera on layer 2, which generally doesn't fall on a block boundary.
+    let origin = array::from_fn::<_, 2, _>(|i| {
+        (screen[i] as f32 * SCREEN_PIXELS as f32 * factors[i]) as usize
+    });
+    let block_size = TILE_SIZE * 2;
+    let layer2 = Layer2Model {
+        level_data: &state.level_data,
+    };
+    // One extra block on each axis covers the part of the screen past the unaligned offset.
+    let ([layer2_width, _], layer2_pixels) = render_tiletable(
+        layout,
+        &SubGridModel {
+            inner: &layer2,
+            origin: origin.map(|p| p / block_size),
+            size: [SCREEN_BLOCKS + 1; 2],
+        },
+        false,
+        rotation,
+    );
+    let [offset_x, offset_y] = origin.map(|p| p % block_size);
+    for (y, row) in layer1.chunks_exact_mut(SCREEN_PIXELS).enumerate() {
+        let layer2_row = &layer2_pixels[(y + offset_y) * layer2_width..];
+        for (pixel, &layer2_pixel) in row.iter_mut().zip(&layer2_row[offset_x..]) {
+            if pixel.a() == 0 {
+                *pixel = layer2_pixel;
+            }
+        }
+    }
+}
+
+/// Renders one screen of the given room state, with layer 2 behind layer 1 if the state has one.
+/// Returns `None` if the room has no such state.
+pub fn get_room_screen_texture(
+    ctx: &egui::Context,
+    room: &Room,
+    state_index: usize,
+    layout: &TilesetVramLayout<&Tileset>,
+    rotation: PaletteRotation,
+    screen: [usize; 2],
+) -> Option<TextureHandle> {
+    let state = room.smart_room.states.get(state_index)?;
+    let cache_key = TileCacheKey::RoomScreen {
+        room: room.handle(),
+        state_index,
+        layout: layout.map_refs(Tileset::handle),
+        rotation,
+        screen,
+    };
+    Some(TileTextureCache::get_or_insert_with(
+        ctx,
+        cache_key,
+        |ctx, cache_key| {
+            let layer1 = Layer1Model {
+                level_data: &state.level_data,
+            };
+            let screen_model = SubGridModel {
+                inner: &layer1,
+                origin: screen.map(|s| s * SCREEN_BLOCKS),
+                size: [SCREEN_BLOCKS; 2],
+            };
+            let has_layer2 =
+                state.layer2_type == LayerType::Layer2 && state.level_data.layer2.is_some();
+            let (size, mut pixels) = render_tiletable(layout, &screen_model, has_layer2, rotation);
+            if has_layer2 {
+                composite_layer2(&mut pixels, layout, state, screen, rotation);
+            }
+            let image = ColorImage::new(size, pixels);
+
+            ctx.load_texture(
+                cache_key.texture_name(),
+                image,
+                TextureOptions {
+                    minification: TextureFilter::Linear,
+                    ..TextureOptions::NEAREST
+                },
+            )
+        },
+    ))
+}
+
+/// Renders a single tile of `gfx_layout` for export, using palette line `palette_line` of
+/// `palette_source`. Color 0 is made transparent.
+pub fn render_gfx_tile(
+    gfx_layout: &OverlaidLayout<&Tileset>,
+    palette_source: &Tileset,
+    palette_line: u8,
+    tile_id: usize,
+) -> Option<ColorImage> {
+    let (tileset, offset) = gfx_layout.lookup(tile_id)?;
+    let tile = tileset.gfx().get(offset)?;
+    let mut palette = palette_source
+        .palette
+        .to_4bpp_color32_lines()
+        .nth(usize::from(palette_line))?;
+    palette[0] = Color32::TRANSPARENT;
+
+    let mut pixels = [[Color32::TRANSPARENT; TILE_SIZE]; TILE_SIZE];
+    tile.write_to_image::<false, false>(&palette, pixels.iter_mut());
+    Some(ColorImage::new(
+        [TILE_SIZE, TILE_SIZE],
+        pixels.as_flattened().to_vec(),
+    ))
+}
+
+/// Renders a single block of the tiletable of `layout` for export. Color 0 of every palette line
+/// is made transparent.
+pub fn render_tiletable_block(
+    layout: &TilesetVramLayout<&Tileset>,
+    block: usize,
+) -> Option<ColorImage> {
+    let len = layout.tiletable.valid_range().map_or(0, |(_, end)| end);
+    if block >= len {
+        return None;
+    }
+    let (size, pixels) = render_tiletable(
+        layout,
+        &SubGridModel {
+            inner: FullTiletableModel { len },
+            origin: [
+                block % TILETABLE_BLOCKS_PER_ROW,
+                block / TILETABLE_BLOCKS_PER_ROW,
+            ],
+            size: [1, 1],
+        },
+        true,
+        PaletteRotation::default(),
+    );
+    Some(ColorImage::new(size, pixels))
+}
+
+/// Frame time statistics collected by [`benchmark_tiletable_render`].
+pub struct RenderBenchmark {
+    pub iterations: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    pub p99: Duration,
+}
+
+/// Repeatedly renders the full tiletable of `layout`, bypassing the texture cache, and reports
+/// how long each render took. Only used as a developer tool for profiling `tiletable_to_image`.
+pub fn benchmark_tiletable_render(
+    layout: &TilesetVramLayout<&Tileset>,
+    iterations: usize,
+) -> RenderBenchmark {
+    let model = FullTiletableModel {
+        len: layout.tiletable.valid_range().map_or(0, |(_, end)| end),
+    };
+    let mut timings: Vec<Duration> = (0..iterations.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            hint::black_box(tiletable_to_image(layout, &model));
+            start.elapsed()
+        })
+        .collect();
+    timings.sort_unstable();
+
+    let percentile = |p: usize| timings[(timings.len() - 1) * p / 100];
+    RenderBenchmark {
+        iterations: timings.len(),
+        mean: timings.iter().sum::<Duration>() / timings.len() as u32,
+        median: percentile(50),
+        p99: percentile(99),
+    }
+}
+
+/// Area map tilemap. The map is stored as a sequence of 32x32 pages, laid out left to right.
+pub struct AreaMapModel<'m> {
+    pub tile_data: &'m [HexU16],
+}
+
+impl AreaMapModel<'_> {
+    pub const PAGE_TILES: usize = 32;
+}
+
+impl GridModel for AreaMapModel<'_> {
+    type Item = TilemapEntry;
+
+    fn dimensions(&self) -> [usize; 2] {
+        let page_len = Self::PAGE_TILES * Self::PAGE_TILES;
+        let pages = self.tile_data.len().div_ceil(page_len);
+        [Self::PAGE_TILES * pages, Self::PAGE_TILES]
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<Self::Item> {
+        let [page, page_x] = [x / Self::PAGE_TILES, x % Self::PAGE_TILES];
+        let i = (page * Self::PAGE_TILES + y) * Self::PAGE_TILES + page_x;
+        self.tile_data.get(i).map(|&HexU16(v)| TilemapEntry(v))
+    }
+}
+
+/// Renders an area map using `gfx_source` for the tile graphics. Since the map tiles aren't
+/// necessarily designed for the palettes of `palette_source`, a grayscale ramp is used if it's
+/// not specified.
+pub fn get_area_map_texture(
+    ctx: &egui::Context,
+    area: u8,
+    tile_data: &[HexU16],
+    gfx_source: &Tileset,
+    palette_source: Option<&Tileset>,
+) -> TextureHandle {
+    let cache_key = TileCacheKey::AreaMap {
+        area,
+        gfx_source: gfx_source.handle(),
+        palette_source: palette_source.map(Tileset::handle),
+    };
+    TileTextureCache::get_or_insert_with(ctx, cache_key, |ctx, cache_key| {
+        let palettes_c32: [_; TilemapEntry::ADDRESSABLE_PALETTES] =
+            if let Some(palette_source) = palette_source {
+                palette_source
+                    .palette
+                    .to_4bpp_color32_lines()
+                    .collect_to_array_padded(|| [Color32::MAGENTA; Palette::LINE_4BPP_LEN])
+            } else {
+                let grayscale = array::from_fn(|i| Color32::from_gray((i * 0x11) as u8));
+                [grayscale; TilemapEntry::ADDRESSABLE_PALETTES]
+            };
+
+        let (size, pixels) = Snes4BppTile::tiles_to_image(
+            |tile_id| gfx_source.gfx().get(tile_id),
+            &palettes_c32,
+            &AreaMapModel { tile_data },
+            false,
+        );
+        let image = ColorImage::new(size, pixels);
+
+        ctx.load_texture(
+            cache_key.texture_name(),
+            image,
+            TextureOptions {
+                minification: TextureFilter::Linear,
+                ..TextureOptions::NEAREST
+            },
+        )
+    })
+}
+
 #[expect(unused)]
 pub fn draw_tiletable_grid(
     ui: &mut Ui,
-    layout: &LoadedTilesetLayout<&Tileset>,
+    layout: &TilesetVramLayout<&Tileset>,
     scale: f32,
 ) -> Response {
     fn scale_rect_by_vec2(rect: Rect, scale: Vec2) -> Rect {
@@ -216,7 +687,7 @@ pub fn draw_tiletable_grid(
     }
 
     let model = BlockTilemapModel {
-        blocks: &FullTiletableModel {
+        blocks: FullTiletableModel {
             len: layout.tiletable.valid_range().map_or(0, |(_, end)| end),
         },
         tiletable_get: |i| {
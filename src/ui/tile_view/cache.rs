@@ -1,4 +1,6 @@
-use crate::tileset::{LoadedTilesetLayout, OverlaidLayout, TilesetRef};
+use crate::room::RoomRef;
+use crate::tileset::{OverlaidLayout, TilesetRef, TilesetVramLayout};
+use crate::ui::tile_view::PaletteRotation;
 use egui::cache::CacheTrait;
 use egui::{Context, TextureHandle};
 use std::any::Any;
@@ -13,8 +15,21 @@ pub enum TileCacheKey {
         palette_source: TilesetRef,
         palette_line: u8,
     },
-    LoadedTilesetLayout {
-        layout: LoadedTilesetLayout<TilesetRef>,
+    TilesetVramLayout {
+        layout: TilesetVramLayout<TilesetRef>,
+        rotation: PaletteRotation,
+    },
+    AreaMap {
+        area: u8,
+        gfx_source: TilesetRef,
+        palette_source: Option<TilesetRef>,
+    },
+    RoomScreen {
+        room: RoomRef,
+        state_index: usize,
+        layout: TilesetVramLayout<TilesetRef>,
+        rotation: PaletteRotation,
+        screen: [usize; 2],
     },
 }
 
@@ -40,19 +55,64 @@ impl TileCacheKey {
                 write!(s, "-pal{palette_line:X}[{palette_source:?}]").unwrap();
                 s
             }
-            TileCacheKey::LoadedTilesetLayout { layout } => {
-                layout_cache_texture_name(&layout.tiletable) + "-ttb"
+            TileCacheKey::TilesetVramLayout { layout, rotation } => {
+                layout_cache_texture_name(&layout.tiletable) + "-ttb" + &rotation.name_suffix()
             }
+            TileCacheKey::AreaMap {
+                area,
+                gfx_source,
+                palette_source,
+            } => format!("areamap{area:X}-gfx[{gfx_source:?}]-pal[{palette_source:?}]"),
+            TileCacheKey::RoomScreen {
+                room,
+                state_index,
+                layout,
+                rotation,
+                screen: [x, y],
+            } => format!(
+                "room[{room:?}]-state{state_index}-screen{x},{y}-{}{}",
+                layout_cache_texture_name(&layout.tiletable),
+                rotation.name_suffix()
+            ),
         }
     }
 }
 
-#[derive(Default)]
+/// Number of eviction passes that textures are kept for by default.
+pub const DEFAULT_MAX_AGE: u32 = 15;
+
+/// Size and eviction counts of the texture cache, for diagnosing memory usage.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct CacheStats {
+    pub len: usize,
+    /// Textures dropped by the latest eviction pass.
+    pub last_evictions: usize,
+    pub total_evictions: usize,
+    /// Approximate GPU memory used by the cached textures, assuming 4 bytes per pixel.
+    pub texture_bytes: usize,
+}
+
 pub struct TileTextureCache {
     /// Incremented every eviction pass
     update_counter: u32,
+    /// Number of eviction passes (i.e. frames) that a texture is kept for after it was last used.
+    max_age: u32,
     /// Tuple contains the value of `update_counter` on last use.
     entries: HashMap<TileCacheKey, (u32, TextureHandle)>,
+    last_evictions: usize,
+    total_evictions: usize,
+}
+
+impl Default for TileTextureCache {
+    fn default() -> Self {
+        Self {
+            update_counter: 0,
+            max_age: DEFAULT_MAX_AGE,
+            entries: HashMap::new(),
+            last_evictions: 0,
+            total_evictions: 0,
+        }
+    }
 }
 
 impl TileTextureCache {
@@ -83,6 +143,36 @@ impl TileTextureCache {
         self.entries.remove(key);
     }
 
+    /// Drops the rendered screens of `room`, so they're rendered again after it's been edited.
+    pub fn invalidate_room(ctx: &Context, room: RoomRef) {
+        Self::for_context(ctx, |cache| {
+            cache.entries.retain(
+                |key, _| !matches!(key, TileCacheKey::RoomScreen { room: r, .. } if *r == room),
+            );
+        });
+    }
+
+    pub fn clear(ctx: &Context) {
+        Self::for_context(ctx, |cache| cache.entries.clear());
+    }
+
+    pub fn set_max_age(ctx: &Context, max_age: u32) {
+        Self::for_context(ctx, |cache| cache.max_age = max_age);
+    }
+
+    pub fn stats(ctx: &Context) -> CacheStats {
+        Self::for_context(ctx, |cache| CacheStats {
+            len: cache.entries.len(),
+            last_evictions: cache.last_evictions,
+            total_evictions: cache.total_evictions,
+            texture_bytes: cache
+                .entries
+                .values()
+                .map(|(_, texture)| texture.byte_size())
+                .sum(),
+        })
+    }
+
     pub fn get_or_insert_with(
         ctx: &Context,
         key: TileCacheKey,
@@ -98,10 +188,11 @@ impl TileTextureCache {
 
 impl CacheTrait for TileTextureCache {
     fn update(&mut self) {
-        const MAX_AGE: u32 = 15;
-
+        let len_before = self.entries.len();
         self.entries
-            .retain(|_, (last_use, _)| last_use.wrapping_sub(self.update_counter) < MAX_AGE);
+            .retain(|_, (last_use, _)| self.update_counter.wrapping_sub(*last_use) < self.max_age);
+        self.last_evictions = len_before - self.entries.len();
+        self.total_evictions += self.last_evictions;
         self.update_counter = self.update_counter.wrapping_add(1);
     }
 
@@ -0,0 +1,144 @@
+use crate::diagnostic::Severity;
+use egui::{Align2, Color32, Context, Frame, Id, Order, RichText, Sense, Ui, vec2};
+
+const ID_SALT: &str = concat!(module_path!(), "::Toasts");
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    /// How long toasts stay up, in seconds. Problems stay up longer so they aren't missed.
+    fn duration(self) -> f64 {
+        match self {
+            ToastLevel::Info | ToastLevel::Success => 4.0,
+            ToastLevel::Warning | ToastLevel::Error => 8.0,
+        }
+    }
+
+    pub fn icon_and_color(self, ui: &Ui) -> (&'static str, Color32) {
+        match self {
+            ToastLevel::Info => ("ℹ", ui.visuals().text_color()),
+            ToastLevel::Success => ("✔", Color32::GREEN),
+            ToastLevel::Warning => ("⚠", ui.visuals().warn_fg_color),
+            ToastLevel::Error => ("❌", ui.visuals().error_fg_color),
+        }
+    }
+}
+
+impl From<Severity> for ToastLevel {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Warning => ToastLevel::Warning,
+            Severity::Error => ToastLevel::Error,
+        }
+    }
+}
+
+struct Toast {
+    level: ToastLevel,
+    text: String,
+    /// Time, in `egui::InputState::time` units, at which the toast is dismissed.
+    expires_at: f64,
+    /// Label of a button that performs an action. Toasts with one stay up until it's clicked or
+    /// they're closed.
+    action: Option<&'static str>,
+}
+
+/// Short-lived notifications shown stacked in the bottom-right corner of the screen. They're
+/// dismissed automatically after a while, or by clicking on them.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    /// Duration of the fade out at the end of a toast's lifetime, in seconds.
+    const FADE_TIME: f64 = 0.5;
+
+    pub fn add(&mut self, ctx: &Context, level: ToastLevel, text: impl Into<String>) {
+        let now = ctx.input(|i| i.time);
+        self.toasts.push(Toast {
+            level,
+            text: text.into(),
+            expires_at: now + level.duration(),
+            action: None,
+        });
+    }
+
+    /// Adds a toast with an `action` button, whose label is returned by `show` when clicked. Replaces
+    /// any toast with the same action that's still up.
+    pub fn add_with_action(
+        &mut self,
+        level: ToastLevel,
+        text: impl Into<String>,
+        action: &'static str,
+    ) {
+        self.toasts.retain(|toast| toast.action != Some(action));
+        self.toasts.push(Toast {
+            level,
+            text: text.into(),
+            expires_at: f64::INFINITY,
+            action: Some(action),
+        });
+    }
+
+    /// Shows the toasts. Returns the action of the toast whose action button was clicked, if any.
+    pub fn show(&mut self, ctx: &Context) -> Option<&'static str> {
+        let now = ctx.input(|i| i.time);
+        self.toasts.retain(|toast| toast.expires_at > now);
+        if self.toasts.is_empty() {
+            return None;
+        }
+        // Keep repainting while toasts are up, to animate the fade out and dismiss them on time
+        ctx.request_repaint();
+
+        let mut dismissed = None;
+        let mut clicked_action = None;
+        egui::Area::new(Id::new(ID_SALT))
+            .order(Order::Foreground)
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-8.0, -8.0))
+            .show(ctx, |ui| {
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    let opacity = ((toast.expires_at - now) / Self::FADE_TIME).min(1.0) as f32;
+                    ui.set_opacity(opacity);
+                    let (icon, color) = toast.level.icon_and_color(ui);
+                    let response = Frame::popup(ui.style())
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new(icon).color(color));
+                                ui.label(&toast.text);
+                                if let Some(action) = toast.action {
+                                    if ui.button(action).clicked() {
+                                        clicked_action = Some(action);
+                                        dismissed = Some(i);
+                                    }
+                                    if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                        dismissed = Some(i);
+                                    }
+                                }
+                            });
+                        })
+                        .response;
+                    // Toasts with buttons can't be clicked as a whole, since that takes priority
+                    // over the buttons
+                    if toast.action.is_none()
+                        && response
+                            .interact(Sense::CLICK)
+                            .on_hover_text("Click to dismiss")
+                            .clicked()
+                    {
+                        dismissed = Some(i);
+                    }
+                }
+            });
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+        clicked_action
+    }
+}
@@ -1,25 +1,189 @@
+mod area_overview;
+mod map_viewer;
 mod room_editor;
+mod search_palette;
+mod settings_window;
+mod sprite_gfx_viewer;
 mod startup_dialog;
 mod tileset_editor;
 mod workspace;
 
+use crate::diagnostic::{Diagnostic, DiagnosticLocation};
 use crate::project::ProjectData;
-use egui::{Context, Id, Response, Ui};
+use crate::room::RoomIndex;
+use crate::tileset::{TilesetIndex, TilesetKind};
+use crate::ui::toasts::ToastLevel;
+use egui::{Context, Id, Pos2, Rect, Response, Ui};
+use serde::{Deserialize, Serialize};
 
 pub use startup_dialog::StartupDialog;
 pub use workspace::Workspace;
 
+/// Identifies the object an editor is open for by its index in the project, rather than by the
+/// slotmap handle, which isn't stable across project loads.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+enum EditorKey {
+    Room(RoomIndex),
+    Tileset(TilesetKind, TilesetIndex),
+    AreaMap(u8),
+    AreaOverview(u8),
+    SpriteGfx(u8),
+}
+
+/// Returns the position to move `rect` to so that it lies inside `bounds`, or `None` if it's at
+/// least partially visible. Saved window positions can end up off-screen if the monitor layout
+/// changed since they were stored.
+fn clamp_into_bounds(rect: Rect, bounds: Rect) -> Option<Pos2> {
+    if bounds.intersects(rect) {
+        return None;
+    }
+    let max_pos = (bounds.max - rect.size()).max(bounds.min);
+    Some(rect.min.clamp(bounds.min, max_pos))
+}
+
+/// Details about the tile under the mouse cursor. Written each frame by whichever editor canvas is
+/// hovered, and displayed by the `Workspace` status bar.
+#[derive(Clone, Default)]
+struct HoverInfo {
+    /// Position of the hovered tile (or block), in tile units.
+    tile: [usize; 2],
+    block: Option<u16>,
+    /// Room screen containing the tile, if hovering a room.
+    screen: Option<[usize; 2]>,
+}
+
+impl HoverInfo {
+    const KEY: &str = "hover_info";
+
+    fn publish(self, ctx: &Context) {
+        ctx.data_mut(|data| data.insert_temp(Id::new(Self::KEY), self));
+    }
+
+    /// Publishes the cell of the grid of `cell_size` squares covering `response` that's hovered,
+    /// if any, with the block ID given by `block`. Returns the hovered cell.
+    fn publish_grid_cell(
+        response: &Response,
+        cell_size: f32,
+        block: impl FnOnce([usize; 2]) -> Option<u16>,
+    ) -> Option<[usize; 2]> {
+        let pointer = response.hover_pos()?;
+        let cell = grid_cell_at(response.rect, cell_size, pointer);
+        HoverInfo {
+            tile: cell,
+            block: block(cell),
+            screen: None,
+        }
+        .publish(&response.ctx);
+        Some(cell)
+    }
+
+    /// Removes and returns the info published since the last call, if any.
+    fn take(ctx: &Context) -> Option<Self> {
+        ctx.data_mut(|data| data.remove_temp(Id::new(Self::KEY)))
+    }
+}
+
+/// Cell of the grid of `cell_size` squares starting at the top left of `rect` that `pos` is in.
+fn grid_cell_at(rect: Rect, cell_size: f32, pos: Pos2) -> [usize; 2] {
+    let cell = ((pos - rect.min) / cell_size).floor();
+    [cell.x as usize, cell.y as usize]
+}
+
+/// Shows a diagnostic with its severity icon. Returns whether its "Go to" button was clicked, which
+/// is only shown if it has a location.
+fn show_diagnostic(ui: &mut Ui, diagnostic: &Diagnostic) -> bool {
+    ui.horizontal(|ui| {
+        let (icon, color) = ToastLevel::from(diagnostic.severity).icon_and_color(ui);
+        ui.colored_label(color, icon);
+        let go_to = diagnostic.location.is_some() && ui.small_button("Go to").clicked();
+        ui.label(&diagnostic.message);
+        go_to
+    })
+    .inner
+}
+
+/// Actions requested by editors that have to be carried out by the `Workspace`.
+#[derive(Default)]
+struct WorkspaceRequests {
+    open_editors: Vec<EditorKey>,
+    toasts: Vec<(ToastLevel, String)>,
+}
+
+impl WorkspaceRequests {
+    fn open_editor(&mut self, key: EditorKey) {
+        self.open_editors.push(key);
+    }
+
+    fn show_toast(&mut self, level: ToastLevel, text: impl Into<String>) {
+        self.toasts.push((level, text.into()));
+    }
+}
+
+/// Commands from the workspace menu bar, carried out by the active editor if it supports them.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum EditorCommand {
+    Undo,
+    Redo,
+    /// Hides every overlay if any is shown, or shows all of them otherwise.
+    ToggleOverlays,
+    ResetZoom,
+    /// Copies a text summary of the room being edited to the clipboard.
+    CopyRoomSummary,
+    /// Shows the part of the project a diagnostic points to.
+    ShowLocation(DiagnosticLocation),
+}
+
+/// Pane of the docked workspace layout.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum DockSide {
+    Left,
+    Right,
+}
+
 trait EditorWindow {
     fn title(&self, project_data: &ProjectData) -> String;
     fn stable_id(&self) -> Id;
-    fn show_contents(&mut self, project_data: &mut ProjectData, ui: &mut Ui);
+    /// Key used to restore this editor when the project is reopened. `None` if it can't be.
+    fn editor_key(&self) -> Option<EditorKey>;
+    /// Pane this editor is shown in when the workspace uses the docked layout. Editors without one
+    /// always float.
+    fn dock_side(&self) -> Option<DockSide> {
+        None
+    }
+    fn show_contents(
+        &mut self,
+        project_data: &mut ProjectData,
+        requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    );
+    /// Carries out a menu bar command. Returns `false` if the editor doesn't support it.
+    fn run_command(
+        &mut self,
+        _command: EditorCommand,
+        _project_data: &mut ProjectData,
+        _ctx: &Context,
+    ) -> bool {
+        false
+    }
 
-    fn show_window(&mut self, project_data: &mut ProjectData, ctx: &Context) -> Option<Response> {
+    fn show_window(
+        &mut self,
+        project_data: &mut ProjectData,
+        requests: &mut WorkspaceRequests,
+        ctx: &Context,
+    ) -> Option<Response> {
+        let id = self.stable_id();
         let mut stay_open = true;
-        egui::Window::new(self.title(project_data))
-            .id(self.stable_id())
-            .open(&mut stay_open)
-            .show(ctx, |ui| self.show_contents(project_data, ui))
+        let mut window = egui::Window::new(self.title(project_data))
+            .id(id)
+            .open(&mut stay_open);
+        if let Some(rect) = ctx.memory(|mem| mem.area_rect(id))
+            && let Some(pos) = clamp_into_bounds(rect, ctx.content_rect())
+        {
+            window = window.current_pos(pos);
+        }
+        window
+            .show(ctx, |ui| self.show_contents(project_data, requests, ui))
             .filter(|_| stay_open)
             .map(|inner_r| inner_r.response)
     }
@@ -0,0 +1,286 @@
+use crate::hex_types::HexU8;
+use crate::project::ProjectData;
+use crate::room::{Room, RoomRef};
+use crate::ui::views::{EditorKey, EditorWindow, WorkspaceRequests};
+use egui::{Align2, Color32, FontId, Id, Pos2, Rect, Sense, Stroke, StrokeKind, Ui, Vec2, vec2};
+use std::collections::BTreeSet;
+
+const ID_SALT: &str = concat!(module_path!(), "::AreaOverview");
+
+/// Size of the area map grid, in screens.
+const MAP_SIZE_SCREENS: [u8; 2] = [64, 32];
+/// Size each screen is drawn as at 1× zoom, in points.
+const SCREEN_CELL_SIZE: f32 = 16.0;
+
+struct RoomDrag {
+    room: RoomRef,
+    /// Accumulated unsnapped pointer movement since the drag started.
+    offset: Vec2,
+}
+
+/// Spatial overview of the rooms in an area, laid out at their position in the area map.
+pub struct AreaOverview {
+    area: u8,
+    zoom: f32,
+    drag: Option<RoomDrag>,
+}
+
+fn room_rect(origin: Pos2, cell_size: f32, [x, y]: [u8; 2], room: &Room) -> Rect {
+    Rect::from_min_size(
+        origin + vec2(f32::from(x), f32::from(y)) * cell_size,
+        vec2(
+            f32::from(room.width_screens),
+            f32::from(room.height_screens),
+        ) * cell_size,
+    )
+}
+
+/// Returns true if `room`, if placed at `pos`, would overlap with `other`.
+fn rooms_overlap(room: &Room, [x, y]: [u8; 2], other: &Room) -> bool {
+    let overlaps_1d = |a: u8, a_len: u8, b: u8, b_len: u8| {
+        u16::from(a) < u16::from(b) + u16::from(b_len)
+            && u16::from(b) < u16::from(a) + u16::from(a_len)
+    };
+    overlaps_1d(x, room.width_screens, other.map_x, other.width_screens)
+        && overlaps_1d(y, room.height_screens, other.map_y, other.height_screens)
+}
+
+impl AreaOverview {
+    const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+
+    pub fn new(area: u8) -> Self {
+        Self {
+            area,
+            zoom: 1.0,
+            drag: None,
+        }
+    }
+
+    fn area_rooms(project_data: &ProjectData, area: u8) -> impl Iterator<Item = &Room> {
+        project_data
+            .rooms
+            .values()
+            .filter(move |room| room.index().is_some_and(|(a, _)| a == area))
+    }
+
+    fn cell_size(&self) -> f32 {
+        SCREEN_CELL_SIZE * self.zoom
+    }
+
+    /// Position `room` would be moved to if the current drag was released, snapped to screens and
+    /// kept within the map.
+    fn snapped_position(&self, room: &Room, offset: Vec2) -> [u8; 2] {
+        let snap = |pos: u8, delta: f32, map_size: u8, room_size: u8| {
+            let max = map_size.saturating_sub(room_size);
+            (f32::from(pos) + delta / self.cell_size())
+                .round()
+                .clamp(0.0, f32::from(max)) as u8
+        };
+        [
+            snap(
+                room.map_x,
+                offset.x,
+                MAP_SIZE_SCREENS[0],
+                room.width_screens,
+            ),
+            snap(
+                room.map_y,
+                offset.y,
+                MAP_SIZE_SCREENS[1],
+                room.height_screens,
+            ),
+        ]
+    }
+
+    fn show_toolbar(
+        &mut self,
+        project_data: &ProjectData,
+        requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Area:");
+            let areas: BTreeSet<u8> = project_data
+                .rooms
+                .values()
+                .filter_map(|room| Some(room.index()?.0))
+                .collect();
+            egui::ComboBox::from_id_salt("area_selector")
+                .selected_text(format!("Area {}", HexU8(self.area)))
+                .show_ui(ui, |ui| {
+                    for area in areas {
+                        ui.selectable_value(&mut self.area, area, format!("Area {}", HexU8(area)));
+                    }
+                });
+
+            ui.label("Zoom:");
+            ui.add(egui::Slider::new(&mut self.zoom, Self::ZOOM_RANGE).suffix("×"));
+
+            if ui.button("Enemy GFX Pointers…").clicked() {
+                requests.open_editor(EditorKey::SpriteGfx(self.area));
+            }
+        });
+    }
+
+    fn show_drag_status(&self, project_data: &ProjectData, ui: &mut Ui) {
+        let dragged_room = self
+            .drag
+            .as_ref()
+            .and_then(|drag| Some((project_data.rooms.get(drag.room)?, drag.offset)));
+        if let Some((room, offset)) = dragged_room {
+            let new_pos = self.snapped_position(room, offset);
+            let overlapping: Vec<_> = Self::area_rooms(project_data, self.area)
+                .filter(|other| {
+                    other.handle() != room.handle() && rooms_overlap(room, new_pos, other)
+                })
+                .map(Room::title)
+                .collect();
+            if overlapping.is_empty() {
+                ui.label(format!(
+                    "Moving {} to ({}, {})",
+                    room.title(),
+                    new_pos[0],
+                    new_pos[1]
+                ));
+            } else {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("⚠ Overlaps with: {}", overlapping.join(", ")),
+                );
+            }
+        } else {
+            ui.label("Click a room to open it, or drag it to reposition it on the map.");
+        }
+    }
+}
+
+impl EditorWindow for AreaOverview {
+    fn title(&self, _project_data: &ProjectData) -> String {
+        format!("Area Overview: {}", HexU8(self.area))
+    }
+
+    fn stable_id(&self) -> Id {
+        // Not keyed by area, since the area can be switched from within the window
+        Id::new(ID_SALT)
+    }
+
+    fn editor_key(&self) -> Option<EditorKey> {
+        Some(EditorKey::AreaOverview(self.area))
+    }
+
+    fn show_contents(
+        &mut self,
+        project_data: &mut ProjectData,
+        requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        self.show_toolbar(project_data, requests, ui);
+        self.show_drag_status(project_data, ui);
+
+        let mut drop_action = None;
+        egui::ScrollArea::both()
+            .max_width(f32::INFINITY)
+            .max_height(f32::INFINITY)
+            .show(ui, |ui| {
+                let cell_size = self.cell_size();
+                let canvas_size = Vec2::from(MAP_SIZE_SCREENS.map(f32::from)) * cell_size;
+                let (canvas, painter) = ui.allocate_painter(canvas_size, Sense::hover());
+                let origin = canvas.rect.min;
+
+                let grid_stroke = Stroke::new(1.0, ui.visuals().faint_bg_color);
+                for x in 0..=MAP_SIZE_SCREENS[0] {
+                    painter.vline(
+                        origin.x + f32::from(x) * cell_size,
+                        canvas.rect.y_range(),
+                        grid_stroke,
+                    );
+                }
+                for y in 0..=MAP_SIZE_SCREENS[1] {
+                    painter.hline(
+                        canvas.rect.x_range(),
+                        origin.y + f32::from(y) * cell_size,
+                        grid_stroke,
+                    );
+                }
+
+                let room_fill = ui.visuals().selection.bg_fill.gamma_multiply(0.5);
+                let room_stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+                let label_font = FontId::proportional((7.0 * self.zoom).clamp(6.0, 14.0));
+                for room in Self::area_rooms(project_data, self.area) {
+                    let rect = room_rect(origin, cell_size, [room.map_x, room.map_y], room);
+                    let response = ui
+                        .interact(
+                            rect,
+                            self.stable_id().with(room.handle()),
+                            Sense::click_and_drag(),
+                        )
+                        .on_hover_text(room.title());
+                    painter.rect(rect, 0, room_fill, room_stroke, StrokeKind::Inside);
+                    painter.with_clip_rect(rect.shrink(1.0)).text(
+                        rect.left_top() + vec2(2.0, 1.0),
+                        Align2::LEFT_TOP,
+                        &room.name,
+                        label_font.clone(),
+                        ui.visuals().text_color(),
+                    );
+
+                    if response.clicked()
+                        && let Some(index) = room.index()
+                    {
+                        requests.open_editor(EditorKey::Room(index));
+                    }
+                    if response.drag_started() {
+                        self.drag = Some(RoomDrag {
+                            room: room.handle(),
+                            offset: Vec2::ZERO,
+                        });
+                    }
+                    let Some(drag) = self.drag.as_mut().filter(|d| d.room == room.handle()) else {
+                        continue;
+                    };
+                    drag.offset += response.drag_delta();
+                    let offset = drag.offset;
+
+                    let new_pos = self.snapped_position(room, offset);
+                    let overlaps = Self::area_rooms(project_data, self.area).any(|other| {
+                        other.handle() != room.handle() && rooms_overlap(room, new_pos, other)
+                    });
+                    let ghost_color = if overlaps {
+                        Color32::RED
+                    } else {
+                        Color32::GREEN
+                    };
+                    painter.rect_stroke(
+                        room_rect(origin, cell_size, new_pos, room),
+                        0,
+                        (2.0, ghost_color),
+                        StrokeKind::Inside,
+                    );
+
+                    if response.drag_stopped() {
+                        drop_action = Some((room.handle(), new_pos));
+                    }
+                }
+
+                // Ctrl+scroll to zoom while hovering the map
+                if canvas.contains_pointer() {
+                    let zoom_delta = ui.input(|i| i.zoom_delta());
+                    if zoom_delta != 1.0 {
+                        self.zoom = (self.zoom * zoom_delta)
+                            .clamp(*Self::ZOOM_RANGE.start(), *Self::ZOOM_RANGE.end());
+                    }
+                }
+            });
+
+        if let Some((room_ref, [x, y])) = drop_action {
+            self.drag = None;
+            if let Some(room) = project_data.rooms.get_mut(room_ref)
+                && [room.map_x, room.map_y] != [x, y]
+            {
+                room.map_x = x;
+                room.map_y = y;
+                room.mark_dirty();
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use crate::gfx::{GridModel, TILE_SIZE};
+use crate::hex_types::{HexU8, HexU16};
+use crate::project::ProjectData;
+use crate::smart_xml::{Icon, Map};
+use crate::tileset::{Tileset, TilesetKind, TilesetRef};
+use crate::ui::tile_view;
+use crate::ui::tile_view::AreaMapModel;
+use crate::ui::views::{EditorKey, EditorWindow, WorkspaceRequests};
+use egui::ecolor::Hsva;
+use egui::emath::GuiRounding;
+use egui::load::SizedTexture;
+use egui::{
+    Align, Align2, Color32, FontId, Id, Painter, Pos2, Rect, Response, Sense, Stroke, StrokeKind,
+    Ui, vec2,
+};
+
+const ID_SALT: &str = concat!(module_path!(), "::MapViewer");
+
+pub struct MapViewer {
+    area: u8,
+    /// Tileset used as the source of the map tile graphics.
+    gfx_source: Option<TilesetRef>,
+    /// Tileset to take palettes from. Grayscale is used if unset.
+    palette_source: Option<TilesetRef>,
+    show_overlays: bool,
+    /// Map tile last clicked by the user, in tile coordinates.
+    selected_tile: Option<[usize; 2]>,
+    /// Set when `selected_tile` was changed from outside the viewer, to scroll it into view.
+    scroll_to_selected: bool,
+}
+
+fn find_default_gfx_source(project_data: &ProjectData) -> Option<&Tileset> {
+    project_data
+        .tilesets
+        .values()
+        .filter(|tileset| tileset.kind == TilesetKind::Cre)
+        .min_by(|a, b| a.display_cmp(b))
+}
+
+fn tileset_combo(
+    ui: &mut Ui,
+    id_salt: &str,
+    project_data: &ProjectData,
+    selected: &mut Option<TilesetRef>,
+    filter: impl Fn(&Tileset) -> bool,
+    none_text: &str,
+) {
+    let selected_text = selected
+        .and_then(|hnd| project_data.tilesets.get(hnd))
+        .map_or(none_text.into(), Tileset::title);
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(selected, None, none_text);
+            let mut tilesets: Vec<_> = project_data
+                .tilesets
+                .values()
+                .filter(|t| filter(t))
+                .collect();
+            tilesets.sort_by(|a, b| a.display_cmp(b));
+            for tileset in tilesets {
+                ui.selectable_value(selected, Some(tileset.handle()), tileset.title());
+            }
+        });
+}
+
+impl MapViewer {
+    pub fn new(area: u8, project_data: &ProjectData) -> Self {
+        Self {
+            area,
+            gfx_source: find_default_gfx_source(project_data).map(Tileset::handle),
+            palette_source: None,
+            show_overlays: true,
+            selected_tile: None,
+            scroll_to_selected: false,
+        }
+    }
+
+    fn focus_request_id(area: u8) -> Id {
+        Id::new(ID_SALT).with(area).with("focus_tile")
+    }
+
+    /// Makes the viewer for `area` select and scroll to `tile` next time it's shown, whether it's
+    /// already open or about to be opened.
+    pub fn request_focus(ctx: &egui::Context, area: u8, tile: [usize; 2]) {
+        ctx.data_mut(|data| data.insert_temp(Self::focus_request_id(area), tile));
+    }
+
+    /// Fallback rendering used when no gfx source is selected: each map tile is drawn as a cell
+    /// with a color derived from its tile number, so the map layout is still recognizable.
+    fn draw_tile_cells(ui: &mut Ui, scale: f32, map: &Map) -> Response {
+        let model = AreaMapModel {
+            tile_data: &map.tile_data,
+        };
+        let [width, height] = model.dimensions();
+        let tile_size = TILE_SIZE as f32 * scale;
+        let (response, painter) =
+            ui.allocate_painter(vec2(width as f32, height as f32) * tile_size, Sense::CLICK);
+        let origin = response.rect.min;
+        for y in 0..height {
+            for x in 0..width {
+                let Some(entry) = model.get(x, y) else {
+                    continue;
+                };
+                // Spread consecutive tile numbers around the hue wheel
+                let hue = (entry.tile_id() as f32 * 0.618_034).fract();
+                let rect = Rect::from_min_size(
+                    origin + vec2(x as f32, y as f32) * tile_size,
+                    vec2(tile_size, tile_size),
+                );
+                painter.rect_filled(rect, 0, Hsva::new(hue, 0.6, 0.7, 1.0));
+            }
+        }
+        response
+    }
+
+    fn draw_overlays(painter: &Painter, origin: Pos2, scale: f32, map: &Map) {
+        let to_screen =
+            |x: HexU16, y: HexU16| origin + vec2(f32::from(x.0), f32::from(y.0)) * scale;
+        let font = FontId::monospace(8.0 * scale.max(1.0));
+
+        for label in &map.area_labels {
+            let pos = to_screen(label.x, label.y);
+            let text_rect = painter.text(
+                pos,
+                Align2::LEFT_TOP,
+                format!("{}", label.gfx),
+                font.clone(),
+                Color32::WHITE,
+            );
+            painter.rect_stroke(text_rect, 0, (1.0, Color32::WHITE), StrokeKind::Outside);
+        }
+
+        let icon_sets: [(&[Icon], Color32, &str); 5] = [
+            (&map.boss_icons, Color32::RED, "B"),
+            (&map.missile_icons, Color32::ORANGE, "M"),
+            (&map.energy_icons, Color32::YELLOW, "E"),
+            (&map.map_icons, Color32::LIGHT_GREEN, "Mp"),
+            (&map.save_icons, Color32::LIGHT_BLUE, "S"),
+        ];
+        for (icons, color, letter) in icon_sets {
+            for icon in icons {
+                let center = to_screen(icon.x, icon.y);
+                painter.circle(
+                    center,
+                    4.0 * scale,
+                    color.gamma_multiply(0.5),
+                    Stroke::new(1.0, color),
+                );
+                painter.text(
+                    center,
+                    Align2::CENTER_CENTER,
+                    letter,
+                    font.clone(),
+                    Color32::WHITE,
+                );
+            }
+        }
+    }
+}
+
+impl EditorWindow for MapViewer {
+    fn title(&self, _project_data: &ProjectData) -> String {
+        format!("Area Map: {}", HexU8(self.area))
+    }
+
+    fn stable_id(&self) -> Id {
+        Id::new(ID_SALT).with(self.area)
+    }
+
+    fn editor_key(&self) -> Option<EditorKey> {
+        Some(EditorKey::AreaMap(self.area))
+    }
+
+    fn show_contents(
+        &mut self,
+        project_data: &mut ProjectData,
+        _requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        let Some(map) = project_data.area_maps.get(&self.area) else {
+            ui.close();
+            return;
+        };
+        if let Some(tile) = ui.data_mut(|data| data.remove_temp(Self::focus_request_id(self.area)))
+        {
+            self.selected_tile = Some(tile);
+            self.scroll_to_selected = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("GFX:");
+            tileset_combo(
+                ui,
+                "gfx_source",
+                project_data,
+                &mut self.gfx_source,
+                |_| true,
+                "Colored cells",
+            );
+            ui.label("Palette:");
+            tileset_combo(
+                ui,
+                "palette_source",
+                project_data,
+                &mut self.palette_source,
+                |t| !t.palette.is_empty(),
+                "Grayscale",
+            );
+            ui.checkbox(&mut self.show_overlays, "Show icons");
+        });
+
+        if let Some([x, y]) = self.selected_tile {
+            let model = AreaMapModel {
+                tile_data: &map.tile_data,
+            };
+            if let Some(entry) = model.get(x, y) {
+                ui.label(format!("Tile ({x}, {y}): {}", HexU16(entry.0)));
+            }
+        } else {
+            ui.label("Click a map tile to inspect it.");
+        }
+
+        let gfx_source = self
+            .gfx_source
+            .and_then(|hnd| project_data.tilesets.get(hnd));
+        let palette_source = self
+            .palette_source
+            .and_then(|hnd| project_data.tilesets.get(hnd));
+
+        egui::ScrollArea::both()
+            .max_width(f32::INFINITY)
+            .max_height(f32::INFINITY)
+            .show(ui, |ui| {
+                let scale_factor = 2.0.round_to_pixels(ui.pixels_per_point());
+                let response = if let Some(gfx_source) = gfx_source {
+                    let tex_handle = tile_view::get_area_map_texture(
+                        ui.ctx(),
+                        self.area,
+                        &map.tile_data,
+                        gfx_source,
+                        palette_source,
+                    );
+                    ui.add(
+                        egui::Image::new(SizedTexture::from_handle(&tex_handle))
+                            .fit_to_original_size(scale_factor)
+                            .sense(Sense::CLICK),
+                    )
+                } else {
+                    Self::draw_tile_cells(ui, scale_factor, map)
+                };
+                let origin = response.rect.min;
+                let tile_size = TILE_SIZE as f32 * scale_factor;
+
+                if response.clicked()
+                    && let Some(pointer) = response.interact_pointer_pos()
+                {
+                    let tile_pos = ((pointer - origin) / tile_size).floor();
+                    self.selected_tile = Some([tile_pos.x as usize, tile_pos.y as usize]);
+                }
+
+                let painter = ui.painter_at(response.rect);
+                if self.show_overlays {
+                    Self::draw_overlays(&painter, origin, scale_factor, map);
+                }
+                if let Some([x, y]) = self.selected_tile {
+                    let rect = Rect::from_min_size(
+                        origin + vec2(x as f32, y as f32) * tile_size,
+                        vec2(tile_size, tile_size),
+                    );
+                    painter.rect_stroke(rect, 0, (2.0, Color32::WHITE), StrokeKind::Outside);
+                    if self.scroll_to_selected {
+                        ui.scroll_to_rect(rect, Some(Align::Center));
+                        self.scroll_to_selected = false;
+                    }
+                }
+            });
+    }
+}
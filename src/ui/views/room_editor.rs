@@ -1,37 +1,1209 @@
+use crate::diagnostic::DiagnosticLocation;
+use crate::door_code;
+use crate::gfx::{GridModel, TILE_SIZE};
+use crate::hex_types::{HexU8, HexU16};
 use crate::project::ProjectData;
-use crate::room::RoomRef;
-use crate::ui::views::EditorWindow;
-use egui::{Id, Ui};
+use crate::room::{
+    BlockSelection, BlockType, BlocksEdit, BtsEdit, DoorEdit, Layer1Model, LevelDataEntry, Liquid,
+    MapDirection, Room, RoomEdit, RoomIndex, RoomProperties, RoomPropertiesEdit, RoomRef,
+    SCREEN_BLOCKS, fx_surface_y,
+};
+use crate::smart_xml::{DoorCode, DoorEntry, Fx1, RoomState};
+use crate::snes_constants::SpecialGfxFlags;
+use crate::tileset::{Tileset, TilesetVramLayout};
+use crate::ui::settings::{RoomOverlays, Settings};
+use crate::ui::tile_view;
+use crate::ui::tile_view::{PaletteAnimation, PaletteRotation};
+use crate::ui::views;
+use crate::ui::views::tileset_editor::find_default_cre;
+use crate::ui::views::{
+    DockSide, EditorCommand, EditorKey, EditorWindow, HoverInfo, WorkspaceRequests, grid_cell_at,
+};
+use crate::undo::{Edit, UndoHistory};
+use crate::{snes_constants, tileset};
+use bit_field::BitField;
+use egui::{
+    Align2, Color32, DragValue, FontId, Id, Key, KeyboardShortcut, Modifiers, Painter, Pos2, Rect,
+    Response, Sense, StrokeKind, Ui, Vec2, Widget, pos2, vec2,
+};
 
 const ID_SALT: &str = concat!(module_path!(), "::RoomEditor");
+pub(super) const UNDO: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+pub(super) const REDO: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+/// Blocks copied from a room, shared between all room editors.
+const BLOCK_CLIPBOARD_KEY: &str = "block_clipboard";
+
+/// Rectangle of blocks in a room, in block units.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct BlockRect {
+    origin: [usize; 2],
+    size: [usize; 2],
+}
+
+impl BlockRect {
+    /// Rectangle spanning both `a` and `b`, inclusive.
+    fn from_corners(a: [usize; 2], b: [usize; 2]) -> Self {
+        let origin = [a[0].min(b[0]), a[1].min(b[1])];
+        let size = [a[0].abs_diff(b[0]) + 1, a[1].abs_diff(b[1]) + 1];
+        Self { origin, size }
+    }
+
+    fn to_ui_rect(self, room_origin: Pos2) -> Rect {
+        let block_size = (TILE_SIZE * 2) as f32;
+        let [x, y] = self.origin.map(|v| v as f32);
+        let [w, h] = self.size.map(|v| v as f32);
+        Rect::from_min_size(
+            room_origin + vec2(x, y) * block_size,
+            vec2(w, h) * block_size,
+        )
+    }
+}
+
+/// Block whose BTS is being edited through the BTS overlay's popup.
+struct BtsPopup {
+    block: [usize; 2],
+    /// Screen position the popup is shown at, next to the block. Updated as the canvas scrolls.
+    anchor: Pos2,
+}
+
+/// Block operation requested through the UI. Carried out after the canvas has been drawn, when
+/// the room can be modified.
+enum BlockAction {
+    /// Makes the block at the given position the current block.
+    Pick([usize; 2]),
+    Fill,
+    Delete,
+    Copy,
+    FlipH,
+    FlipV,
+    /// Starts previewing the clipboard contents under the cursor.
+    Paste,
+    /// Places the previewed blocks with their top-left corner at the given position.
+    CommitPaste([usize; 2]),
+}
 
 pub struct RoomEditor {
     room: RoomRef,
+    /// Cached from `room` so the window identity doesn't depend on the slotmap handle.
+    room_index: Option<RoomIndex>,
+    /// Index of the room state being displayed.
+    state_index: usize,
+    overlays: RoomOverlays,
+    palette_animation: PaletteAnimation,
+    /// Index into the state's FX1s of the entry previewed by the FX overlay. `None` previews the
+    /// default one.
+    fx_index: Option<usize>,
+    /// Index of the door shown in the door editor panel.
+    selected_door: Option<usize>,
+    bts_popup: Option<BtsPopup>,
+    history: UndoHistory<RoomEdit>,
+
+    /// Block placed by the fill operation. Picked by right-clicking a block.
+    current_block: LevelDataEntry,
+    /// Block the marquee was started from, while Shift-dragging a selection.
+    marquee_start: Option<[usize; 2]>,
+    selection: Option<BlockRect>,
+    /// Blocks being pasted, which follow the cursor until clicking to place them.
+    paste_preview: Option<BlockSelection>,
+}
+
+/// Room state sized to the whole room, meant to be placed in a `ScrollArea`. Only the screens
+/// that overlap the scroll area's visible region are drawn, so large rooms don't cost more to show
+/// than small ones.
+struct RoomCanvas<'a> {
+    room: &'a Room,
+    state_index: usize,
+    layout: &'a TilesetVramLayout<&'a Tileset>,
+    rotation: PaletteRotation,
+}
+
+impl Widget for RoomCanvas<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let Some(state) = self.room.smart_room.states.get(self.state_index) else {
+            return ui.response();
+        };
+        let screens = [state.level_data.width.0, state.level_data.height.0].map(usize::from);
+        let screen_size = Vec2::splat(tile_view::SCREEN_PIXELS as f32);
+        let (rect, response) = ui.allocate_exact_size(
+            vec2(screens[0] as f32, screens[1] as f32) * screen_size,
+            Sense::click_and_drag(),
+        );
+        // Inside a `ScrollArea`, the clip rect is its inner rect, i.e. the visible part of the
+        // canvas.
+        let visible_rect = ui.clip_rect();
+        let painter = ui.painter_at(rect);
+        for screen_y in 0..screens[1] {
+            for screen_x in 0..screens[0] {
+                let screen_rect = Rect::from_min_size(
+                    rect.min + vec2(screen_x as f32, screen_y as f32) * screen_size,
+                    screen_size,
+                );
+                if !visible_rect.intersects(screen_rect) {
+                    continue;
+                }
+                if let Some(texture) = tile_view::get_room_screen_texture(
+                    ui.ctx(),
+                    self.room,
+                    self.state_index,
+                    self.layout,
+                    self.rotation,
+                    [screen_x, screen_y],
+                ) {
+                    painter.image(
+                        texture.id(),
+                        screen_rect,
+                        Rect::from_min_max(Pos2::ZERO, pos2(1.0, 1.0)),
+                        Color32::WHITE,
+                    );
+                }
+            }
+        }
+        response
+    }
 }
 
+/// Names of the values of the low 2 bits of a door's direction.
+const DOOR_DIRECTIONS: [&str; 4] = ["Right", "Left", "Down", "Up"];
+
 impl RoomEditor {
-    pub fn new(room: RoomRef) -> Self {
-        Self { room }
+    pub fn new(room: &Room, settings: &Settings) -> Self {
+        Self {
+            room: room.handle(),
+            room_index: room.index(),
+            state_index: 0,
+            overlays: settings.room_overlays,
+            palette_animation: PaletteAnimation::default(),
+            fx_index: None,
+            selected_door: None,
+            bts_popup: None,
+            history: UndoHistory::default(),
+            current_block: LevelDataEntry(0),
+            marquee_start: None,
+            selection: None,
+            paste_preview: None,
+        }
+    }
+
+    /// Selects the door or state a diagnostic points to. Returns false if it's not in this room.
+    fn show_location(&mut self, location: DiagnosticLocation) -> bool {
+        if location.room() != Some(self.room) {
+            return false;
+        }
+        match location {
+            DiagnosticLocation::Door { door, .. } => self.selected_door = Some(door),
+            DiagnosticLocation::RoomState { state, .. } => {
+                self.state_index = state;
+                self.fx_index = None;
+                self.bts_popup = None;
+            }
+            DiagnosticLocation::Room(_) | DiagnosticLocation::Tileset(_) => {}
+        }
+        true
+    }
+
+    /// Tints each screen of the room by its scroll value, using the same colors as SMART.
+    fn draw_scroll_overlay(painter: &Painter, origin: Pos2, state: &RoomState) {
+        let kind = state.scroll_data.resolve();
+        let width = usize::from(state.level_data.width.0);
+        let height = usize::from(state.level_data.height.0);
+        let screen_size = (SCREEN_BLOCKS * TILE_SIZE * 2) as f32;
+        for y in 0..height {
+            for x in 0..width {
+                let color = match kind.screen_value(y * width + x) {
+                    Some(0x00) => Color32::RED,
+                    Some(0x01) => Color32::BLUE,
+                    Some(0x02) => Color32::GREEN,
+                    _ => Color32::GRAY,
+                };
+                let rect = Rect::from_min_size(
+                    origin + vec2(x as f32, y as f32) * screen_size,
+                    Vec2::splat(screen_size),
+                );
+                painter.rect(
+                    rect,
+                    0,
+                    color.gamma_multiply(0.25),
+                    (1.0, color),
+                    StrokeKind::Inside,
+                );
+            }
+        }
+    }
+
+    /// Draws the liquid surface of `fx` as a line across the room, tinting everything below it.
+    /// The level it rises or falls to, if any, is drawn as a thinner line.
+    fn draw_fx_overlay(painter: &Painter, room_rect: Rect, fx: &Fx1) {
+        let Some(liquid) = Liquid::from_fx_type(fx.type_.0) else {
+            return;
+        };
+        let Some(surface_y) = fx_surface_y(fx.surfacestart) else {
+            return;
+        };
+        let tint = liquid.tint();
+        let surface_y = room_rect.min.y + f32::from(surface_y);
+        let liquid_rect = Rect::from_x_y_ranges(room_rect.x_range(), surface_y..=room_rect.max.y);
+        painter.rect_filled(liquid_rect, 0, tint);
+        painter.hline(room_rect.x_range(), surface_y, (2.0, tint.to_opaque()));
+        if let Some(new_y) = fx_surface_y(fx.surfacenew)
+            && new_y != fx.surfacestart.0
+        {
+            let new_y = room_rect.min.y + f32::from(new_y);
+            painter.hline(room_rect.x_range(), new_y, (1.0, tint.to_opaque()));
+        }
+    }
+
+    /// Marks the position of each enemy of `state` with a circle labeled with its species ID.
+    /// Enemies of the same species share a color.
+    fn draw_enemy_overlay(painter: &Painter, origin: Pos2, state: &RoomState) {
+        for enemy in &state.enemies.enemy {
+            let pos = origin + vec2(f32::from(enemy.x.0), f32::from(enemy.y.0));
+            let hue = f32::from(enemy.id.0.wrapping_mul(0x9E37)) / f32::from(u16::MAX);
+            let color = Color32::from(egui::ecolor::Hsva::new(hue, 0.8, 1.0, 1.0));
+            painter.circle(pos, 6.0, color.gamma_multiply(0.5), (1.5, color));
+            painter.text(
+                pos + vec2(0.0, 8.0),
+                Align2::CENTER_TOP,
+                enemy.id.to_string(),
+                FontId::monospace(9.0),
+                Color32::WHITE,
+            );
+        }
+    }
+
+    fn fx_label(fx: &Fx1) -> String {
+        if fx.default {
+            "Default".into()
+        } else if let Some(door) = fx.fromdoor {
+            format!("From door {door}")
+        } else {
+            "Unconditional".into()
+        }
+    }
+
+    /// Selector for the FX1 entry to preview, and a short description of it.
+    fn fx_selector(&mut self, ui: &mut Ui, state: &RoomState) {
+        let default_index = state.fx1s.iter().position(|fx| fx.default);
+        let Some(selected) = self
+            .fx_index
+            .filter(|&i| i < state.fx1s.len())
+            .or(default_index)
+        else {
+            ui.label("No FX1 entries");
+            return;
+        };
+        let fx = &state.fx1s[selected];
+        egui::ComboBox::from_id_salt("fx1_selector")
+            .selected_text(Self::fx_label(fx))
+            .show_ui(ui, |ui| {
+                for (i, fx) in state.fx1s.iter().enumerate() {
+                    if ui
+                        .selectable_label(i == selected, Self::fx_label(fx))
+                        .clicked()
+                    {
+                        self.fx_index = Some(i);
+                    }
+                }
+            });
+        let liquid = Liquid::from_fx_type(fx.type_.0);
+        match (liquid, fx_surface_y(fx.surfacestart)) {
+            (Some(liquid), Some(y)) => ui.label(format!("{liquid:?} at Y={y}px")),
+            (Some(liquid), None) => ui.label(format!("{liquid:?}, no surface")),
+            (None, _) => ui.label(format!("FX type {} has no liquid", fx.type_)),
+        };
+    }
+
+    /// Outlines the door blocks in the visible part of the room, labeled with the index of the
+    /// door they lead through.
+    fn draw_door_overlay(painter: &Painter, origin: Pos2, model: &Layer1Model) {
+        let block_size = (TILE_SIZE * 2) as f32;
+        let visible = painter.clip_rect().translate(-origin.to_vec2()) / block_size;
+        let [width, height] = model.dimensions();
+        let x_range = (visible.min.x.max(0.0) as usize)..(visible.max.x.ceil() as usize).min(width);
+        let y_range =
+            (visible.min.y.max(0.0) as usize)..(visible.max.y.ceil() as usize).min(height);
+        let color = BlockType::Door.color();
+        for y in y_range {
+            for x in x_range.clone() {
+                if model.get(x, y).map(|e| e.typed_block_type()) != Some(BlockType::Door) {
+                    continue;
+                }
+                let rect = Rect::from_min_size(
+                    origin + vec2(x as f32, y as f32) * block_size,
+                    Vec2::splat(block_size),
+                );
+                painter.rect(
+                    rect,
+                    0,
+                    color.gamma_multiply(0.25),
+                    (1.0, color),
+                    StrokeKind::Inside,
+                );
+                if let Some(bts) = model.bts(x, y) {
+                    painter.text(
+                        rect.center(),
+                        Align2::CENTER_CENTER,
+                        format!("{bts:02X}"),
+                        FontId::monospace(8.0),
+                        Color32::WHITE,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Labels the blocks in the visible part of the room that have a non-zero BTS with its value.
+    fn draw_bts_overlay(painter: &Painter, origin: Pos2, model: &Layer1Model) {
+        let block_size = (TILE_SIZE * 2) as f32;
+        let visible = painter.clip_rect().translate(-origin.to_vec2()) / block_size;
+        let [width, height] = model.dimensions();
+        let x_range = (visible.min.x.max(0.0) as usize)..(visible.max.x.ceil() as usize).min(width);
+        let y_range =
+            (visible.min.y.max(0.0) as usize)..(visible.max.y.ceil() as usize).min(height);
+        for y in y_range {
+            for x in x_range.clone() {
+                let Some(bts @ 1..) = model.bts(x, y) else {
+                    continue;
+                };
+                let rect = Rect::from_min_size(
+                    origin + vec2(x as f32, y as f32) * block_size,
+                    Vec2::splat(block_size),
+                );
+                painter.rect_filled(rect, 0, Color32::BLACK.gamma_multiply(0.5));
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    format!("{bts:02X}"),
+                    FontId::monospace(8.0),
+                    Color32::WHITE,
+                );
+            }
+        }
+    }
+
+    /// Shows the popup editing the BTS of the block clicked with the BTS overlay enabled,
+    /// recording any changes in the undo history.
+    fn show_bts_popup(&mut self, ctx: &egui::Context, room: &mut Room) {
+        if !self.overlays.bts {
+            self.bts_popup = None;
+        }
+        let Some(popup) = &self.bts_popup else {
+            return;
+        };
+        let [x, y] = popup.block;
+        let Some((block_type, old_value)) =
+            room.smart_room
+                .states
+                .get(self.state_index)
+                .and_then(|state| {
+                    let model = Layer1Model {
+                        level_data: &state.level_data,
+                    };
+                    Some((model.get(x, y)?.typed_block_type(), model.bts(x, y)?))
+                })
+        else {
+            self.bts_popup = None;
+            return;
+        };
+
+        let mut value = old_value;
+        let mut dragging = false;
+        let mut close = false;
+        egui::Area::new(self.stable_id().with("bts_popup"))
+            .fixed_pos(popup.anchor)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("BTS at ({x}, {y}):"));
+                        let response = ui
+                            .add(
+                                DragValue::new(&mut value)
+                                    .hexadecimal(2, false, true)
+                                    .prefix("$"),
+                            )
+                            .on_hover_text(format!("{block_type:?} block"));
+                        dragging = response.dragged() && !response.drag_started();
+                        close = ui.small_button("✖").on_hover_text("Close").clicked();
+                    });
+                    ui.label(snes_constants::interpret_bts(block_type, value).to_string());
+                });
+            });
+
+        if value != old_value {
+            let edit = RoomEdit::Bts(BtsEdit::new(self.state_index, [x, y], old_value, value));
+            edit.apply(room);
+            self.history.push(edit, dragging);
+        }
+        if close || ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.bts_popup = None;
+        }
+    }
+
+    fn hex_u8_field(ui: &mut Ui, label: &str, value: &mut HexU8) -> Response {
+        ui.label(label);
+        let response = ui.add(
+            DragValue::new(&mut value.0)
+                .range(0..=u8::MAX)
+                .hexadecimal(2, false, true)
+                .prefix("$"),
+        );
+        ui.end_row();
+        response
+    }
+
+    fn hex_u16_field(ui: &mut Ui, label: &str, value: &mut HexU16) -> Response {
+        ui.label(label);
+        let response = ui.add(
+            DragValue::new(&mut value.0)
+                .hexadecimal(4, false, true)
+                .prefix("$"),
+        );
+        ui.end_row();
+        response
+    }
+
+    /// Shows the fields of the selected door, recording any changes made to them in the undo
+    /// history.
+    fn show_door_panel(
+        &mut self,
+        room: &mut Room,
+        door_index: usize,
+        requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        ui.horizontal(|ui| {
+            ui.heading(format!("Door {}", HexU8(door_index as u8)));
+            if ui.small_button("✖").on_hover_text("Close").clicked() {
+                self.selected_door = None;
+            }
+        });
+        let door = match room.smart_room.doors.get(door_index) {
+            None => {
+                ui.label("Room has no door with this index.");
+                return;
+            }
+            Some(DoorEntry::Elevator) => {
+                ui.label("Elevator");
+                ui.label("Elevator entries have no door data to edit.");
+                return;
+            }
+            Some(DoorEntry::Door(door)) => door,
+        };
+
+        let before = door.clone();
+        let mut after = door.clone();
+        let mut changed = false;
+        let mut dragging = false;
+        let mut track = |response: Response| {
+            changed |= response.changed();
+            dragging |= response.dragged() && !response.drag_started();
+        };
+        egui::Grid::new("door_fields")
+            .num_columns(2)
+            .show(ui, |ui| {
+                track(Self::hex_u8_field(ui, "To area", &mut after.toroom.area));
+                track(Self::hex_u8_field(ui, "To room", &mut after.toroom.index));
+                track(
+                    Self::hex_u8_field(ui, "Bitflag", &mut after.bitflag)
+                        .on_hover_text("$40: Area transition\n$80: Elevator"),
+                );
+
+                ui.label("Direction");
+                let mut direction = after.direction.0;
+                ui.horizontal(|ui| {
+                    let selected = usize::from(direction.get_bits(0..2));
+                    let mut new_direction = selected;
+                    egui::ComboBox::from_id_salt("door_direction")
+                        .selected_text(DOOR_DIRECTIONS[selected])
+                        .show_ui(ui, |ui| {
+                            for (i, name) in DOOR_DIRECTIONS.iter().enumerate() {
+                                ui.selectable_value(&mut new_direction, i, *name);
+                            }
+                        });
+                    direction.set_bits(0..2, new_direction as u8);
+                    let mut closes = direction.get_bit(2);
+                    ui.checkbox(&mut closes, "Closes behind");
+                    direction.set_bit(2, closes);
+                });
+                ui.end_row();
+                after.direction = HexU8(direction);
+
+                track(Self::hex_u8_field(ui, "Tile X", &mut after.tilex));
+                track(Self::hex_u8_field(ui, "Tile Y", &mut after.tiley));
+                track(Self::hex_u8_field(ui, "Screen X", &mut after.screenx));
+                track(Self::hex_u8_field(ui, "Screen Y", &mut after.screeny));
+                track(Self::hex_u16_field(ui, "Distance", &mut after.distance));
+
+                let doorcode = &mut after.doorcode;
+                if let Some(address) = &mut doorcode.address {
+                    track(Self::hex_u16_field(ui, "Door code", address));
+                } else {
+                    ui.label("Door code");
+                    if !doorcode.ops.is_empty() {
+                        ui.label(format!("{} code ops (read-only)", doorcode.ops.len()));
+                    } else if let Some(scroll_data) = &doorcode.scroll_data {
+                        ui.label(format!(
+                            "{} scroll changes (read-only)",
+                            scroll_data.entries.len()
+                        ));
+                    } else {
+                        ui.label("None");
+                    }
+                    ui.end_row();
+                }
+            });
+        Self::show_door_code_listing(ui, &after.doorcode);
+
+        // The direction combo box doesn't report changes through a single response
+        changed |= after.direction != before.direction;
+
+        if ui.button("Open destination room").clicked() {
+            let index = (after.toroom.area.0, after.toroom.index.0);
+            requests.open_editor(EditorKey::Room(index));
+        }
+
+        if changed {
+            let edit = DoorEdit {
+                door: door_index,
+                before,
+                after,
+            };
+            let edit = RoomEdit::Door(edit);
+            edit.apply(room);
+            self.history.push(edit, dragging);
+        }
+    }
+
+    /// Shows the room header fields that control scrolling and CRE loading, recording any changes
+    /// made to them in the undo history.
+    fn show_properties_panel(&mut self, room: &mut Room, ui: &mut Ui) {
+        let before = RoomProperties::of(room);
+        let mut after = before;
+        let mut dragging = false;
+        egui::Grid::new("room_properties")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let response = Self::hex_u8_field(ui, "Up scroll", &mut after.upscroll);
+                dragging |= response.dragged() && !response.drag_started();
+                let response = Self::hex_u8_field(ui, "Down scroll", &mut after.dnscroll);
+                dragging |= response.dragged() && !response.drag_started();
+
+                ui.label("Special GFX");
+                ui.horizontal_wrapped(|ui| {
+                    let special_gfx = &mut after.special_gfx.0;
+                    for bit in 0..8 {
+                        let mut set = special_gfx.get_bit(bit);
+                        ui.checkbox(&mut set, SpecialGfxFlags::bit_label(bit as u32));
+                        special_gfx.set_bit(bit, set);
+                    }
+                });
+                ui.end_row();
+            });
+
+        if after != before {
+            let edit = RoomEdit::Properties(RoomPropertiesEdit { before, after });
+            edit.apply(room);
+            self.history.push(edit, dragging);
+        }
+    }
+
+    /// Read-only listing of a door's inline code ops, disassembled, or of its scroll changes.
+    fn show_door_code_listing(ui: &mut Ui, doorcode: &DoorCode) {
+        let lines: Vec<String> = if let Some(scroll_data) = &doorcode.scroll_data {
+            scroll_data
+                .entries
+                .iter()
+                .map(door_code::format_scroll_change)
+                .collect()
+        } else {
+            doorcode.ops.iter().map(door_code::format_op).collect()
+        };
+        if lines.is_empty() {
+            return;
+        }
+        egui::CollapsingHeader::new("Door code listing")
+            .default_open(true)
+            .show(ui, |ui| {
+                for line in lines {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    /// Buttons for the block operations, and their keyboard shortcuts.
+    fn show_block_toolbar(&mut self, ui: &mut Ui, accepts_keys: bool) -> Vec<BlockAction> {
+        const COPY: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::C);
+        const PASTE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::V);
+
+        let mut actions = Vec::new();
+        let has_clipboard = ui.data(|data| {
+            data.get_temp::<BlockSelection>(Id::new(ID_SALT).with(BLOCK_CLIPBOARD_KEY))
+                .is_some()
+        });
+        let has_selection = self.selection.is_some();
+        ui.horizontal(|ui| {
+            ui.label("Block:");
+            ui.add(
+                DragValue::new(&mut self.current_block.0)
+                    .hexadecimal(4, false, true)
+                    .prefix("$"),
+            )
+            .on_hover_text("Level data value placed by Fill. Right-click a block to pick it.");
+
+            ui.add_enabled_ui(has_selection, |ui| {
+                if ui.button("Fill").clicked() {
+                    actions.push(BlockAction::Fill);
+                }
+                if ui.button("Delete").clicked() {
+                    actions.push(BlockAction::Delete);
+                }
+                if ui.button("Copy").clicked() {
+                    actions.push(BlockAction::Copy);
+                }
+                if ui.button("Flip H").clicked() {
+                    actions.push(BlockAction::FlipH);
+                }
+                if ui.button("Flip V").clicked() {
+                    actions.push(BlockAction::FlipV);
+                }
+            });
+            if ui
+                .add_enabled(has_clipboard, egui::Button::new("Paste"))
+                .clicked()
+            {
+                actions.push(BlockAction::Paste);
+            }
+            ui.weak("Shift+drag to select");
+        });
+
+        if accepts_keys {
+            ui.input_mut(|i| {
+                if has_selection && i.consume_shortcut(&COPY) {
+                    actions.push(BlockAction::Copy);
+                }
+                if has_selection && i.consume_key(Modifiers::NONE, Key::Delete) {
+                    actions.push(BlockAction::Delete);
+                }
+                if has_clipboard && i.consume_shortcut(&PASTE) {
+                    actions.push(BlockAction::Paste);
+                }
+                // Left for the BTS popup to close itself with, while it's open
+                if self.bts_popup.is_none() && i.consume_key(Modifiers::NONE, Key::Escape) {
+                    if self.paste_preview.is_some() {
+                        self.paste_preview = None;
+                    } else {
+                        self.selection = None;
+                    }
+                }
+            });
+        }
+        actions
+    }
+
+    fn run_block_action(&mut self, ctx: &egui::Context, room: &mut Room, action: BlockAction) {
+        let clipboard_id = Id::new(ID_SALT).with(BLOCK_CLIPBOARD_KEY);
+        let Some(state) = room.smart_room.states.get(self.state_index) else {
+            return;
+        };
+        let model = Layer1Model {
+            level_data: &state.level_data,
+        };
+        let selected_region = |rect: BlockRect| {
+            let [width, height] = rect.size;
+            model.region(rect.origin, width, height)
+        };
+
+        let (origin, before, after) = match action {
+            BlockAction::Pick([x, y]) => {
+                if let Some(entry) = model.get(x, y) {
+                    self.current_block = entry;
+                }
+                return;
+            }
+            BlockAction::Copy => {
+                if let Some(selection) = self.selection {
+                    let blocks = selected_region(selection);
+                    ctx.data_mut(|data| data.insert_temp(clipboard_id, blocks));
+                }
+                return;
+            }
+            BlockAction::Paste => {
+                self.paste_preview = ctx.data(|data| data.get_temp(clipboard_id));
+                return;
+            }
+            BlockAction::CommitPaste(origin) => {
+                let Some(blocks) = self.paste_preview.take() else {
+                    return;
+                };
+                self.selection = Some(BlockRect {
+                    origin,
+                    size: [blocks.width, blocks.height],
+                });
+                let before = model.region(origin, blocks.width, blocks.height);
+                (origin, before, blocks)
+            }
+            BlockAction::Fill | BlockAction::Delete | BlockAction::FlipH | BlockAction::FlipV => {
+                let Some(selection) = self.selection else {
+                    return;
+                };
+                let before = selected_region(selection);
+                let [width, height] = selection.size;
+                let after = match action {
+                    BlockAction::Fill => BlockSelection::filled(width, height, self.current_block),
+                    BlockAction::Delete => BlockSelection::filled(width, height, LevelDataEntry(0)),
+                    BlockAction::FlipH => before.flipped_h(),
+                    _ => before.flipped_v(),
+                };
+                (selection.origin, before, after)
+            }
+        };
+
+        let edit = RoomEdit::Blocks(BlocksEdit {
+            state: self.state_index,
+            origin,
+            before,
+            after,
+        });
+        edit.apply(room);
+        self.history.push(edit, false);
+        tile_view::invalidate_room_textures(ctx, self.room);
+    }
+
+    /// Handles marquee selection, block picking and paste placement on the room canvas, and draws
+    /// the selection and paste preview.
+    fn canvas_block_interaction(
+        &mut self,
+        ui: &Ui,
+        response: &Response,
+        model: &Layer1Model,
+        actions: &mut Vec<BlockAction>,
+    ) {
+        let block_size = (TILE_SIZE * 2) as f32;
+        let [width, height] = model.dimensions();
+        let block_at = |pointer: Pos2| {
+            let pos = ((pointer - response.rect.min) / block_size).floor();
+            // Pointer can be outside of the room while dragging
+            [
+                (pos.x.max(0.0) as usize).min(width.saturating_sub(1)),
+                (pos.y.max(0.0) as usize).min(height.saturating_sub(1)),
+            ]
+        };
+        let painter = ui.painter_at(response.rect);
+        let selection_color = ui.visuals().selection.stroke.color;
+
+        if let Some(blocks) = &self.paste_preview {
+            if let Some(pointer) = response.hover_pos() {
+                let rect = BlockRect {
+                    origin: block_at(pointer),
+                    size: [blocks.width, blocks.height],
+                };
+                painter.rect(
+                    rect.to_ui_rect(response.rect.min),
+                    0,
+                    selection_color.gamma_multiply(0.4),
+                    (2.0, selection_color),
+                    StrokeKind::Inside,
+                );
+            }
+            if response.clicked()
+                && let Some(pointer) = response.interact_pointer_pos()
+            {
+                actions.push(BlockAction::CommitPaste(block_at(pointer)));
+            } else if response.secondary_clicked() {
+                self.paste_preview = None;
+            }
+            return;
+        }
+
+        if response.drag_started()
+            && ui.input(|i| i.modifiers.shift)
+            && let Some(pointer) = response.interact_pointer_pos()
+        {
+            let start = block_at(pointer);
+            self.marquee_start = Some(start);
+            self.selection = Some(BlockRect::from_corners(start, start));
+        }
+        if let Some(start) = self.marquee_start {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                self.selection = Some(BlockRect::from_corners(start, block_at(pointer)));
+            }
+            if !response.dragged() {
+                self.marquee_start = None;
+            }
+        }
+        if response.clicked() && !ui.input(|i| i.modifiers.shift) {
+            self.selection = None;
+        }
+        if response.secondary_clicked()
+            && let Some(pointer) = response.interact_pointer_pos()
+        {
+            actions.push(BlockAction::Pick(block_at(pointer)));
+        }
+
+        if let Some(selection) = self.selection {
+            painter.rect(
+                selection.to_ui_rect(response.rect.min),
+                0,
+                selection_color.gamma_multiply(0.15),
+                (2.0, selection_color),
+                StrokeKind::Inside,
+            );
+        }
+    }
+
+    fn show_canvas(
+        &mut self,
+        project_data: &ProjectData,
+        room: &Room,
+        ui: &mut Ui,
+        actions: &mut Vec<BlockAction>,
+    ) {
+        let Some(state) = room.smart_room.states.get(self.state_index) else {
+            ui.label("Room has no states.");
+            return;
+        };
+        let Some(sce_tileset) = project_data
+            .tileset_ids
+            .get(&state.gfx_set.0)
+            .and_then(|&hnd| project_data.tilesets.get(hnd))
+        else {
+            ui.label(format!("Tileset {} not found.", state.gfx_set));
+            return;
+        };
+        let layout =
+            tileset::detect_sources_layout(sce_tileset, find_default_cre(ui.ctx(), project_data));
+
+        egui::ScrollArea::both()
+            .max_width(f32::INFINITY)
+            .max_height(f32::INFINITY)
+            .show(ui, |ui| {
+                let response = ui.add(RoomCanvas {
+                    room,
+                    state_index: self.state_index,
+                    layout: &layout,
+                    rotation: self.palette_animation.current_rotation(ui.ctx()),
+                });
+                let model = Layer1Model {
+                    level_data: &state.level_data,
+                };
+
+                if self.overlays.scroll {
+                    Self::draw_scroll_overlay(
+                        &ui.painter_at(response.rect),
+                        response.rect.min,
+                        state,
+                    );
+                }
+                if self.overlays.fx
+                    && let Some(fx) = self
+                        .fx_index
+                        .and_then(|i| state.fx1s.get(i))
+                        .or_else(|| state.fx1s.iter().find(|fx| fx.default))
+                {
+                    Self::draw_fx_overlay(&ui.painter_at(response.rect), response.rect, fx);
+                }
+                if self.overlays.doors {
+                    Self::draw_door_overlay(
+                        &ui.painter_at(response.rect),
+                        response.rect.min,
+                        &model,
+                    );
+                }
+                if self.overlays.enemies {
+                    Self::draw_enemy_overlay(
+                        &ui.painter_at(response.rect),
+                        response.rect.min,
+                        state,
+                    );
+                }
+                if self.overlays.bts {
+                    Self::draw_bts_overlay(
+                        &ui.painter_at(response.rect),
+                        response.rect.min,
+                        &model,
+                    );
+                }
+
+                let block_size = (TILE_SIZE * 2) as f32;
+                let block_at = |pointer| grid_cell_at(response.rect, block_size, pointer);
+                self.canvas_block_interaction(ui, &response, &model, actions);
+                if self.overlays.doors
+                    && self.paste_preview.is_none()
+                    && response.clicked()
+                    && let Some([x, y]) = response.interact_pointer_pos().map(block_at)
+                    && model.get(x, y).map(|e| e.typed_block_type()) == Some(BlockType::Door)
+                    && let Some(bts) = model.bts(x, y)
+                {
+                    self.selected_door = Some(usize::from(bts));
+                }
+                if self.overlays.bts
+                    && self.paste_preview.is_none()
+                    && response.clicked()
+                    && !ui.input(|i| i.modifiers.shift)
+                    && let Some([x, y]) = response.interact_pointer_pos().map(block_at)
+                    && model.bts(x, y).is_some()
+                {
+                    self.bts_popup = Some(BtsPopup {
+                        block: [x, y],
+                        anchor: Pos2::ZERO,
+                    });
+                }
+                if let Some(popup) = &mut self.bts_popup {
+                    let rect = BlockRect::from_corners(popup.block, popup.block)
+                        .to_ui_rect(response.rect.min);
+                    ui.painter_at(response.rect).rect_stroke(
+                        rect,
+                        0,
+                        (2.0, Color32::WHITE),
+                        StrokeKind::Inside,
+                    );
+                    popup.anchor = rect.right_bottom();
+                }
+
+                if let Some(pointer) = response.hover_pos() {
+                    let [x, y] = block_at(pointer);
+                    HoverInfo {
+                        tile: [x, y],
+                        block: model.get(x, y).map(|entry| entry.block_id()),
+                        screen: Some([x / SCREEN_BLOCKS, y / SCREEN_BLOCKS]),
+                    }
+                    .publish(ui.ctx());
+                }
+            });
     }
 }
 
 impl EditorWindow for RoomEditor {
     fn title(&self, project_data: &ProjectData) -> String {
         if let Some(room) = project_data.rooms.get(self.room) {
-            format!("Room: {}", &room.name)
+            let dirty_marker = if room.is_dirty() { "*" } else { "" };
+            let index = room.index().map_or("??,??".into(), |(area, index)| {
+                format!("{area:02X},{index:02X}")
+            });
+            format!(
+                "Room: {}{dirty_marker} [{index}] ({}×{} screens)",
+                &room.name, room.width_screens, room.height_screens
+            )
         } else {
             format!("Room: <{:?}>", self.room)
         }
     }
 
     fn stable_id(&self) -> Id {
-        Id::new(ID_SALT).with(self.room)
+        if let Some(room_index) = self.room_index {
+            Id::new(ID_SALT).with(room_index)
+        } else {
+            Id::new(ID_SALT).with(self.room)
+        }
+    }
+
+    fn editor_key(&self) -> Option<EditorKey> {
+        self.room_index.map(EditorKey::Room)
+    }
+
+    fn dock_side(&self) -> Option<DockSide> {
+        Some(DockSide::Left)
+    }
+
+    fn run_command(
+        &mut self,
+        command: EditorCommand,
+        project_data: &mut ProjectData,
+        ctx: &egui::Context,
+    ) -> bool {
+        let Some(room) = project_data.rooms.get_mut(self.room) else {
+            return false;
+        };
+        match command {
+            EditorCommand::Undo => self.history.undo(room),
+            EditorCommand::Redo => self.history.redo(room),
+            EditorCommand::ToggleOverlays => {
+                let RoomOverlays {
+                    scroll,
+                    doors,
+                    fx,
+                    enemies,
+                    bts,
+                } = self.overlays;
+                let show = !(scroll || doors || fx || enemies || bts);
+                self.overlays = RoomOverlays {
+                    scroll: show,
+                    doors: show,
+                    fx: show,
+                    enemies: show,
+                    bts: show,
+                };
+                return true;
+            }
+            EditorCommand::ResetZoom => return false,
+            EditorCommand::CopyRoomSummary => {
+                ctx.copy_text(room.text_summary());
+                return true;
+            }
+            EditorCommand::ShowLocation(location) => return self.show_location(location),
+        }
+        tile_view::invalidate_room_textures(ctx, self.room);
+        true
     }
 
-    fn show_contents(&mut self, project_data: &mut ProjectData, ui: &mut Ui) {
-        let Some(_room) = project_data.rooms.get(self.room) else {
+    fn show_contents(
+        &mut self,
+        project_data: &mut ProjectData,
+        requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        let Some(room) = project_data.rooms.get(self.room) else {
             ui.close();
             return;
         };
+
+        ui.label(format!(
+            "Size: {}×{} screens, {} states, {} doors",
+            room.width_screens,
+            room.height_screens,
+            room.smart_room.states.len(),
+            room.smart_room.doors.len(),
+        ));
+
+        if !room.diagnostics.is_empty() {
+            egui::CollapsingHeader::new(
+                egui::RichText::new(format!("⚠ {} diagnostics", room.diagnostics.len()))
+                    .color(ui.visuals().warn_fg_color),
+            )
+            .id_salt("room_diagnostics")
+            .show(ui, |ui| {
+                for diagnostic in &room.diagnostics {
+                    if views::show_diagnostic(ui, diagnostic)
+                        && let Some(location) = diagnostic.location
+                    {
+                        self.show_location(location);
+                    }
+                }
+            });
+        }
+
+        // Arrow keys jump to the neighboring room, but only for the topmost editor and while no
+        // other widget is capturing keyboard input.
+        let accepts_keys = ui.ctx().top_layer_id() == Some(ui.layer_id())
+            && ui.ctx().memory(|mem| mem.focused().is_none());
+
+        ui.horizontal(|ui| {
+            ui.label("Neighbors:");
+            for direction in MapDirection::ALL {
+                let neighbors = project_data.room_neighbors(self.room, direction);
+                let key = match direction {
+                    MapDirection::Left => Key::ArrowLeft,
+                    MapDirection::Right => Key::ArrowRight,
+                    MapDirection::Up => Key::ArrowUp,
+                    MapDirection::Down => Key::ArrowDown,
+                };
+                let key_pressed =
+                    accepts_keys && ui.input_mut(|i| i.consume_key(Modifiers::NONE, key));
+
+                match neighbors.as_slice() {
+                    [] => {
+                        ui.add_enabled(false, egui::Button::new(direction.arrow()));
+                    }
+                    [neighbor] => {
+                        if ui
+                            .button(direction.arrow())
+                            .on_hover_text(neighbor.title())
+                            .clicked()
+                            || key_pressed
+                        {
+                            requests.open_editor(EditorKey::Room(neighbor.index().unwrap()));
+                        }
+                    }
+                    // Ambiguous neighbors: keyboard picks the lowest index, the menu allows any.
+                    [first, ..] => {
+                        if key_pressed {
+                            requests.open_editor(EditorKey::Room(first.index().unwrap()));
+                        }
+                        ui.menu_button(format!("{}…", direction.arrow()), |ui| {
+                            for neighbor in &neighbors {
+                                if ui.button(neighbor.title()).clicked() {
+                                    requests
+                                        .open_editor(EditorKey::Room(neighbor.index().unwrap()));
+                                }
+                            }
+                        });
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Overlays:");
+            ui.checkbox(&mut self.overlays.scroll, "Scroll");
+            ui.checkbox(&mut self.overlays.doors, "Doors")
+                .on_hover_text("Click a door block to edit the door it leads through");
+            ui.checkbox(&mut self.overlays.fx, "FX1");
+            ui.checkbox(&mut self.overlays.enemies, "Enemies")
+                .on_hover_text("Mark enemy positions, labeled with their species ID");
+            ui.checkbox(&mut self.overlays.bts, "BTS")
+                .on_hover_text("Click a block to edit its BTS");
+            if self.overlays.fx
+                && let Some(state) = room.smart_room.states.get(self.state_index)
+            {
+                self.fx_selector(ui, state);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.palette_animation.enabled, "Animate palette")
+                .on_hover_text("Rotate the selected palette lines over time");
+            if self.palette_animation.enabled {
+                self.palette_animation.show_controls(ui);
+            }
+        });
+
+        let Some(room) = project_data.rooms.get_mut(self.room) else {
+            return;
+        };
+        // Check for redo first, since the undo shortcut would also match it
+        let redo_pressed = accepts_keys && ui.input_mut(|i| i.consume_shortcut(&REDO));
+        let undo_pressed = accepts_keys && ui.input_mut(|i| i.consume_shortcut(&UNDO));
+        ui.horizontal(|ui| {
+            let undo_clicked = ui
+                .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                .clicked();
+            if undo_clicked || undo_pressed {
+                self.history.undo(room);
+                tile_view::invalidate_room_textures(ui.ctx(), self.room);
+            }
+            let redo_clicked = ui
+                .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                .clicked();
+            if redo_clicked || redo_pressed {
+                self.history.redo(room);
+                tile_view::invalidate_room_textures(ui.ctx(), self.room);
+            }
+        });
+        let mut actions = self.show_block_toolbar(ui, accepts_keys);
+
+        if let Some(door_index) = self.selected_door {
+            egui::SidePanel::right("door_editor").show_inside(ui, |ui| {
+                self.show_door_panel(room, door_index, requests, ui);
+            });
+        }
+        egui::TopBottomPanel::bottom("room_properties").show_inside(ui, |ui| {
+            egui::CollapsingHeader::new("Room Properties")
+                .id_salt(self.stable_id().with("room_properties"))
+                .show(ui, |ui| self.show_properties_panel(room, ui));
+        });
+
+        let Some(room) = project_data.rooms.get(self.room) else {
+            return;
+        };
+        self.show_canvas(project_data, room, ui, &mut actions);
+
+        let Some(room) = project_data.rooms.get_mut(self.room) else {
+            return;
+        };
+        for action in actions {
+            self.run_block_action(ui.ctx(), room, action);
+        }
+        self.show_bts_popup(ui.ctx(), room);
     }
 }
@@ -0,0 +1,277 @@
+use crate::project::ProjectData;
+use crate::tileset::TilesetKind;
+use crate::ui::views::EditorKey;
+use egui::{Align2, Id, Key, Ui, vec2};
+
+const ID_SALT: &str = concat!(module_path!(), "::SearchPalette");
+
+/// Maximum number of results listed at once. Refining the query is expected for the rest.
+const MAX_RESULTS: usize = 50;
+
+/// Workspace commands that can be run from the palette.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PaletteCommand {
+    Reload,
+    ExportArchive,
+    ExportRoomsCsv,
+    Settings,
+    GoToRoom,
+}
+
+impl PaletteCommand {
+    const ALL: [Self; 5] = [
+        Self::Reload,
+        Self::ExportArchive,
+        Self::ExportRoomsCsv,
+        Self::Settings,
+        Self::GoToRoom,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Reload => "Reload Project",
+            Self::ExportArchive => "Export Archive...",
+            Self::ExportRoomsCsv => "Export Room List as CSV...",
+            Self::Settings => "Settings...",
+            Self::GoToRoom => "Go to Room...",
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum SearchTarget {
+    Editor(EditorKey),
+    Command(PaletteCommand),
+}
+
+struct SearchEntry {
+    label: String,
+    /// Lowercased strings the query is matched against, e.g. the name and hex index.
+    search_terms: Vec<String>,
+    target: SearchTarget,
+}
+
+/// Length of the shortest span of `term` containing the characters of `query` in order, if it
+/// contains all of them.
+fn subsequence_span(term: &str, query: &str) -> Option<usize> {
+    let term: Vec<char> = term.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+    let (&first, rest) = query.split_first()?;
+    // Greedily matching from each occurrence of the first character finds the shortest span
+    // that starts there.
+    term.iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == first)
+        .filter_map(|(start, _)| {
+            let mut pos = start;
+            for &c in rest {
+                pos += 1 + term[pos + 1..].iter().position(|&t| t == c)?;
+            }
+            Some(pos + 1 - start)
+        })
+        .min()
+}
+
+/// Ranks how well `query` (already lowercased) matches an entry, lower being better. Prefix
+/// matches rank before substring matches, followed by fuzzy matches of the query's characters in
+/// order, the closer together the better. `None` means no match at all.
+fn match_rank(search_terms: &[String], query: &str) -> Option<usize> {
+    if search_terms.iter().any(|term| term.starts_with(query)) {
+        Some(0)
+    } else if search_terms.iter().any(|term| term.contains(query)) {
+        Some(1)
+    } else {
+        let span = search_terms
+            .iter()
+            .filter_map(|term| subsequence_span(term, query))
+            .min()?;
+        Some(2 + span)
+    }
+}
+
+fn collect_entries(project_data: &ProjectData) -> Vec<SearchEntry> {
+    let mut entries = Vec::new();
+    for (&(area, index), &room_ref) in &project_data.room_ids {
+        let Some(room) = project_data.rooms.get(room_ref) else {
+            continue;
+        };
+        entries.push(SearchEntry {
+            label: room.title(),
+            search_terms: vec![
+                room.file_name.to_lowercase(),
+                room.title().to_lowercase(),
+                format!("{area:02x}{index:02x}"),
+                format!("{area:02x},{index:02x}"),
+            ],
+            target: SearchTarget::Editor(EditorKey::Room((area, index))),
+        });
+    }
+
+    let mut tilesets: Vec<_> = project_data
+        .tilesets
+        .values()
+        .filter(|tileset| !tileset.palette.is_empty())
+        .collect();
+    tilesets.sort_by(|a, b| a.display_cmp(b));
+    for tileset in tilesets {
+        let Some(index) = tileset.index() else {
+            continue;
+        };
+        let kind_name = match tileset.kind {
+            TilesetKind::Sce => "SCE",
+            TilesetKind::Cre => "CRE",
+        };
+        entries.push(SearchEntry {
+            label: format!("{kind_name} {}", tileset.title()),
+            search_terms: vec![tileset.name.to_lowercase(), format!("{index:02x}")],
+            target: SearchTarget::Editor(EditorKey::Tileset(tileset.kind, index)),
+        });
+    }
+
+    for command in PaletteCommand::ALL {
+        entries.push(SearchEntry {
+            label: command.label().into(),
+            search_terms: vec![command.label().to_lowercase()],
+            target: SearchTarget::Command(command),
+        });
+    }
+    entries
+}
+
+pub enum PaletteAction {
+    None,
+    Close,
+    Open(EditorKey),
+    Run(PaletteCommand),
+}
+
+impl From<SearchTarget> for PaletteAction {
+    fn from(target: SearchTarget) -> Self {
+        match target {
+            SearchTarget::Editor(key) => PaletteAction::Open(key),
+            SearchTarget::Command(command) => PaletteAction::Run(command),
+        }
+    }
+}
+
+/// Modal palette for quickly opening rooms and tilesets by name or index, and running workspace
+/// commands. Usable entirely from the keyboard.
+#[derive(Default)]
+pub struct SearchPalette {
+    query: String,
+    /// Index into the filtered results of the entry that Enter would open.
+    selected: usize,
+}
+
+impl SearchPalette {
+    fn results(&self, project_data: &ProjectData) -> Vec<SearchEntry> {
+        let query = self.query.trim().to_lowercase();
+        let mut ranked: Vec<_> = collect_entries(project_data)
+            .into_iter()
+            .filter_map(|entry| Some((match_rank(&entry.search_terms, &query)?, entry)))
+            .collect();
+        // Stable sort, so entries keep their index ordering within a rank
+        ranked.sort_by_key(|&(rank, _)| rank);
+        ranked
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .take(MAX_RESULTS)
+            .collect()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, project_data: &ProjectData) -> PaletteAction {
+        let results = self.results(project_data);
+        self.selected = self.selected.min(results.len().saturating_sub(1));
+
+        let mut action = PaletteAction::None;
+        let modal_response = egui::Modal::new(Id::new(ID_SALT))
+            .area(
+                egui::Modal::default_area(Id::new(ID_SALT))
+                    .anchor(Align2::CENTER_TOP, vec2(0.0, 48.0)),
+            )
+            .show(ctx, |ui| {
+                ui.set_width(400.0);
+                let text_response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Search rooms, tilesets and commands…")
+                        .desired_width(f32::INFINITY),
+                );
+                text_response.request_focus();
+                if text_response.changed() {
+                    self.selected = 0;
+                }
+
+                ui.input_mut(|i| {
+                    if i.consume_key(egui::Modifiers::NONE, Key::ArrowDown) {
+                        self.selected = (self.selected + 1).min(results.len().saturating_sub(1));
+                    }
+                    if i.consume_key(egui::Modifiers::NONE, Key::ArrowUp) {
+                        self.selected = self.selected.saturating_sub(1);
+                    }
+                    // Consumed so that dialogs opened by the entry don't see the same press
+                    if let Some(entry) = results.get(self.selected)
+                        && i.consume_key(egui::Modifiers::NONE, Key::Enter)
+                    {
+                        action = entry.target.into();
+                    }
+                });
+
+                ui.separator();
+                if let Some(target) = self.show_results(ui, &results) {
+                    action = target.into();
+                }
+            });
+
+        // Clicking anywhere outside the palette or pressing Escape dismisses it
+        if matches!(action, PaletteAction::None) && modal_response.should_close() {
+            action = PaletteAction::Close;
+        }
+        action
+    }
+
+    fn show_results(&self, ui: &mut Ui, results: &[SearchEntry]) -> Option<SearchTarget> {
+        if results.is_empty() {
+            ui.weak("No matches");
+            return None;
+        }
+        let mut clicked = None;
+        egui::ScrollArea::vertical()
+            .max_height(320.0)
+            .show(ui, |ui| {
+                for (i, entry) in results.iter().enumerate() {
+                    let response = ui.selectable_label(i == self.selected, &entry.label);
+                    if i == self.selected {
+                        response.scroll_to_me(None);
+                    }
+                    if response.clicked() {
+                        clicked = Some(entry.target);
+                    }
+                }
+            });
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_rank() {
+        let terms = ["landing site".to_owned(), "0000".to_owned()];
+        assert_eq!(match_rank(&terms, "land"), Some(0));
+        assert_eq!(match_rank(&terms, "site"), Some(1));
+        assert_eq!(match_rank(&terms, "00"), Some(0));
+        assert_eq!(match_rank(&terms, "lst"), Some(2 + 11));
+        assert_eq!(match_rank(&terms, "ceres"), None);
+        assert_eq!(match_rank(&terms, ""), Some(0));
+    }
+
+    #[test]
+    fn test_subsequence_span() {
+        assert_eq!(subsequence_span("abcabc", "ac"), Some(3));
+        assert_eq!(subsequence_span("axxbab", "ab"), Some(2));
+        assert_eq!(subsequence_span("abc", "ca"), None);
+        assert_eq!(subsequence_span("abc", ""), None);
+    }
+}
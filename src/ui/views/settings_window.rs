@@ -0,0 +1,98 @@
+use crate::project::ProjectData;
+use crate::smart_xml::LoadMode;
+use crate::ui::settings::Settings;
+use crate::ui::views::tileset_editor::TilesetEditor;
+use crate::ui::views::{EditorKey, EditorWindow, WorkspaceRequests};
+use egui::{Id, ThemePreference, Ui};
+
+const ID_SALT: &str = concat!(module_path!(), "::SettingsWindow");
+
+/// Edits the persisted `Settings`. Changes are stored as soon as they're made.
+pub struct SettingsWindow;
+
+impl EditorWindow for SettingsWindow {
+    fn title(&self, _project_data: &ProjectData) -> String {
+        "Settings".into()
+    }
+
+    fn stable_id(&self) -> Id {
+        Id::new(ID_SALT)
+    }
+
+    fn editor_key(&self) -> Option<EditorKey> {
+        None
+    }
+
+    fn show_contents(
+        &mut self,
+        _project_data: &mut ProjectData,
+        _requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        let mut settings = Settings::get(ui.ctx());
+        let old_settings = settings.clone();
+
+        egui::Grid::new("settings_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Theme:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.theme, ThemePreference::System, "System")
+                        .on_hover_text("Follow the operating system's light/dark preference");
+                    ui.radio_value(&mut settings.theme, ThemePreference::Light, "Light");
+                    ui.radio_value(&mut settings.theme, ThemePreference::Dark, "Dark");
+                });
+                ui.end_row();
+
+                ui.label("Project loading:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut settings.load_mode, LoadMode::Lenient, "Lenient")
+                        .on_hover_text("Skip invalid files and report them as warnings");
+                    ui.radio_value(&mut settings.load_mode, LoadMode::Strict, "Strict")
+                        .on_hover_text("Fail to load projects with any invalid files");
+                });
+                ui.end_row();
+
+                ui.label("External changes:");
+                ui.checkbox(&mut settings.watch_project, "Watch project files")
+                    .on_hover_text("Offer to reload the project when its files change on disk");
+                ui.end_row();
+
+                ui.label("Tileset zoom:");
+                ui.horizontal(|ui| {
+                    for level in TilesetEditor::ZOOM_LEVELS {
+                        ui.selectable_value(&mut settings.default_zoom, level, format!("{level}×"));
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Room overlays:");
+                ui.horizontal(|ui| {
+                    let overlays = &mut settings.room_overlays;
+                    ui.checkbox(&mut overlays.scroll, "Scroll");
+                    ui.checkbox(&mut overlays.doors, "Doors");
+                    ui.checkbox(&mut overlays.fx, "FX1");
+                    ui.checkbox(&mut overlays.enemies, "Enemies");
+                    ui.checkbox(&mut overlays.bts, "BTS");
+                });
+                ui.end_row();
+
+                ui.label("Texture cache:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.texture_cache_max_age)
+                        .range(1..=600)
+                        .suffix(" frames"),
+                )
+                .on_hover_text(
+                    "How long rendered rooms and tilesets are kept in GPU memory after they \
+                     were last shown",
+                );
+                ui.end_row();
+            });
+        ui.weak("Zoom and overlays apply to newly opened editors.");
+
+        if settings != old_settings {
+            settings.store(ui.ctx());
+        }
+    }
+}
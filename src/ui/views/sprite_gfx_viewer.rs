@@ -0,0 +1,109 @@
+use crate::hex_types::{HexU8, HexU16, HexU24};
+use crate::project::ProjectData;
+use crate::snes_address::{ENEMY_HEADER_BANK, lorom_to_pc};
+use crate::ui::views::{EditorKey, EditorWindow, WorkspaceRequests};
+use egui::{Id, Ui};
+use egui_extras::{Column, TableBuilder};
+use std::collections::BTreeMap;
+
+const ID_SALT: &str = concat!(module_path!(), "::SpriteGfxViewer");
+
+/// Lists the enemy graphics pointers used by the rooms of an area, resolved to their ROM location.
+/// The graphics themselves are only in the ROM, which isn't part of the exported project, so they
+/// aren't shown.
+pub struct SpriteGfxViewer {
+    area: u8,
+}
+
+impl SpriteGfxViewer {
+    pub fn new(area: u8) -> Self {
+        Self { area }
+    }
+
+    /// Enemy gfx pointers used in the area, mapped to the names of the rooms that use them.
+    fn collect_enemy_gfx(&self, project_data: &ProjectData) -> BTreeMap<HexU16, Vec<String>> {
+        let mut enemy_gfx = BTreeMap::<_, Vec<_>>::new();
+        let area_rooms = project_data
+            .rooms
+            .values()
+            .filter(|room| room.index().is_some_and(|(area, _)| area == self.area));
+        for room in area_rooms {
+            for state in &room.smart_room.states {
+                for enemy_type in &state.enemy_types {
+                    let rooms = enemy_gfx.entry(enemy_type.gfx).or_default();
+                    if rooms.last() != Some(&room.name) {
+                        rooms.push(room.name.clone());
+                    }
+                }
+            }
+        }
+        enemy_gfx
+    }
+}
+
+impl EditorWindow for SpriteGfxViewer {
+    fn title(&self, _project_data: &ProjectData) -> String {
+        format!("Enemy GFX Pointers: Area {}", HexU8(self.area))
+    }
+
+    fn stable_id(&self) -> Id {
+        Id::new(ID_SALT).with(self.area)
+    }
+
+    fn editor_key(&self) -> Option<EditorKey> {
+        Some(EditorKey::SpriteGfx(self.area))
+    }
+
+    fn show_contents(
+        &mut self,
+        project_data: &mut ProjectData,
+        _requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
+        let enemy_gfx = self.collect_enemy_gfx(project_data);
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::remainder())
+            .header(18.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("GFX");
+                });
+                header.col(|ui| {
+                    ui.strong("Header address");
+                });
+                header.col(|ui| {
+                    ui.strong("ROM offset");
+                });
+                header.col(|ui| {
+                    ui.strong("Used in");
+                });
+            })
+            .body(|mut body| {
+                for (&gfx, rooms) in &enemy_gfx {
+                    let address = HexU24(u32::from(ENEMY_HEADER_BANK) << 16 | u32::from(gfx.0));
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(gfx.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.label(address.to_string());
+                        });
+                        row.col(|ui| {
+                            let pc = lorom_to_pc(address);
+                            ui.label(pc.map_or("-".into(), |pc| format!("0x{pc:06X}")));
+                        });
+                        row.col(|ui| {
+                            ui.label(rooms.join(", "));
+                        });
+                    });
+                }
+            });
+
+        ui.separator();
+        ui.weak("Enemy graphics are stored in the ROM, which isn't part of the exported project.");
+    }
+}
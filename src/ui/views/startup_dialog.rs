@@ -1,31 +1,55 @@
 use crate::project::validate_smart_project_path;
+use crate::ui::debounce::Debounce;
 use crate::ui::measurer::Measurer;
+use crate::ui::project_drop::ProjectDropTarget;
 use crate::ui::promise::{EguiWaker, LocalBoxFuture, Promise};
+use crate::ui::recent_projects::{RecentProjects, format_time_ago};
 use blocking::{Task, unblock};
-use egui::{Align, Button, Context, Layout, Sense, TextEdit, Ui};
+use egui::{Align, Button, Context, Layout, RichText, Sense, TextEdit, Ui};
 use egui_extras::{Column, TableBuilder};
 use std::path::PathBuf;
-use tracing::error;
+use std::time::{Duration, SystemTime};
+
+/// How long to wait after the last edit to the path before validating it.
+const VALIDATION_DELAY: Duration = Duration::from_millis(200);
 
 pub struct StartupDialog {
     picked_path_new: Promise<LocalBoxFuture<Option<rfd::FileHandle>>>,
     picked_path: PathBuf,
 
     path_validation_result: Promise<Task<Result<(), String>>>,
+    /// Delays validation of typed paths until typing stops. Re-launching the validation cancels
+    /// the previous one, so only the result for the latest path is ever shown.
+    validation_debounce: Debounce,
+    drop_target: ProjectDropTarget,
+
+    recent_projects: RecentProjects,
+    /// Whether each of `recent_projects` still exists, checked when the dialog is opened.
+    recent_found: Vec<bool>,
 }
 
 impl StartupDialog {
-    pub fn new(ctx: &Context) -> Self {
+    pub fn new(ctx: &Context, storage: Option<&dyn eframe::Storage>) -> Self {
         let waker = EguiWaker::for_context(ctx);
+        let recent_projects = storage.map(RecentProjects::load).unwrap_or_default();
+        let recent_found = recent_projects.0.iter().map(|p| p.path.exists()).collect();
         Self {
             picked_path_new: Promise::new(waker.clone()),
             picked_path: PathBuf::new(),
             path_validation_result: Promise::new(waker),
+            validation_debounce: Debounce::new(VALIDATION_DELAY),
+            drop_target: ProjectDropTarget::new(ctx),
+            recent_projects,
+            recent_found,
         }
     }
 
-    pub fn with_error_message(ctx: &Context, err: String) -> Self {
-        let mut slf = Self::new(ctx);
+    pub fn with_error_message(
+        ctx: &Context,
+        storage: Option<&dyn eframe::Storage>,
+        err: String,
+    ) -> Self {
+        let mut slf = Self::new(ctx, storage);
         slf.path_validation_result.set_response(Err(err));
         slf
     }
@@ -34,15 +58,30 @@ impl StartupDialog {
         self.picked_path
     }
 
-    pub fn show_contents(&mut self, ui: &mut Ui, frame: &eframe::Frame) {
-        let mut path_changed = false;
+    pub fn show_contents(&mut self, ui: &mut Ui, frame: &mut eframe::Frame) {
+        let time = ui.input(|i| i.time);
+        // Paths that were picked rather than typed are validated right away
+        let mut path_picked = false;
+        let mut path_edited = false;
 
         // Handle events
         if let Some(res) = self.picked_path_new.take_response()
             && let Some(p) = res
         {
             p.path().clone_into(&mut self.picked_path);
-            path_changed = true;
+            path_picked = true;
+        }
+        match self.drop_target.show(ui.ctx()) {
+            None => {}
+            Some((path, Ok(()))) => {
+                self.picked_path = path;
+                ui.close();
+            }
+            Some((path, Err(err))) => {
+                self.picked_path = path;
+                self.path_validation_result.cancel();
+                self.path_validation_result.set_response(Err(err));
+            }
         }
 
         // UI
@@ -54,13 +93,30 @@ impl StartupDialog {
         ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
             if ui
                 .add_enabled(
-                    matches!(self.path_validation_result.response(), Some(Ok(()))),
+                    self.validation_debounce.remaining(time).is_none()
+                        && !self.path_validation_result.is_pending()
+                        && matches!(self.path_validation_result.response(), Some(Ok(()))),
                     Button::new("Open"),
                 )
                 .clicked()
             {
                 ui.close();
             }
+            if ui
+                .add_enabled(
+                    !self.picked_path_new.is_pending(),
+                    Button::new("Browse Archive"),
+                )
+                .on_hover_text("Open a project stored in a .smart or .zip file")
+                .clicked()
+            {
+                self.picked_path_new.launch(Box::pin(
+                    rfd::AsyncFileDialog::new()
+                        .set_parent(frame)
+                        .add_filter("SMART project archive", &["smart", "zip"])
+                        .pick_file(),
+                ));
+            }
             if ui
                 .add_enabled(!self.picked_path_new.is_pending(), Button::new("Browse"))
                 .clicked()
@@ -80,7 +136,7 @@ impl StartupDialog {
                 .changed()
             {
                 self.picked_path = PathBuf::from(lossy_path_str);
-                path_changed = true;
+                path_edited = true;
             }
         });
 
@@ -91,7 +147,7 @@ impl StartupDialog {
         ui.separator();
         ui.label("Recent projects:");
 
-        let button_strip_measurer = Measurer::new(ui);
+        let button_strip_measurer = Measurer::with_id("startup_dialog_button_strip").persisted();
         const SCROLL_MIN_HEIGHT: f32 = 60.0;
         let scroll_height = if let Some(strip_height) = button_strip_measurer.query_height(ui) {
             (ui.available_height() - ui.spacing().item_spacing.y - strip_height)
@@ -100,25 +156,57 @@ impl StartupDialog {
             SCROLL_MIN_HEIGHT
         };
 
+        let now = SystemTime::now();
+        let mut removed = None;
+        let mut open = false;
         TableBuilder::new(ui)
             .auto_shrink(false)
             .min_scrolled_height(scroll_height)
             .max_scroll_height(scroll_height)
             .striped(true)
             .column(Column::remainder())
-            .sense(Sense::CLICK)
+            .column(Column::auto())
+            .sense(Sense::click())
             .body(|body| {
-                body.rows(18.0, 5, |mut row| {
-                    // TODO: Recent projects list
-                    let row_index = row.index();
+                body.rows(18.0, self.recent_projects.0.len(), |mut row| {
+                    let i = row.index();
+                    let project = &self.recent_projects.0[i];
+                    let found = self.recent_found[i];
+                    row.col(|ui| {
+                        let name = if found {
+                            RichText::new(project.name())
+                        } else {
+                            RichText::new(format!("{} (not found)", project.name())).weak()
+                        };
+                        ui.label(name).on_hover_text(project.path.to_string_lossy());
+                    });
                     row.col(|ui| {
-                        ui.label(format!("Test {}", row_index + 1));
+                        let elapsed = now.duration_since(project.last_opened).unwrap_or_default();
+                        ui.weak(format_time_ago(elapsed));
                     });
-                    if row.response().clicked() {
-                        error!("TODO");
+                    let response = row.response();
+                    if response.clicked() || response.double_clicked() {
+                        self.picked_path.clone_from(&project.path);
+                        path_picked = true;
+                        open = response.double_clicked();
                     }
+                    response.context_menu(|ui| {
+                        if ui.button("Remove from list").clicked() {
+                            removed = Some(i);
+                        }
+                    });
                 });
             });
+        if let Some(i) = removed {
+            self.recent_projects.0.remove(i);
+            self.recent_found.remove(i);
+            if let Some(storage) = frame.storage_mut() {
+                self.recent_projects.store(storage);
+            }
+        }
+        if open {
+            ui.close();
+        }
 
         button_strip_measurer.measure(ui, |ui| {
             ui.separator();
@@ -127,10 +215,18 @@ impl StartupDialog {
             });
         });
 
-        if path_changed {
+        if path_picked {
+            self.validation_debounce.trigger_now(time);
+        } else if path_edited {
+            self.path_validation_result.cancel();
+            self.validation_debounce.trigger(time);
+        }
+        if self.validation_debounce.poll(time) {
             let picked_path = self.picked_path.clone();
             self.path_validation_result
                 .launch(unblock(move || validate_smart_project_path(&picked_path)));
+        } else if let Some(remaining) = self.validation_debounce.remaining(time) {
+            ui.ctx().request_repaint_after(remaining);
         }
     }
 }
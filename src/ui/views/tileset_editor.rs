@@ -1,30 +1,65 @@
-use crate::gfx::SnesColor;
+use crate::gfx::{ColorDiff, SnesColor, TILE_SIZE};
 use crate::project::ProjectData;
 use crate::tileset;
-use crate::tileset::{Tileset, TilesetKind, TilesetRef};
+use crate::tileset::{Tileset, TilesetIndex, TilesetKind, TilesetRef, TilesetVramLayout};
+use crate::ui::clipboard;
+use crate::ui::settings::Settings;
 use crate::ui::tile_view;
-use crate::ui::views::EditorWindow;
+use crate::ui::tile_view::PaletteAnimation;
+use crate::ui::toasts::ToastLevel;
+use crate::ui::views::{
+    DockSide, EditorCommand, EditorKey, EditorWindow, HoverInfo, WorkspaceRequests, grid_cell_at,
+};
 use egui::emath::GuiRounding;
 use egui::load::SizedTexture;
-use egui::{Id, Rect, Response, Sense, Ui, Vec2, vec2};
+use egui::{
+    Align2, Color32, ColorImage, EventFilter, FontId, Id, Key, Modifiers, Painter, Pos2, Rect,
+    Response, Sense, StrokeKind, Ui, Vec2, pos2, vec2,
+};
+use std::collections::HashMap;
 
 const ID_SALT: &str = concat!(module_path!(), "::TilesetEditor");
 
 pub struct TilesetEditor {
     /// Main tileset being edited. Used for things like title.
     tileset: TilesetRef,
+    /// Cached from `tileset` so the window identity doesn't depend on the slotmap handle.
+    tileset_index: Option<(TilesetKind, TilesetIndex)>,
     /// Currently selected CRE to edit together with `tileset`.
     cre_tileset: Option<TilesetRef>,
+    /// Tileset whose palette is shown next to this one's for comparison.
+    compare_tileset: Option<TilesetRef>,
     /// Current palette line to preview GFX with.
     pal_line: usize,
+    /// While enabled, cycles `pal_line` through all lines of the palette and rotates the lines of
+    /// the tiletable view, to preview palette animations.
+    animation: PaletteAnimation,
+    /// Integer magnification applied to the GFX and tiletable views.
+    zoom_level: u8,
+    /// GFX tile and tiletable block that were last right-clicked, for their context menus.
+    context_tile: Option<usize>,
+    context_block: Option<usize>,
+    /// Block clicked in the tiletable view. Moved with the arrow keys while the view has focus.
+    selected_block: Option<usize>,
+    /// Tiletable view indices of the blocks found by "Find duplicates", highlighted until cleared.
+    duplicate_blocks: Option<Vec<usize>>,
+    /// Result of `ProjectData::compute_block_usage`, counted when the usage heatmap is enabled.
+    block_usage: Option<HashMap<TilesetRef, Vec<u32>>>,
+    /// Cached `Tileset::gfx_fingerprint`, to avoid rehashing the GFX every frame.
+    gfx_fingerprint: String,
 }
 
 const LAST_USED_CRE_KEY: &str = "last_used_cre";
+const SHOW_BLOCK_INDICES_KEY: &str = "show_block_indices";
 
-fn find_default_cre<'p>(ctx: &egui::Context, project_data: &'p ProjectData) -> Option<&'p Tileset> {
+pub(super) fn find_default_cre<'p>(
+    ctx: &egui::Context,
+    project_data: &'p ProjectData,
+) -> Option<&'p Tileset> {
     let id = Id::new(ID_SALT).with(LAST_USED_CRE_KEY);
-    if let Some(last_used_cre) = ctx.data_mut(|data| data.get_persisted::<TilesetRef>(id))
-        && let Some(tileset) = project_data.tilesets.get(last_used_cre)
+    if let Some(last_used_cre) = ctx.data_mut(|data| data.get_persisted::<TilesetIndex>(id))
+        && let Some(&tileset_ref) = project_data.cre_tileset_ids.get(&last_used_cre)
+        && let Some(tileset) = project_data.tilesets.get(tileset_ref)
     {
         return Some(tileset);
     }
@@ -37,18 +72,206 @@ fn find_default_cre<'p>(ctx: &egui::Context, project_data: &'p ProjectData) -> O
 }
 
 impl TilesetEditor {
-    pub fn new(ctx: &egui::Context, tileset: TilesetRef, project_data: &ProjectData) -> Self {
+    pub const ZOOM_LEVELS: std::ops::RangeInclusive<u8> = 1..=4;
+
+    pub fn new(
+        ctx: &egui::Context,
+        tileset: &Tileset,
+        project_data: &ProjectData,
+        settings: &Settings,
+    ) -> Self {
         Self {
-            tileset,
+            tileset: tileset.handle(),
+            tileset_index: tileset.index().map(|index| (tileset.kind, index)),
             cre_tileset: find_default_cre(ctx, project_data).map(Tileset::handle),
+            compare_tileset: None,
             pal_line: 0,
+            animation: PaletteAnimation::default(),
+            zoom_level: Self::default_zoom(settings),
+            context_tile: None,
+            context_block: None,
+            selected_block: None,
+            duplicate_blocks: None,
+            block_usage: None,
+            gfx_fingerprint: tileset.gfx_fingerprint(),
         }
     }
 
+    fn default_zoom(settings: &Settings) -> u8 {
+        settings
+            .default_zoom
+            .clamp(*Self::ZOOM_LEVELS.start(), *Self::ZOOM_LEVELS.end())
+    }
+
     fn tileset<'p>(&self, project_data: &'p ProjectData) -> Option<&'p Tileset> {
         project_data.tilesets.get(self.tileset)
     }
 
+    fn zoom_selector(ui: &mut Ui, zoom_level: &mut u8) {
+        ui.horizontal(|ui| {
+            ui.label("Zoom:");
+            for level in Self::ZOOM_LEVELS {
+                ui.selectable_value(zoom_level, level, format!("{level}×"));
+            }
+        });
+    }
+
+    fn scale_factor(&self, ui: &Ui) -> f32 {
+        // TODO: Implement a band-limited pixel art resizing shader or similar instead
+        f32::from(self.zoom_level).round_to_pixels(ui.pixels_per_point())
+    }
+
+    /// Labels each block of the tiletable with its index, in a color contrasting with the block.
+    fn draw_block_indices(painter: &Painter, origin: Pos2, scale_factor: f32, brightness: &[f32]) {
+        let block_size = (TILE_SIZE * 2) as f32 * scale_factor;
+        let font = FontId::monospace(4.0 * scale_factor);
+        for (block, &brightness) in brightness.iter().enumerate() {
+            let [x, y] = [
+                block % tile_view::TILETABLE_BLOCKS_PER_ROW,
+                block / tile_view::TILETABLE_BLOCKS_PER_ROW,
+            ];
+            let color = if brightness > 0.5 {
+                Color32::BLACK
+            } else {
+                Color32::WHITE
+            };
+            painter.text(
+                origin + vec2(x as f32, y as f32) * block_size + vec2(1.0, 0.0),
+                Align2::LEFT_TOP,
+                format!("{block:03X}"),
+                font.clone(),
+                color,
+            );
+        }
+    }
+
+    /// Moves `block` according to the navigation keys pressed this frame, staying within the first
+    /// `len` blocks. Page Up/Down move by a row, like Up/Down.
+    fn navigate_block(ui: &Ui, block: usize, len: usize) -> usize {
+        const ROW: usize = tile_view::TILETABLE_BLOCKS_PER_ROW;
+        let mut block = block.min(len.saturating_sub(1));
+        // Vertical moves keep the column, stopping at the first or last row that has it.
+        let move_up = |block: usize| if block >= ROW { block - ROW } else { block };
+        let move_down = |block: usize| {
+            if block + ROW < len {
+                block + ROW
+            } else {
+                block
+            }
+        };
+        ui.input_mut(|i| {
+            if i.consume_key(Modifiers::NONE, Key::ArrowLeft) {
+                block = block.saturating_sub(1);
+            }
+            if i.consume_key(Modifiers::NONE, Key::ArrowRight) {
+                block = (block + 1).min(len.saturating_sub(1));
+            }
+            for key in [Key::ArrowUp, Key::PageUp] {
+                if i.consume_key(Modifiers::NONE, key) {
+                    block = move_up(block);
+                }
+            }
+            for key in [Key::ArrowDown, Key::PageDown] {
+                if i.consume_key(Modifiers::NONE, key) {
+                    block = move_down(block);
+                }
+            }
+        });
+        block
+    }
+
+    fn draw_duplicate_blocks(painter: &Painter, origin: Pos2, scale_factor: f32, blocks: &[usize]) {
+        let block_size = (TILE_SIZE * 2) as f32 * scale_factor;
+        for &block in blocks {
+            let [x, y] = [
+                block % tile_view::TILETABLE_BLOCKS_PER_ROW,
+                block / tile_view::TILETABLE_BLOCKS_PER_ROW,
+            ];
+            let rect = Rect::from_min_size(
+                origin + vec2(x as f32, y as f32) * block_size,
+                Vec2::splat(block_size),
+            );
+            painter.rect_stroke(rect, 0, (2.0, Color32::YELLOW), StrokeKind::Inside);
+        }
+    }
+
+    /// Tints each block by how often rooms use it, from transparent for unused blocks to red for
+    /// the most used one. `usage` is indexed like the tiletable view.
+    fn draw_block_usage(painter: &Painter, origin: Pos2, scale_factor: f32, usage: &[u32]) {
+        let Some(&max) = usage.iter().max().filter(|&&max| max > 0) else {
+            return;
+        };
+        let block_size = (TILE_SIZE * 2) as f32 * scale_factor;
+        let hot = Color32::from_rgba_unmultiplied(255, 0, 0, 192);
+        for (block, &count) in usage.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let [x, y] = [
+                block % tile_view::TILETABLE_BLOCKS_PER_ROW,
+                block / tile_view::TILETABLE_BLOCKS_PER_ROW,
+            ];
+            let rect = Rect::from_min_size(
+                origin + vec2(x as f32, y as f32) * block_size,
+                Vec2::splat(block_size),
+            );
+            let t = count as f32 / max as f32;
+            painter.rect_filled(rect, 0, Color32::TRANSPARENT.lerp_to_gamma(hot, t));
+        }
+    }
+
+    /// Separates the CRE blocks at the start of the tiletable from the SCE ones, labeling both.
+    fn draw_cre_boundary(painter: &Painter, origin: Pos2, scale_factor: f32) {
+        const SCE_START: usize = 0x100;
+        let block_size = (TILE_SIZE * 2) as f32 * scale_factor;
+        let width = tile_view::TILETABLE_BLOCKS_PER_ROW as f32 * block_size;
+        let y = origin.y + (SCE_START / tile_view::TILETABLE_BLOCKS_PER_ROW) as f32 * block_size;
+        painter.hline(origin.x..=origin.x + width, y, (3.0, Color32::YELLOW));
+
+        let font = FontId::proportional(10.0);
+        for (label, pos, align) in [
+            (
+                "CRE ($000–$0FF)",
+                pos2(origin.x + 2.0, y - 3.0),
+                Align2::LEFT_BOTTOM,
+            ),
+            (
+                "SCE ($100–$3FF)",
+                pos2(origin.x + 2.0, y + 3.0),
+                Align2::LEFT_TOP,
+            ),
+        ] {
+            let galley = painter.layout_no_wrap(label.into(), font.clone(), Color32::YELLOW);
+            let rect = align.anchor_size(pos, galley.size());
+            painter.rect_filled(rect.expand(1.0), 2, Color32::BLACK.gamma_multiply(0.7));
+            painter.galley(rect.min, galley, Color32::YELLOW);
+        }
+    }
+
+    fn copy_image_to_clipboard(
+        requests: &mut WorkspaceRequests,
+        image: Option<ColorImage>,
+        description: &str,
+    ) {
+        let Some(image) = image else {
+            requests.show_toast(
+                ToastLevel::Warning,
+                format!("Nothing to copy for {description}"),
+            );
+            return;
+        };
+        match clipboard::copy_image(&image) {
+            Ok(()) => requests.show_toast(
+                ToastLevel::Success,
+                format!("Copied {description} to clipboard"),
+            ),
+            Err(e) => requests.show_toast(
+                ToastLevel::Error,
+                format!("Couldn't copy {description} to clipboard: {e}"),
+            ),
+        }
+    }
+
     fn draw_palette_grid(ui: &mut Ui, palette_lines: &[[SnesColor; 16]]) -> Response {
         const CELL_SIZE: f32 = 16.0;
 
@@ -68,22 +291,125 @@ impl TilesetEditor {
 
         res
     }
+
+    /// Draws a grid laid out like the palette, with a cell per entry colored by how it differs
+    /// from the compared palette. Hovering a changed entry shows the per-channel differences.
+    fn draw_palette_diff(ui: &mut Ui, diffs: &[ColorDiff]) -> Response {
+        const CELL_SIZE: f32 = 16.0;
+        const LINE_LEN: usize = 16;
+
+        let lines = diffs.len().div_ceil(LINE_LEN);
+        let (res, p) = ui.allocate_painter(vec2(16.0, lines as f32) * CELL_SIZE, Sense::HOVER);
+        for (i, diff) in diffs.iter().enumerate() {
+            let color = match diff {
+                ColorDiff::Identical => Color32::GRAY,
+                ColorDiff::Changed(_) | ColorDiff::Removed => Color32::RED,
+                ColorDiff::Added => Color32::GREEN,
+            };
+            let pos = vec2((i % LINE_LEN) as f32, (i / LINE_LEN) as f32) * CELL_SIZE;
+            let cell = Rect::from_min_size(res.rect.min + pos, Vec2::splat(CELL_SIZE));
+            p.rect_filled(cell.shrink(1.0), 0, color);
+        }
+
+        let hovered = res.hover_pos().and_then(|pointer| {
+            let pos = ((pointer - res.rect.min) / CELL_SIZE).floor();
+            let i = pos.y as usize * LINE_LEN + pos.x as usize;
+            Some((i, diffs.get(i)?))
+        });
+        match hovered {
+            Some((i, ColorDiff::Changed([r, g, b]))) => {
+                res.on_hover_text(format!("${i:02X}: ΔR {r}, ΔG {g}, ΔB {b}"))
+            }
+            Some((i, ColorDiff::Identical)) => res.on_hover_text(format!("${i:02X}: Identical")),
+            Some((i, ColorDiff::Added)) => {
+                res.on_hover_text(format!("${i:02X}: Only in compared palette"))
+            }
+            Some((i, ColorDiff::Removed)) => {
+                res.on_hover_text(format!("${i:02X}: Only in this palette"))
+            }
+            None => res,
+        }
+    }
+
+    fn compare_selector(&mut self, ui: &mut Ui, project_data: &ProjectData) {
+        let selected = self
+            .compare_tileset
+            .and_then(|hnd| project_data.tilesets.get(hnd));
+        egui::ComboBox::from_label("Compare with…")
+            .selected_text(selected.map_or("None".into(), Tileset::title))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.compare_tileset, None, "None");
+                let mut tilesets: Vec<_> = project_data
+                    .tilesets
+                    .values()
+                    .filter(|t| t.handle() != self.tileset && !t.palette.is_empty())
+                    .collect();
+                tilesets.sort_by(|a, b| a.display_cmp(b));
+                for tileset in tilesets {
+                    ui.selectable_value(
+                        &mut self.compare_tileset,
+                        Some(tileset.handle()),
+                        tileset.title(),
+                    );
+                }
+            });
+    }
+
+    /// Whether the tiletable expects the CRE to provide its first blocks, but the project has no
+    /// CRE tilesets to take them from.
+    fn is_missing_cre(layout: &TilesetVramLayout<&Tileset>, project_data: &ProjectData) -> bool {
+        layout.tiletable.entries.iter().all(|e| e.base >= 0x100)
+            && !project_data
+                .tilesets
+                .values()
+                .any(|tileset| tileset.kind == TilesetKind::Cre)
+    }
 }
 
 impl EditorWindow for TilesetEditor {
     fn title(&self, project_data: &ProjectData) -> String {
-        let tileset = self.tileset(project_data);
         format!(
             "Tileset: {}",
-            tileset.map_or("<UNKNOWN>".into(), |t| t.title())
+            project_data.tileset_display_name(self.tileset)
         )
     }
 
     fn stable_id(&self) -> Id {
-        Id::new(ID_SALT).with(self.tileset)
+        if let Some(tileset_index) = self.tileset_index {
+            Id::new(ID_SALT).with(tileset_index)
+        } else {
+            Id::new(ID_SALT).with(self.tileset)
+        }
     }
 
-    fn show_contents(&mut self, project_data: &mut ProjectData, ui: &mut Ui) {
+    fn editor_key(&self) -> Option<EditorKey> {
+        self.tileset_index
+            .map(|(kind, index)| EditorKey::Tileset(kind, index))
+    }
+
+    fn dock_side(&self) -> Option<DockSide> {
+        Some(DockSide::Right)
+    }
+
+    fn run_command(
+        &mut self,
+        command: EditorCommand,
+        _project_data: &mut ProjectData,
+        ctx: &egui::Context,
+    ) -> bool {
+        if command != EditorCommand::ResetZoom {
+            return false;
+        }
+        self.zoom_level = Self::default_zoom(&Settings::get(ctx));
+        true
+    }
+
+    fn show_contents(
+        &mut self,
+        project_data: &mut ProjectData,
+        requests: &mut WorkspaceRequests,
+        ui: &mut Ui,
+    ) {
         let Some(tileset) = self.tileset(project_data) else {
             ui.close();
             return;
@@ -97,14 +423,45 @@ impl EditorWindow for TilesetEditor {
 
         ui.horizontal_centered(|ui| {
             ui.vertical(|ui| {
-                let palette_lines = tileset_layout.palette_source.palette.as_4bpp_lines();
+                let palette = &tileset_layout.palette_source.palette;
+                let palette_lines = palette.as_4bpp_lines();
                 ui.group(|ui| {
-                    ui.label("Palette");
-                    Self::draw_palette_grid(ui, palette_lines);
+                    ui.label(format!("Palette ({} lines)", palette.line_count()));
+                    let compare_tileset = self
+                        .compare_tileset
+                        .and_then(|hnd| project_data.tilesets.get(hnd));
+                    ui.horizontal(|ui| {
+                        Self::draw_palette_grid(ui, palette_lines);
+                        if let Some(compare_tileset) = compare_tileset {
+                            Self::draw_palette_grid(ui, compare_tileset.palette.as_4bpp_lines());
+                        }
+                    });
+                    if let Some(compare_tileset) = compare_tileset {
+                        Self::draw_palette_diff(ui, &palette.diff(&compare_tileset.palette));
+                    }
+                    self.compare_selector(ui, project_data);
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.animation.enabled, "Animate")
+                            .on_hover_text(
+                                "Cycle the GFX preview through the palette lines, and rotate the \
+                            selected lines in the tiletable",
+                            );
+                        self.animation.show_controls(ui);
+                    });
+                    if self.animation.enabled {
+                        let step = self.animation.current_step(ui.ctx());
+                        self.pal_line = step % palette_lines.len().max(1);
+                    }
                 });
 
                 ui.group(|ui| {
                     ui.label("GFX");
+                    ui.label(format!(
+                        "{} tiles, SHA-256: {}",
+                        tileset.gfx_len(),
+                        self.gfx_fingerprint
+                    ))
+                    .on_hover_text("Hash of the tileset's own GFX data, excluding the CRE");
                     ui.horizontal(|ui| {
                         ui.label("Palette:");
                         ui.add(egui::Slider::new(
@@ -112,7 +469,9 @@ impl EditorWindow for TilesetEditor {
                             0..=palette_lines.len() - 1,
                         ));
                     });
+                    Self::zoom_selector(ui, &mut self.zoom_level);
 
+                    let scale_factor = self.scale_factor(ui);
                     egui::ScrollArea::vertical()
                         .max_height(f32::INFINITY)
                         .id_salt("gfx_scrollarea")
@@ -124,12 +483,36 @@ impl EditorWindow for TilesetEditor {
                                 self.pal_line as u8,
                             );
                             let sized_texture = SizedTexture::from_handle(&tex_handle);
-
-                            // TODO: Implement a band-limited pixel art resizing shader or similar instead
-                            let scale_factor = 2.0.round_to_pixels(ui.pixels_per_point());
-                            ui.add(
-                                egui::Image::new(sized_texture).fit_to_original_size(scale_factor),
+                            let response = ui.add(
+                                egui::Image::new(sized_texture)
+                                    .fit_to_original_size(scale_factor)
+                                    .sense(Sense::CLICK),
                             );
+                            let tile_size = TILE_SIZE as f32 * scale_factor;
+                            HoverInfo::publish_grid_cell(&response, tile_size, |_| None);
+                            if response.secondary_clicked()
+                                && let Some(pointer) = response.interact_pointer_pos()
+                            {
+                                let [x, y] = grid_cell_at(response.rect, tile_size, pointer);
+                                self.context_tile = Some(y * tile_view::GFX_TILES_PER_ROW + x);
+                            }
+                            response.context_menu(|ui| {
+                                if let Some(tile_id) = self.context_tile
+                                    && ui.button("Copy as image").clicked()
+                                {
+                                    let image = tile_view::render_gfx_tile(
+                                        &tileset_layout.gfx,
+                                        tileset_layout.palette_source,
+                                        self.pal_line as u8,
+                                        tile_id,
+                                    );
+                                    Self::copy_image_to_clipboard(
+                                        requests,
+                                        image,
+                                        &format!("tile ${tile_id:03X}"),
+                                    );
+                                }
+                            });
                         });
                 });
             });
@@ -137,19 +520,226 @@ impl EditorWindow for TilesetEditor {
             ui.vertical(|ui| {
                 ui.group(|ui| {
                     ui.label("Tiletable");
+                    if Self::is_missing_cre(&tileset_layout, project_data) {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            "⚠ CRE not loaded — blocks $000–$0FF may appear blank",
+                        );
+                    }
+                    let show_indices_id = Id::new(ID_SALT).with(SHOW_BLOCK_INDICES_KEY);
+                    let mut show_indices = ui.data_mut(|data| {
+                        *data.get_persisted_mut_or(show_indices_id, cfg!(debug_assertions))
+                    });
+                    ui.horizontal(|ui| {
+                        Self::zoom_selector(ui, &mut self.zoom_level);
+                        // Text is unreadable at 1× zoom
+                        ui.add_enabled(
+                            self.zoom_level >= 2,
+                            egui::Checkbox::new(&mut show_indices, "Show indices"),
+                        )
+                        .on_disabled_hover_text("Only available at 2× zoom or higher");
+                        if self.duplicate_blocks.is_some() {
+                            if ui.button("Clear duplicates").clicked() {
+                                self.duplicate_blocks = None;
+                            }
+                        } else if ui
+                            .button("Find duplicates")
+                            .on_hover_text("Highlight blocks that are flips or copies of another")
+                            .clicked()
+                        {
+                            let base = tileset_layout
+                                .tiletable
+                                .entries
+                                .iter()
+                                .find(|entry| entry.tileset.handle() == tileset.handle())
+                                .map_or(0, |entry| entry.base);
+                            let duplicates = tileset.find_duplicate_blocks();
+                            let mut blocks: Vec<usize> = duplicates
+                                .iter()
+                                .flat_map(|&(original, duplicate)| [original, duplicate])
+                                .map(|block| base + block)
+                                .collect();
+                            blocks.sort_unstable();
+                            blocks.dedup();
+                            requests.show_toast(
+                                ToastLevel::Info,
+                                format!("Found {} duplicate blocks", duplicates.len()),
+                            );
+                            self.duplicate_blocks = Some(blocks);
+                        }
+                        let mut show_usage = self.block_usage.is_some();
+                        if ui
+                            .checkbox(&mut show_usage, "Usage heatmap")
+                            .on_hover_text(
+                                "Color blocks by how often the project's rooms use them. \
+                                 Counted when enabled.",
+                            )
+                            .changed()
+                        {
+                            self.block_usage =
+                                show_usage.then(|| project_data.compute_block_usage());
+                        }
+                    });
+                    ui.data_mut(|data| data.insert_persisted(show_indices_id, show_indices));
+                    // Usage of each block in the tiletable view, which may span several tilesets
+                    let view_usage: Option<Vec<u32>> = self.block_usage.as_ref().map(|usage| {
+                        let len = tileset_layout
+                            .tiletable
+                            .valid_range()
+                            .map_or(0, |(_, end)| end);
+                        (0..len)
+                            .map(|block| {
+                                tileset_layout
+                                    .tiletable
+                                    .lookup(block)
+                                    .and_then(|(tileset, i)| usage.get(&tileset.handle())?.get(i))
+                                    .copied()
+                                    .unwrap_or(0)
+                            })
+                            .collect()
+                    });
+
+                    let scale_factor = self.scale_factor(ui);
                     egui::ScrollArea::both()
                         .max_width(f32::INFINITY)
                         .max_height(f32::INFINITY)
                         .id_salt("tiletable_scrollarea")
                         .show(ui, |ui| {
-                            let tex_handle =
-                                tile_view::get_tileset_ttb_texture(ui.ctx(), &tileset_layout);
+                            let tex_handle = tile_view::get_tileset_ttb_texture(
+                                ui.ctx(),
+                                &tileset_layout,
+                                self.animation.current_rotation(ui.ctx()),
+                            );
                             let sized_texture = SizedTexture::from_handle(&tex_handle);
-
-                            let scale_factor = 2.0.round_to_pixels(ui.pixels_per_point());
-                            ui.add(
-                                egui::Image::new(sized_texture).fit_to_original_size(scale_factor),
+                            let response = ui.add(
+                                egui::Image::new(sized_texture)
+                                    .fit_to_original_size(scale_factor)
+                                    .sense(Sense::click()),
                             );
+                            let block_size = (TILE_SIZE * 2) as f32 * scale_factor;
+                            let block_index =
+                                |[x, y]: [usize; 2]| y * tile_view::TILETABLE_BLOCKS_PER_ROW + x;
+                            let block_at = |pointer| {
+                                block_index(grid_cell_at(response.rect, block_size, pointer))
+                            };
+                            if let Some(cell) =
+                                HoverInfo::publish_grid_cell(&response, block_size, |cell| {
+                                    u16::try_from(block_index(cell)).ok()
+                                })
+                            {
+                                let block = block_index(cell);
+                                if let Some(count) =
+                                    view_usage.as_ref().and_then(|usage| usage.get(block))
+                                {
+                                    let plural = if *count == 1 { "" } else { "s" };
+                                    response.clone().on_hover_text_at_pointer(format!(
+                                        "Block ${block:03X}: used {count} time{plural}"
+                                    ));
+                                }
+                            }
+                            if response.secondary_clicked()
+                                && let Some(pointer) = response.interact_pointer_pos()
+                            {
+                                self.context_block = Some(block_at(pointer));
+                            }
+                            let len = tileset_layout
+                                .tiletable
+                                .valid_range()
+                                .map_or(0, |(_, end)| end);
+                            if response.clicked()
+                                && let Some(pointer) = response.interact_pointer_pos()
+                                && let block = block_at(pointer)
+                                && block < len
+                            {
+                                self.selected_block = Some(block);
+                                response.request_focus();
+                            }
+                            let mut scroll_to_selected = false;
+                            if response.has_focus()
+                                && let Some(block) = self.selected_block
+                            {
+                                // Keep arrow keys from moving the focus to other widgets
+                                ui.memory_mut(|mem| {
+                                    mem.set_focus_lock_filter(
+                                        response.id,
+                                        EventFilter {
+                                            horizontal_arrows: true,
+                                            vertical_arrows: true,
+                                            ..Default::default()
+                                        },
+                                    );
+                                });
+                                let moved = Self::navigate_block(ui, block, len);
+                                scroll_to_selected = moved != block;
+                                self.selected_block = Some(moved);
+                            }
+                            if let Some(block) = self.selected_block {
+                                let [x, y] = [
+                                    block % tile_view::TILETABLE_BLOCKS_PER_ROW,
+                                    block / tile_view::TILETABLE_BLOCKS_PER_ROW,
+                                ];
+                                let rect = Rect::from_min_size(
+                                    response.rect.min + vec2(x as f32, y as f32) * block_size,
+                                    Vec2::splat(block_size),
+                                );
+                                ui.painter_at(response.rect).rect_stroke(
+                                    rect,
+                                    0,
+                                    (2.0, ui.visuals().selection.stroke.color),
+                                    StrokeKind::Inside,
+                                );
+                                if scroll_to_selected {
+                                    ui.scroll_to_rect(rect, None);
+                                }
+                            }
+                            response.context_menu(|ui| {
+                                if let Some(block) = self.context_block
+                                    && ui.button("Copy as image").clicked()
+                                {
+                                    let image =
+                                        tile_view::render_tiletable_block(&tileset_layout, block);
+                                    Self::copy_image_to_clipboard(
+                                        requests,
+                                        image,
+                                        &format!("block ${block:03X}"),
+                                    );
+                                }
+                            });
+                            if show_indices && self.zoom_level >= 2 {
+                                let brightness = tile_view::get_tiletable_block_brightness(
+                                    ui.ctx(),
+                                    &tileset_layout,
+                                );
+                                Self::draw_block_indices(
+                                    &ui.painter_at(response.rect),
+                                    response.rect.min,
+                                    scale_factor,
+                                    &brightness,
+                                );
+                            }
+                            if let Some(usage) = &view_usage {
+                                Self::draw_block_usage(
+                                    &ui.painter_at(response.rect),
+                                    response.rect.min,
+                                    scale_factor,
+                                    usage,
+                                );
+                            }
+                            if let Some(blocks) = &self.duplicate_blocks {
+                                Self::draw_duplicate_blocks(
+                                    &ui.painter_at(response.rect),
+                                    response.rect.min,
+                                    scale_factor,
+                                    blocks,
+                                );
+                            }
+                            if cre_tileset.is_some() && !tileset::is_ceres_tileset(tileset) {
+                                Self::draw_cre_boundary(
+                                    &ui.painter_at(response.rect),
+                                    response.rect.min,
+                                    scale_factor,
+                                );
+                            }
                             // tile_view::draw_tiletable_grid(ui, &tileset_layout, scale_factor);
                         });
                 })
@@ -1,83 +1,1526 @@
-use crate::project::ProjectData;
-use crate::ui::views::EditorWindow;
+use crate::archive;
+use crate::diagnostic::{Diagnostic, DiagnosticLocation};
+use crate::hex_types::HexU8;
+use crate::project;
+use crate::project::{ProjectData, load_smart_project};
+use crate::room;
+use crate::room::{Room, RoomRef};
+use crate::tileset::{TilesetKind, TilesetRef};
+use crate::ui::file_manager;
+use crate::ui::project_drop::ProjectDropTarget;
+use crate::ui::project_watcher::ProjectWatcher;
+use crate::ui::promise::{EguiWaker, LocalBoxFuture, Promise};
+use crate::ui::settings::Settings;
+use crate::ui::tile_view;
+use crate::ui::toasts::{ToastLevel, Toasts};
+use crate::ui::views;
+use crate::ui::views::area_overview::AreaOverview;
+use crate::ui::views::map_viewer::MapViewer;
+use crate::ui::views::room_editor;
 use crate::ui::views::room_editor::RoomEditor;
-use crate::ui::views::tileset_editor::TilesetEditor;
-use egui::{LayerId, Order};
+use crate::ui::views::search_palette::{PaletteAction, PaletteCommand, SearchPalette};
+use crate::ui::views::settings_window::SettingsWindow;
+use crate::ui::views::sprite_gfx_viewer::SpriteGfxViewer;
+use crate::ui::views::tileset_editor::{TilesetEditor, find_default_cre};
+use crate::ui::views::{
+    DockSide, EditorCommand, EditorKey, EditorWindow, HoverInfo, WorkspaceRequests,
+};
+use crate::{snes_constants, tileset};
+use anyhow::Context;
+use blocking::{Task, unblock};
+use egui::{Id, Key, KeyboardShortcut, LayerId, Modifiers, Order};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const OPEN_EDITORS_KEY: &str = concat!(module_path!(), "::open_editors");
+const DOCKED_LAYOUT_KEY: &str = concat!(module_path!(), "::docked_layout");
+const GO_TO_ROOM: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::G);
+const SEARCH_PALETTE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::P);
+/// Action of the toast offering to reload the project after it changed on disk.
+const RELOAD_ACTION: &str = "Reload";
 
 pub struct Workspace {
     project_data: ProjectData,
 
     open_editors: Vec<Box<dyn EditorWindow>>,
+    toasts: Toasts,
+    /// Open "go to" palette, toggled with Ctrl+P.
+    search_palette: Option<SearchPalette>,
+    /// Text typed in the "Go to room" dialog, while it's open.
+    go_to_room: Option<String>,
+    /// Group of rooms listed in the sidebar. `None` lists all of them, grouped by area.
+    room_group_filter: Option<RoomGroup>,
+    /// Whether the latest room and tileset editors are docked into panes instead of floating.
+    docked: bool,
+
+    /// Destination picked in the "Export → Archive..." save dialog.
+    archive_path: Promise<LocalBoxFuture<Option<rfd::FileHandle>>>,
+    /// Number of files archived by a running export.
+    archive_export: Promise<Task<anyhow::Result<usize>>>,
+    /// Destination picked in the "Export → Room list as CSV..." save dialog.
+    rooms_csv_path: Promise<LocalBoxFuture<Option<rfd::FileHandle>>>,
+    /// Project being loaded again from disk, which replaces the current one once done.
+    project_reload: Promise<Task<anyhow::Result<ProjectData>>>,
+    /// Notices changes made to the project by other programs, if enabled.
+    watcher: Option<ProjectWatcher>,
+    /// `Settings::watch_project` as of the last time `watcher` was started or stopped.
+    watch_project: bool,
+    /// Summary of a running "Debug → Benchmark Render", once it's done.
+    render_benchmark: Promise<Task<String>>,
+    /// Shows the texture cache statistics overlay. Only available in debug builds.
+    show_cache_stats: bool,
+    /// Set by "File → Open Project...", to go back to the startup dialog.
+    close_requested: bool,
+    drop_target: ProjectDropTarget,
+    /// Action waiting for confirmation to discard unsaved changes.
+    confirm_discard: Option<DiscardAction>,
+    /// Project to open in place of this one, once any unsaved changes are confirmed discarded.
+    open_requested: Option<PathBuf>,
+    /// Set once quitting was confirmed, so the window is let to close.
+    quit_confirmed: bool,
+}
+
+/// Group of rooms in the sidebar.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum RoomGroup {
+    Area(u8),
+    /// Rooms without an index, or in an area the vanilla game doesn't have.
+    Other,
+}
+
+impl RoomGroup {
+    fn of(room: &Room) -> Self {
+        match room.index() {
+            Some((area, _)) if snes_constants::area_name(area).is_some() => RoomGroup::Area(area),
+            _ => RoomGroup::Other,
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            RoomGroup::Area(area) => {
+                let name = snes_constants::area_name(area).unwrap_or_default();
+                format!("{} {name}", HexU8(area))
+            }
+            RoomGroup::Other => "Other".into(),
+        }
+    }
+}
+
+/// Action picked from the sidebar's room and tileset lists.
+enum SidebarAction {
+    Open(Box<dyn EditorWindow>),
+    /// Re-reads the room from disk, discarding unsaved changes to it.
+    ReloadRoom(RoomRef),
+    /// Re-reads the tileset from disk, discarding unsaved changes to it.
+    ReloadTileset(TilesetRef),
+    /// Shows the room's file in the system file manager.
+    RevealRoom(RoomRef),
+    /// Shows the tileset's directory in the system file manager.
+    RevealTileset(TilesetRef),
+    /// Opens the editor for the part of the project a diagnostic points to.
+    ShowLocation(DiagnosticLocation),
+}
+
+/// Action that throws away unsaved room edits, so it's confirmed first if there are any.
+enum DiscardAction {
+    /// Opens another project, dropped onto the window.
+    OpenProject(PathBuf),
+    /// Loads the project again from disk.
+    ReloadProject,
+    /// Goes back to the startup dialog.
+    CloseProject,
+    /// Closes the window.
+    Quit,
+    /// Re-reads a room from disk.
+    ReloadRoom(RoomRef),
+}
+
+impl EditorKey {
+    fn open_editor(
+        self,
+        ctx: &egui::Context,
+        project_data: &ProjectData,
+    ) -> Option<Box<dyn EditorWindow>> {
+        match self {
+            EditorKey::Room((area, index)) => {
+                let room = project_data
+                    .rooms
+                    .get(project_data.lookup_room_by_index(area, index)?)?;
+                Some(Box::new(RoomEditor::new(room, &Settings::get(ctx))))
+            }
+            EditorKey::Tileset(kind, tileset_index) => {
+                let tileset_ids = match kind {
+                    TilesetKind::Sce => &project_data.tileset_ids,
+                    TilesetKind::Cre => &project_data.cre_tileset_ids,
+                };
+                let tileset = project_data
+                    .tilesets
+                    .get(*tileset_ids.get(&tileset_index)?)?;
+                Some(Box::new(TilesetEditor::new(
+                    ctx,
+                    tileset,
+                    project_data,
+                    &Settings::get(ctx),
+                )))
+            }
+            EditorKey::AreaMap(area) => project_data
+                .area_maps
+                .contains_key(&area)
+                .then(|| Box::new(MapViewer::new(area, project_data)) as Box<dyn EditorWindow>),
+            EditorKey::AreaOverview(area) => Some(Box::new(AreaOverview::new(area))),
+            EditorKey::SpriteGfx(area) => Some(Box::new(SpriteGfxViewer::new(area))),
+        }
+    }
+
+    /// Short description of the edited object, for messages.
+    fn description(self) -> String {
+        match self {
+            EditorKey::Room((area, room)) => format!("Room [{area:02X},{room:02X}]"),
+            EditorKey::Tileset(TilesetKind::Sce, index) => format!("Tileset [{index:02X}]"),
+            EditorKey::Tileset(TilesetKind::Cre, index) => format!("CRE tileset [{index:02X}]"),
+            EditorKey::AreaMap(area) => format!("Area {} map", HexU8(area)),
+            EditorKey::AreaOverview(area) => format!("Area {} overview", HexU8(area)),
+            EditorKey::SpriteGfx(area) => format!("Area {} enemy GFX pointers", HexU8(area)),
+        }
+    }
 }
 
 impl Workspace {
-    pub fn new(project_data: ProjectData) -> Self {
+    pub fn new(ctx: &egui::Context, project_data: ProjectData) -> Self {
+        let saved_editors = ctx
+            .data_mut(|data| data.get_persisted::<Vec<EditorKey>>(Id::new(OPEN_EDITORS_KEY)))
+            .unwrap_or_default();
+        let open_editors = saved_editors
+            .into_iter()
+            .filter_map(|key| key.open_editor(ctx, &project_data))
+            .collect();
+
+        let mut toasts = Toasts::default();
+        for diagnostic in &project_data.diagnostics {
+            toasts.add(ctx, diagnostic.severity.into(), &diagnostic.message);
+        }
+
+        let watch_project = Settings::get(ctx).watch_project;
+        let watcher = watch_project
+            .then(|| Self::start_watcher(ctx, &project_data, &mut toasts))
+            .flatten();
+
+        let waker = EguiWaker::for_context(ctx);
         Self {
             project_data,
-            open_editors: Vec::new(),
+            open_editors,
+            toasts,
+            search_palette: None,
+            go_to_room: None,
+            room_group_filter: None,
+            docked: ctx
+                .data_mut(|data| data.get_persisted(Id::new(DOCKED_LAYOUT_KEY)))
+                .unwrap_or(false),
+            archive_path: Promise::new(waker.clone()),
+            archive_export: Promise::new(waker.clone()),
+            render_benchmark: Promise::new(waker.clone()),
+            rooms_csv_path: Promise::new(waker.clone()),
+            project_reload: Promise::new(waker),
+            watcher,
+            watch_project,
+            show_cache_stats: false,
+            close_requested: false,
+            drop_target: ProjectDropTarget::new(ctx),
+            confirm_discard: None,
+            open_requested: None,
+            quit_confirmed: false,
+        }
+    }
+
+    fn start_watcher(
+        ctx: &egui::Context,
+        project_data: &ProjectData,
+        toasts: &mut Toasts,
+    ) -> Option<ProjectWatcher> {
+        match ProjectWatcher::new(ctx, &project_data.project_path) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::error!("Failed to watch project directory: {e}");
+                let message = format!("Failed to watch project directory for changes: {e}");
+                toasts.add(ctx, ToastLevel::Error, message);
+                None
+            }
+        }
+    }
+
+    /// Offers to reload the project when the watcher notices it changed on disk.
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        let watch_project = Settings::get(ctx).watch_project;
+        if watch_project != self.watch_project {
+            self.watch_project = watch_project;
+            self.watcher = watch_project
+                .then(|| Self::start_watcher(ctx, &self.project_data, &mut self.toasts))
+                .flatten();
+        }
+        if let Some(watcher) = &self.watcher
+            && watcher.poll(ctx)
+            && !self.project_reload.is_pending()
+        {
+            self.toasts.add_with_action(
+                ToastLevel::Info,
+                "Project changed on disk — Reload?",
+                RELOAD_ACTION,
+            );
+        }
+    }
+
+    fn start_reload(&mut self, ctx: &egui::Context) {
+        if self.project_reload.is_pending() {
+            return;
+        }
+        let project_path = self.project_data.project_path.clone();
+        let load_mode = Settings::get(ctx).load_mode;
+        self.project_reload.launch(unblock(move || {
+            load_smart_project(&project_path, load_mode)
+        }));
+    }
+
+    /// Swaps in the reloaded project once it's done loading, reopening the editors for the same
+    /// rooms and tilesets in it. Editors whose object no longer exists are closed.
+    fn poll_project_reload(&mut self, ctx: &egui::Context) {
+        if self.project_reload.is_pending() {
+            egui::Modal::new(Id::new("reloading_project_spinner")).show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.spinner();
+                    ui.label("Reloading project...");
+                });
+            });
         }
+        match self.project_reload.take_response() {
+            None => {}
+            Some(Ok(project_data)) => {
+                self.project_data = project_data;
+                tile_view::clear_textures(ctx);
+
+                let keys: Vec<EditorKey> = self
+                    .open_editors
+                    .drain(..)
+                    .filter_map(|e| e.editor_key())
+                    .collect();
+                for key in keys {
+                    if let Some(editor) = key.open_editor(ctx, &self.project_data) {
+                        self.open_editors.push(editor);
+                    } else {
+                        let message = format!(
+                            "{} no longer exists, its editor was closed",
+                            key.description()
+                        );
+                        self.toasts.add(ctx, ToastLevel::Warning, message);
+                    }
+                }
+
+                for diagnostic in &self.project_data.diagnostics {
+                    self.toasts
+                        .add(ctx, diagnostic.severity.into(), &diagnostic.message);
+                }
+                self.toasts
+                    .add(ctx, ToastLevel::Success, "Reloaded project from disk");
+            }
+            Some(Err(e)) => {
+                tracing::error!("Failed to reload project: {e:#}");
+                let message = format!("Failed to reload project: {e:#}");
+                self.toasts.add(ctx, ToastLevel::Error, message);
+            }
+        }
+    }
+
+    fn save_open_editors(&self, ctx: &egui::Context) {
+        let keys: Vec<EditorKey> = self
+            .open_editors
+            .iter()
+            .filter_map(|e| e.editor_key())
+            .collect();
+        ctx.data_mut(|data| data.insert_persisted(Id::new(OPEN_EDITORS_KEY), keys));
     }
 
     fn open_editor(&mut self, ctx: &egui::Context, editor: Box<dyn EditorWindow>) {
         // If there's an existing editor open, bring that to front instead
         let editor_id = editor.stable_id();
-        if let Some(existing_id) = self
+        if let Some(existing_index) = self
             .open_editors
             .iter()
-            .map(|e| e.stable_id())
-            .find(|id| *id == editor_id)
+            .position(|e| e.stable_id() == editor_id)
         {
-            let layer_id = LayerId::new(Order::Middle, existing_id);
+            let layer_id = LayerId::new(Order::Middle, editor_id);
             ctx.move_to_top(layer_id);
+            // Latest editors are the ones docked, so move it to the end
+            let existing = self.open_editors.remove(existing_index);
+            self.open_editors.push(existing);
         } else {
             self.open_editors.push(editor);
         }
     }
 
-    pub fn show(&mut self, ctx: &egui::Context) {
-        let mut new_editor: Option<Box<dyn EditorWindow>> = None;
+    /// Starts timing renders of `tileset` in the background, with the same layout the tileset
+    /// editor would use. The results are shown in a toast once done. Release builds should be used
+    /// for meaningful timings.
+    fn start_benchmark_render(&mut self, ctx: &egui::Context, tileset_ref: TilesetRef) {
+        const ITERATIONS: usize = 1000;
 
-        egui::SidePanel::left("editor_list").show(ctx, |ui| {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.collapsing("Rooms", |ui| {
-                    for (room_ref, room) in &self.project_data.rooms {
+        let Some(tileset) = self.project_data.tilesets.get(tileset_ref) else {
+            return;
+        };
+        // Copied, since the project can change while the benchmark runs
+        let tileset = tileset.clone();
+        let cre_tileset = find_default_cre(ctx, &self.project_data).cloned();
+        let message = format!("Benchmarking {}...", tileset.title());
+        self.toasts.add(ctx, ToastLevel::Info, message);
+        self.render_benchmark.launch(unblock(move || {
+            let layout = tileset::detect_sources_layout(&tileset, cre_tileset.as_ref());
+            let result = tile_view::benchmark_tiletable_render(&layout, ITERATIONS);
+            let message = format!(
+                "Rendered {} {} times: mean {:.2?}, median {:.2?}, p99 {:.2?}",
+                tileset.title(),
+                result.iterations,
+                result.mean,
+                result.median,
+                result.p99,
+            );
+            tracing::info!("{message}");
+            message
+        }));
+    }
+
+    fn poll_render_benchmark(&mut self, ctx: &egui::Context) {
+        if let Some(message) = self.render_benchmark.take_response() {
+            self.toasts.add(ctx, ToastLevel::Info, message);
+        }
+    }
+
+    fn show_menu_bar(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        const RELOAD: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::R);
+
+        let mut benchmark_tileset = None;
+        let mut reload = ctx.input_mut(|i| i.consume_shortcut(&RELOAD));
+        let mut open_settings = false;
+        let mut editor_command = None;
+        let mut toggle_area_map = false;
+        let mut discard_action = None;
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::MenuBar::new().ui(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui
+                        .button("Open Project...")
+                        .on_hover_text("Close this project and pick another one")
+                        .clicked()
+                    {
+                        discard_action = Some(DiscardAction::CloseProject);
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.project_reload.is_pending(),
+                            egui::Button::new("Reload Project")
+                                .shortcut_text(ctx.format_shortcut(&RELOAD)),
+                        )
+                        .on_hover_text(
+                            "Load the project again, picking up changes made outside of the editor",
+                        )
+                        .clicked()
+                    {
+                        reload = true;
+                    }
+                    let mut settings = Settings::get(ctx);
+                    if ui
+                        .checkbox(&mut settings.watch_project, "Watch for external changes")
+                        .on_hover_text("Offer to reload the project when its files change on disk")
+                        .changed()
+                    {
+                        settings.store(ctx);
+                    }
+                    Self::todo_menu_item(ui, "Save All");
+                    ui.menu_button("Export", |ui| {
+                        let exporting =
+                            self.archive_path.is_pending() || self.archive_export.is_pending();
                         if ui
-                            .add(egui::Button::new(room.title()).frame_when_inactive(false))
+                            .add_enabled(!exporting, egui::Button::new("Archive..."))
                             .clicked()
                         {
-                            new_editor = Some(Box::new(RoomEditor::new(room_ref)));
+                            self.pick_archive_path(frame);
                         }
+                        if ui
+                            .add_enabled(
+                                !self.rooms_csv_path.is_pending(),
+                                egui::Button::new("Room list as CSV..."),
+                            )
+                            .on_hover_text("Save a table of the metadata of every room")
+                            .clicked()
+                        {
+                            self.pick_rooms_csv_path(frame);
+                        }
+                    });
+                    ui.separator();
+                    if ui.button("Settings...").clicked() {
+                        open_settings = true;
+                    }
+                    ui.separator();
+                    if ui.button("Quit").clicked() {
+                        discard_action = Some(DiscardAction::Quit);
+                    }
+                });
+                ui.menu_button("Edit", |ui| {
+                    if ui
+                        .add(
+                            egui::Button::new("Undo")
+                                .shortcut_text(ctx.format_shortcut(&room_editor::UNDO)),
+                        )
+                        .clicked()
+                    {
+                        editor_command = Some(EditorCommand::Undo);
+                    }
+                    if ui
+                        .add(
+                            egui::Button::new("Redo")
+                                .shortcut_text(ctx.format_shortcut(&room_editor::REDO)),
+                        )
+                        .clicked()
+                    {
+                        editor_command = Some(EditorCommand::Redo);
+                    }
+                    ui.separator();
+                    if ui
+                        .button("Copy Room Summary")
+                        .on_hover_text("Copy a text description of the active room")
+                        .clicked()
+                    {
+                        editor_command = Some(EditorCommand::CopyRoomSummary);
+                    }
+                    ui.separator();
+                    if ui
+                        .add(
+                            egui::Button::new("Find...")
+                                .shortcut_text(ctx.format_shortcut(&SEARCH_PALETTE)),
+                        )
+                        .clicked()
+                    {
+                        self.search_palette = Some(SearchPalette::default());
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    if ui
+                        .add(
+                            egui::Button::new("Go to Room...")
+                                .shortcut_text(ctx.format_shortcut(&GO_TO_ROOM)),
+                        )
+                        .clicked()
+                    {
+                        self.go_to_room = Some(String::new());
+                    }
+                    if ui.button("Toggle Area Map").clicked() {
+                        toggle_area_map = true;
+                    }
+                    ui.separator();
+                    if ui
+                        .button("Toggle All Overlays")
+                        .on_hover_text("Show or hide every overlay of the active room editor")
+                        .clicked()
+                    {
+                        editor_command = Some(EditorCommand::ToggleOverlays);
+                    }
+                    if ui
+                        .button("Reset Zoom")
+                        .on_hover_text("Set the active tileset editor to the default zoom")
+                        .clicked()
+                    {
+                        editor_command = Some(EditorCommand::ResetZoom);
+                    }
+                    ui.separator();
+                    let label = if self.docked {
+                        "Float layout"
+                    } else {
+                        "Dock layout"
+                    };
+                    if ui
+                        .button(label)
+                        .on_hover_text("Dock the latest room and tileset editors side by side")
+                        .clicked()
+                    {
+                        self.docked = !self.docked;
+                        ctx.data_mut(|data| {
+                            data.insert_persisted(Id::new(DOCKED_LAYOUT_KEY), self.docked);
+                        });
+                    }
+                    ui.separator();
+                    if ui
+                        .button("Clear Texture Cache")
+                        .on_hover_text("Free the GPU memory used by rendered rooms and tilesets")
+                        .clicked()
+                    {
+                        tile_view::clear_textures(ctx);
+                    }
+                });
+                ui.menu_button("Help", |ui| {
+                    if ui.button("About").clicked() {
+                        let message = concat!(
+                            env!("CARGO_PKG_DESCRIPTION"),
+                            ", version ",
+                            env!("CARGO_PKG_VERSION")
+                        );
+                        self.toasts.add(ctx, ToastLevel::Info, message);
+                    }
+                });
+                ui.menu_button("Debug", |ui| {
+                    // Benchmarks need release builds to be meaningful, but the cache stats stay debug-only
+                    if cfg!(debug_assertions) {
+                        ui.checkbox(&mut self.show_cache_stats, "Texture Cache Stats");
+                    }
+                    ui.add_enabled_ui(!self.render_benchmark.is_pending(), |ui| {
+                        ui.menu_button("Benchmark Render", |ui| {
+                            egui::ScrollArea::vertical().show(ui, |ui| {
+                                let mut tilesets: Vec<_> = self
+                                    .project_data
+                                    .tilesets
+                                    .values()
+                                    .filter(|t| t.kind == TilesetKind::Sce && !t.palette.is_empty())
+                                    .collect();
+                                tilesets.sort_by(|a, b| a.display_cmp(b));
+                                for tileset in tilesets {
+                                    if ui.button(tileset.title()).clicked() {
+                                        benchmark_tileset = Some(tileset.handle());
+                                    }
+                                }
+                            });
+                        });
+                    });
+                });
+            });
+        });
+
+        if reload {
+            discard_action = Some(DiscardAction::ReloadProject);
+        }
+        if let Some(action) = discard_action {
+            self.confirm_discard(ctx, action);
+        }
+        if open_settings {
+            self.open_editor(ctx, Box::new(SettingsWindow));
+        }
+        if let Some(command) = editor_command {
+            self.run_editor_command(ctx, command);
+        }
+        if toggle_area_map {
+            self.toggle_area_map(ctx);
+        }
+        if let Some(tileset_ref) = benchmark_tileset {
+            self.start_benchmark_render(ctx, tileset_ref);
+        }
+    }
+
+    /// Menu item for a command that isn't implemented yet, shown greyed out.
+    fn todo_menu_item(ui: &mut egui::Ui, label: &str) {
+        if ui
+            .button(egui::RichText::new(label).weak())
+            .on_hover_text("Not implemented yet")
+            .clicked()
+        {
+            tracing::warn!("TODO: menu item {label}");
+        }
+    }
+
+    /// Index of the editor that menu bar commands apply to: the topmost floating window, or the
+    /// latest opened editor if no window is open.
+    fn active_editor(&self, ctx: &egui::Context) -> Option<usize> {
+        let top_layer = ctx.memory(|mem| mem.areas().top_layer_id(Order::Middle));
+        top_layer
+            .and_then(|layer| {
+                self.open_editors
+                    .iter()
+                    .position(|e| LayerId::new(Order::Middle, e.stable_id()) == layer)
+            })
+            .or_else(|| self.open_editors.len().checked_sub(1))
+    }
+
+    fn run_editor_command(&mut self, ctx: &egui::Context, command: EditorCommand) {
+        let handled = self.active_editor(ctx).is_some_and(|i| {
+            self.open_editors[i].run_command(command, &mut self.project_data, ctx)
+        });
+        if !handled {
+            let message = format!("{command:?} isn't available in the active editor");
+            self.toasts.add(ctx, ToastLevel::Info, message);
+        }
+    }
+
+    /// Closes the area map viewer if one is open. Otherwise opens the map of the active room's
+    /// area, or of the first area with a map.
+    fn toggle_area_map(&mut self, ctx: &egui::Context) {
+        let is_map = |e: &dyn EditorWindow| matches!(e.editor_key(), Some(EditorKey::AreaMap(_)));
+        if self.open_editors.iter().any(|e| is_map(e.as_ref())) {
+            self.open_editors.retain(|e| !is_map(e.as_ref()));
+            return;
+        }
+        let active_area = self
+            .active_editor(ctx)
+            .and_then(|i| match self.open_editors[i].editor_key() {
+                Some(EditorKey::Room((area, _))) => Some(area),
+                _ => None,
+            })
+            .filter(|area| self.project_data.area_maps.contains_key(area));
+        let Some(area) = active_area.or_else(|| self.project_data.area_maps.keys().next().copied())
+        else {
+            self.toasts
+                .add(ctx, ToastLevel::Info, "The project has no area maps");
+            return;
+        };
+        self.open_editor(ctx, Box::new(MapViewer::new(area, &self.project_data)));
+    }
+
+    /// Whether "File → Open Project..." was picked, so the project should be closed.
+    pub fn close_requested(&self) -> bool {
+        self.close_requested
+    }
+
+    /// Project dropped onto the window to be opened instead of this one, if any.
+    pub fn take_open_request(&mut self) -> Option<PathBuf> {
+        self.open_requested.take()
+    }
+
+    fn project_name(&self) -> String {
+        self.project_data
+            .project_path
+            .file_name()
+            .map_or("project".into(), |name| name.to_string_lossy().into_owned())
+    }
+
+    /// Asks where to save the project archive. The export starts once a destination is picked.
+    fn pick_archive_path(&mut self, frame: &eframe::Frame) {
+        if self.archive_path.is_pending() || self.archive_export.is_pending() {
+            return;
+        }
+        let file_name = format!("{}.zip", self.project_name());
+        self.archive_path.launch(Box::pin(
+            rfd::AsyncFileDialog::new()
+                .set_parent(frame)
+                .add_filter("ZIP archive", &["zip"])
+                .set_file_name(file_name)
+                .save_file(),
+        ));
+    }
+
+    fn pick_rooms_csv_path(&mut self, frame: &eframe::Frame) {
+        if self.rooms_csv_path.is_pending() {
+            return;
+        }
+        let file_name = format!("{} rooms.csv", self.project_name());
+        self.rooms_csv_path.launch(Box::pin(
+            rfd::AsyncFileDialog::new()
+                .set_parent(frame)
+                .add_filter("CSV table", &["csv"])
+                .set_file_name(file_name)
+                .save_file(),
+        ));
+    }
+
+    /// Starts the export once a destination has been picked, and reports its result.
+    fn poll_archive_export(&mut self, ctx: &egui::Context) {
+        if let Some(Some(file)) = self.archive_path.take_response() {
+            let project_path = self.project_data.project_path.clone();
+            let output_path = file.path().to_owned();
+            self.archive_export.launch(unblock(move || {
+                archive::export_project_archive(&project_path, &output_path)
+            }));
+        }
+        match self.archive_export.take_response() {
+            None => {}
+            Some(Ok(file_count)) => {
+                let message = format!("Exported {file_count} files to archive");
+                self.toasts.add(ctx, ToastLevel::Success, message);
+            }
+            Some(Err(e)) => {
+                tracing::error!("Failed to export archive: {e:#}");
+                let message = format!("Failed to export archive: {e:#}");
+                self.toasts.add(ctx, ToastLevel::Error, message);
+            }
+        }
+    }
+
+    fn write_rooms_csv(project_data: &ProjectData, path: &Path) -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        project::export_rooms_csv(project_data, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the room list once a destination has been picked, and reports the result.
+    fn poll_rooms_csv_export(&mut self, ctx: &egui::Context) {
+        let Some(Some(file)) = self.rooms_csv_path.take_response() else {
+            return;
+        };
+        match Self::write_rooms_csv(&self.project_data, file.path()) {
+            Ok(()) => {
+                let message = format!("Exported {} rooms to CSV", self.project_data.room_ids.len());
+                self.toasts.add(ctx, ToastLevel::Success, message);
+            }
+            Err(e) => {
+                tracing::error!("Failed to export room list: {e:#}");
+                let message = format!("Failed to export room list: {e:#}");
+                self.toasts.add(ctx, ToastLevel::Error, message);
+            }
+        }
+    }
+
+    /// Index of the editor shown in each pane of the docked layout: the latest one for each side.
+    fn docked_editors(&self) -> [Option<usize>; 2] {
+        [DockSide::Left, DockSide::Right].map(|side| {
+            self.open_editors
+                .iter()
+                .rposition(|e| e.dock_side() == Some(side))
+        })
+    }
+
+    /// Shows an editor filling a pane of the docked layout. Returns whether it was closed.
+    fn show_docked_editor(
+        editor: &mut dyn EditorWindow,
+        project_data: &mut ProjectData,
+        requests: &mut WorkspaceRequests,
+        ui: &mut egui::Ui,
+    ) -> bool {
+        let mut closed = false;
+        ui.horizontal(|ui| {
+            ui.heading(editor.title(project_data));
+            if ui.small_button("✖").on_hover_text("Close").clicked() {
+                closed = true;
+            }
+        });
+        ui.separator();
+        ui.push_id(editor.stable_id(), |ui| {
+            editor.show_contents(project_data, requests, ui);
+        });
+        closed
+    }
+
+    /// Splits the remaining space between the docked editors. The split position is remembered by
+    /// the side panel itself, so it persists across sessions.
+    fn show_docked_panes(
+        &mut self,
+        ctx: &egui::Context,
+        docked: [Option<usize>; 2],
+        requests: &mut WorkspaceRequests,
+    ) -> [bool; 2] {
+        let [left, right] = docked;
+        let mut closed = [false; 2];
+        egui::SidePanel::right("docked_right_pane")
+            .resizable(true)
+            .default_width(ctx.content_rect().width() / 3.0)
+            .show(ctx, |ui| match right {
+                Some(i) => {
+                    closed[1] = Self::show_docked_editor(
+                        self.open_editors[i].as_mut(),
+                        &mut self.project_data,
+                        requests,
+                        ui,
+                    );
+                }
+                None => {
+                    ui.weak("Open a tileset to dock it here.");
+                }
+            });
+        egui::CentralPanel::default().show(ctx, |ui| match left {
+            Some(i) => {
+                closed[0] = Self::show_docked_editor(
+                    self.open_editors[i].as_mut(),
+                    &mut self.project_data,
+                    requests,
+                    ui,
+                );
+            }
+            None => {
+                ui.weak("Open a room to dock it here.");
+            }
+        });
+        closed
+    }
+
+    /// Rooms in the sidebar, grouped by area. Returns the action picked for one of them, if any.
+    fn show_room_list(&mut self, ui: &mut egui::Ui) -> Option<SidebarAction> {
+        let selected_label = self
+            .room_group_filter
+            .map_or("All areas".into(), RoomGroup::label);
+        egui::ComboBox::from_id_salt("room_area_filter")
+            .selected_text(selected_label)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.room_group_filter, None, "All areas");
+                for area in 0..=u8::MAX {
+                    if snes_constants::area_name(area).is_none() {
+                        break;
+                    }
+                    let group = RoomGroup::Area(area);
+                    ui.selectable_value(&mut self.room_group_filter, Some(group), group.label());
+                }
+                let other = RoomGroup::Other;
+                ui.selectable_value(&mut self.room_group_filter, Some(other), other.label());
+            });
+
+        let mut rooms: Vec<_> = self
+            .project_data
+            .rooms
+            .values()
+            .map(|room| (RoomGroup::of(room), room))
+            .filter(|&(group, _)| self.room_group_filter.is_none_or(|filter| filter == group))
+            .collect();
+        // Stable sort, so rooms keep their load order within each group
+        rooms.sort_by_key(|&(group, _)| group);
+
+        let mut action = None;
+        let mut room_button = |ui: &mut egui::Ui, room: &Room| {
+            let response = ui.add(egui::Button::new(room.title()).frame_when_inactive(false));
+            if response.clicked() {
+                let editor = RoomEditor::new(room, &Settings::get(ui.ctx()));
+                action = Some(SidebarAction::Open(Box::new(editor)));
+            }
+            response.context_menu(|ui| {
+                if let Some(picked) = Self::room_context_menu(ui, &self.project_data, room) {
+                    action = Some(picked);
+                }
+            });
+        };
+        if self.room_group_filter.is_some() {
+            for (_, room) in rooms {
+                room_button(ui, room);
+            }
+        } else {
+            for group_rooms in rooms.chunk_by(|(a, _), (b, _)| a == b) {
+                let group = group_rooms[0].0;
+                ui.collapsing(group.label(), |ui| {
+                    for &(_, room) in group_rooms {
+                        room_button(ui, room);
+                    }
+                });
+            }
+        }
+        action
+    }
+
+    /// Context menu of a room in the sidebar. Returns the action picked, if any.
+    fn room_context_menu(
+        ui: &mut egui::Ui,
+        project_data: &ProjectData,
+        room: &Room,
+    ) -> Option<SidebarAction> {
+        if ui.button("Open Editor").clicked() {
+            let editor = RoomEditor::new(room, &Settings::get(ui.ctx()));
+            return Some(SidebarAction::Open(Box::new(editor)));
+        }
+        if let Some((area, index)) = room.index()
+            && ui.button("Copy Room Index").clicked()
+        {
+            ui.ctx()
+                .copy_text(format!("{},{}", HexU8(area), HexU8(index)));
+        }
+        if ui.button("Copy File Name").clicked() {
+            ui.ctx().copy_text(room.file_name.clone());
+        }
+        if ui
+            .button("Copy Summary")
+            .on_hover_text("Copy a text description of the room, e.g. for bug reports")
+            .clicked()
+        {
+            ui.ctx().copy_text(room.text_summary());
+        }
+        let area = room.index().map(|(area, _)| area);
+        let has_map = area.is_some_and(|area| project_data.area_maps.contains_key(&area));
+        if ui
+            .add_enabled(has_map, egui::Button::new("View in Area Map"))
+            .on_disabled_hover_text("The project has no map for this room's area")
+            .clicked()
+            && let Some(area) = area
+        {
+            let tile = [usize::from(room.map_x), usize::from(room.map_y)];
+            MapViewer::request_focus(ui.ctx(), area, tile);
+            return Some(SidebarAction::Open(Box::new(MapViewer::new(
+                area,
+                project_data,
+            ))));
+        }
+        ui.separator();
+        if ui
+            .add_enabled(
+                project_data.project_path.is_dir(),
+                egui::Button::new("Show in File Manager"),
+            )
+            .on_disabled_hover_text("Files inside project archives can't be shown")
+            .clicked()
+        {
+            return Some(SidebarAction::RevealRoom(room.handle()));
+        }
+        if ui
+            .button("Reload from Disk")
+            .on_hover_text("Discards unsaved changes to this room")
+            .clicked()
+        {
+            return Some(SidebarAction::ReloadRoom(room.handle()));
+        }
+        None
+    }
+
+    /// Tilesets in the sidebar. Returns the action picked for one of them, if any.
+    fn show_tileset_list(&self, ui: &mut egui::Ui) -> Option<SidebarAction> {
+        let mut action = None;
+        for tileset in self.project_data.tilesets.values() {
+            if tileset.palette.is_empty() {
+                continue;
+            }
+            let response = ui.add(egui::Button::new(tileset.title()).frame_when_inactive(false));
+            let open = || {
+                let editor = TilesetEditor::new(
+                    ui.ctx(),
+                    tileset,
+                    &self.project_data,
+                    &Settings::get(ui.ctx()),
+                );
+                SidebarAction::Open(Box::new(editor))
+            };
+            if response.clicked() {
+                action = Some(open());
+            }
+            response.context_menu(|ui| {
+                if ui.button("Open Editor").clicked() {
+                    action = Some(open());
+                }
+                ui.separator();
+                if ui
+                    .add_enabled(
+                        self.project_data.project_path.is_dir(),
+                        egui::Button::new("Show in File Manager"),
+                    )
+                    .on_disabled_hover_text("Files inside project archives can't be shown")
+                    .clicked()
+                {
+                    action = Some(SidebarAction::RevealTileset(tileset.handle()));
+                }
+                if ui
+                    .button("Reload from Disk")
+                    .on_hover_text("Discards unsaved changes to this tileset")
+                    .clicked()
+                {
+                    action = Some(SidebarAction::ReloadTileset(tileset.handle()));
+                }
+            });
+        }
+        action
+    }
+
+    /// Problems found in the project, with those of each room grouped under it. Returns the
+    /// location picked to jump to, if any.
+    fn show_diagnostics(&self, ui: &mut egui::Ui) -> Option<SidebarAction> {
+        let mut action = None;
+        let mut show = |ui: &mut egui::Ui, diagnostic: &Diagnostic| {
+            if views::show_diagnostic(ui, diagnostic)
+                && let Some(location) = diagnostic.location
+            {
+                action = Some(SidebarAction::ShowLocation(location));
+            }
+        };
+        for diagnostic in &self.project_data.diagnostics {
+            show(ui, diagnostic);
+        }
+        for &room_ref in self.project_data.room_ids.values() {
+            let Some(room) = self.project_data.rooms.get(room_ref) else {
+                continue;
+            };
+            if room.diagnostics.is_empty() {
+                continue;
+            }
+            egui::CollapsingHeader::new(room.title())
+                .id_salt(("room_diagnostics", room_ref))
+                .show(ui, |ui| {
+                    for diagnostic in &room.diagnostics {
+                        show(ui, diagnostic);
+                    }
+                });
+        }
+        action
+    }
+
+    /// Opens the editor of the room or tileset `location` is in, and has it select the location.
+    fn show_location(&mut self, ctx: &egui::Context, location: DiagnosticLocation) {
+        let key = match location {
+            DiagnosticLocation::Room(room)
+            | DiagnosticLocation::RoomState { room, .. }
+            | DiagnosticLocation::Door { room, .. } => self
+                .project_data
+                .rooms
+                .get(room)
+                .and_then(|room| room.index())
+                .map(EditorKey::Room),
+            DiagnosticLocation::Tileset(tileset) => self
+                .project_data
+                .tilesets
+                .get(tileset)
+                .and_then(|tileset| Some(EditorKey::Tileset(tileset.kind, tileset.index()?))),
+        };
+        let Some(editor) = key.and_then(|key| key.open_editor(ctx, &self.project_data)) else {
+            self.toasts.add(
+                ctx,
+                ToastLevel::Info,
+                "That part of the project can't be opened",
+            );
+            return;
+        };
+        self.open_editor(ctx, editor);
+        // The opened (or existing) editor is moved to the end of the list
+        if let Some(editor) = self.open_editors.last_mut() {
+            editor.run_command(
+                EditorCommand::ShowLocation(location),
+                &mut self.project_data,
+                ctx,
+            );
+        }
+    }
+
+    /// Replaces the open editors for `key` with fresh ones, after the object they edit was
+    /// reloaded. Their undo history refers to the discarded data, so it can't be kept.
+    fn reopen_editors(&mut self, ctx: &egui::Context, key: EditorKey) {
+        for editor in &mut self.open_editors {
+            if editor.editor_key() == Some(key)
+                && let Some(new_editor) = key.open_editor(ctx, &self.project_data)
+            {
+                *editor = new_editor;
+            }
+        }
+    }
+
+    fn reveal_path(&mut self, ctx: &egui::Context, path: anyhow::Result<Option<PathBuf>>) {
+        let result = path.and_then(|path| {
+            let path = path.context("Files inside project archives can't be shown")?;
+            file_manager::reveal(&path).with_context(|| format!("opening {}", path.display()))
+        });
+        if let Err(e) = result {
+            tracing::error!("Failed to show file: {e:#}");
+            let message = format!("Failed to show file: {e:#}");
+            self.toasts.add(ctx, ToastLevel::Error, message);
+        }
+    }
+
+    fn reload_room(&mut self, ctx: &egui::Context, room_ref: RoomRef) {
+        if let Err(e) = self.project_data.reload_room(room_ref) {
+            tracing::error!("Failed to reload room: {e:#}");
+            let message = format!("Failed to reload room: {e:#}");
+            self.toasts.add(ctx, ToastLevel::Error, message);
+            return;
+        }
+        tile_view::invalidate_room_textures(ctx, room_ref);
+        let message = format!(
+            "Reloaded {} from disk",
+            self.project_data.room_display_name(room_ref)
+        );
+        if let Some(index) = self.project_data.rooms[room_ref].index() {
+            self.reopen_editors(ctx, EditorKey::Room(index));
+        }
+        self.toasts.add(ctx, ToastLevel::Success, message);
+    }
+
+    fn reload_tileset(&mut self, ctx: &egui::Context, tileset_ref: TilesetRef) {
+        let load_mode = Settings::get(ctx).load_mode;
+        let diagnostics = match self.project_data.reload_tileset(tileset_ref, load_mode) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                tracing::error!("Failed to reload tileset: {e:#}");
+                let message = format!("Failed to reload tileset: {e:#}");
+                self.toasts.add(ctx, ToastLevel::Error, message);
+                return;
+            }
+        };
+        // Rooms using the tileset are rendered with it too, so every texture may be stale
+        tile_view::clear_textures(ctx);
+        let tileset = &self.project_data.tilesets[tileset_ref];
+        let message = format!("Reloaded {} from disk", tileset.title());
+        if let Some(index) = tileset.index() {
+            self.reopen_editors(ctx, EditorKey::Tileset(tileset.kind, index));
+        }
+        for diagnostic in diagnostics {
+            self.toasts
+                .add(ctx, diagnostic.severity.into(), diagnostic.message);
+        }
+        self.toasts.add(ctx, ToastLevel::Success, message);
+    }
+
+    /// Reserves space for the status bar at the bottom of the window. Its contents are drawn by
+    /// [`Self::show_status_bar`] into the returned rect, once editors published their hover info.
+    fn reserve_status_bar(ctx: &egui::Context) -> egui::Rect {
+        egui::TopBottomPanel::bottom("status_bar")
+            .show(ctx, |ui| {
+                let height = ui.spacing().interact_size.y;
+                ui.allocate_space(egui::vec2(ui.available_width(), height));
+                ui.min_rect()
+            })
+            .inner
+    }
+
+    /// Shows information about whatever editor canvas is hovered during this frame.
+    fn show_status_bar(ctx: &egui::Context, rect: egui::Rect) {
+        let Some(info) = HoverInfo::take(ctx) else {
+            return;
+        };
+        let mut ui = egui::Ui::new(
+            ctx.clone(),
+            Id::new("status_bar_contents"),
+            egui::UiBuilder::new().max_rect(rect),
+        );
+        ui.horizontal(|ui| {
+            let [x, y] = info.tile;
+            ui.label(format!("Tile: ({x}, {y})"));
+            if let Some(block) = info.block {
+                ui.separator();
+                ui.label(format!("Block: ${block:03X}"));
+            }
+            if let Some([sx, sy]) = info.screen {
+                ui.separator();
+                ui.label(format!("Screen: ({sx}, {sy})"));
+            }
+        });
+    }
+
+    fn show_cache_stats(ctx: &egui::Context) {
+        let stats = tile_view::texture_cache_stats(ctx);
+        egui::Area::new(Id::new("texture_cache_stats"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-8.0, -32.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(format!("Cached textures: {}", stats.len));
+                    ui.label(format!(
+                        "Evicted: {} last pass, {} total",
+                        stats.last_evictions, stats.total_evictions
+                    ));
+                    ui.label(format!(
+                        "Texture memory: ~{:.1} MiB",
+                        stats.texture_bytes as f64 / (1024.0 * 1024.0)
+                    ));
+                });
+            });
+    }
+
+    /// Small dialog that opens a room by its area and index, typed in hex.
+    fn show_go_to_room(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_shortcut(&GO_TO_ROOM)) {
+            self.go_to_room = Some(String::new());
+        }
+        let Some(text) = &mut self.go_to_room else {
+            return;
+        };
+
+        let mut submit = false;
+        let modal_response = egui::Modal::new(Id::new("go_to_room")).show(ctx, |ui| {
+            ui.label("Go to room (area, index in hex):");
+            let response = ui.add(egui::TextEdit::singleline(text).hint_text("e.g. 4,1F"));
+            // Pressing Enter in a single line text edit makes it lose focus. The edit only has
+            // focus after the first frame, so an Enter press that opened the dialog is ignored.
+            submit = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            response.request_focus();
+            ui.horizontal(|ui| {
+                submit |= ui.button("Open").clicked();
+                if ui.button("Cancel").clicked() {
+                    ui.close();
+                }
+            });
+        });
+        if modal_response.should_close() {
+            self.go_to_room = None;
+            return;
+        }
+        if !submit {
+            return;
+        }
+
+        let Some(index) = room::parse_room_index(text) else {
+            let message = format!("\"{}\" isn't a room index like 4,1F", text.trim());
+            self.toasts.add(ctx, ToastLevel::Error, message);
+            return;
+        };
+        self.go_to_room = None;
+        let key = EditorKey::Room(index);
+        if self
+            .project_data
+            .lookup_room_by_index(index.0, index.1)
+            .is_some()
+            && let Some(editor) = key.open_editor(ctx, &self.project_data)
+        {
+            self.open_editor(ctx, editor);
+        } else {
+            let message = format!("{} doesn't exist", key.description());
+            self.toasts.add(ctx, ToastLevel::Error, message);
+        }
+    }
+
+    /// Asks before the window is closed with unsaved changes, e.g. with its title bar button.
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.quit_confirmed
+            && self.dirty_room_count(&DiscardAction::Quit) > 0
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.confirm_discard = Some(DiscardAction::Quit);
+        }
+    }
+
+    /// Opens projects dropped onto the window, asking first if there are unsaved changes.
+    fn show_project_drop(&mut self, ctx: &egui::Context) {
+        match self.drop_target.show(ctx) {
+            None => {}
+            Some((path, Ok(()))) => self.confirm_discard(ctx, DiscardAction::OpenProject(path)),
+            Some((path, Err(e))) => {
+                let message = format!("Can't open {}: {e}", path.display());
+                self.toasts.add(ctx, ToastLevel::Error, message);
+            }
+        }
+    }
+
+    /// Number of rooms with unsaved changes that `action` would discard. All of them, unless it
+    /// only affects one room.
+    fn dirty_room_count(&self, action: &DiscardAction) -> usize {
+        if let DiscardAction::ReloadRoom(room_ref) = action {
+            return self
+                .project_data
+                .rooms
+                .get(*room_ref)
+                .is_some_and(Room::is_dirty)
+                .into();
+        }
+        self.project_data
+            .rooms
+            .values()
+            .filter(|room| room.is_dirty())
+            .count()
+    }
+
+    /// Runs `action` right away if it doesn't discard any unsaved changes, or asks for
+    /// confirmation first.
+    fn confirm_discard(&mut self, ctx: &egui::Context, action: DiscardAction) {
+        if self.dirty_room_count(&action) == 0 {
+            self.run_discard_action(ctx, action);
+        } else {
+            self.confirm_discard = Some(action);
+        }
+    }
+
+    fn run_discard_action(&mut self, ctx: &egui::Context, action: DiscardAction) {
+        match action {
+            DiscardAction::OpenProject(path) => self.open_requested = Some(path),
+            DiscardAction::ReloadProject => self.start_reload(ctx),
+            DiscardAction::CloseProject => self.close_requested = true,
+            DiscardAction::Quit => {
+                self.quit_confirmed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            DiscardAction::ReloadRoom(room_ref) => self.reload_room(ctx, room_ref),
+        }
+    }
+
+    /// Asks whether to go ahead with the action waiting in `confirm_discard`, if any.
+    fn show_confirm_discard(&mut self, ctx: &egui::Context) {
+        let Some(action) = &self.confirm_discard else {
+            return;
+        };
+        let (question, button) = match action {
+            DiscardAction::OpenProject(path) => {
+                (format!("Open {}?", path.display()), "Discard and Open")
+            }
+            DiscardAction::ReloadProject => {
+                ("Reload the project?".to_owned(), "Discard and Reload")
+            }
+            DiscardAction::CloseProject => ("Close the project?".to_owned(), "Discard and Close"),
+            DiscardAction::Quit => ("Quit?".to_owned(), "Discard and Quit"),
+            DiscardAction::ReloadRoom(room_ref) => {
+                let title = self.project_data.room_display_name(*room_ref);
+                (format!("Reload {title} from disk?"), "Discard and Reload")
+            }
+        };
+        let dirty_count = self.dirty_room_count(action);
+        let mut confirmed = false;
+        let modal_response = egui::Modal::new(Id::new("confirm_discard")).show(ctx, |ui| {
+            ui.label(format!(
+                "{question} Unsaved changes to {dirty_count} room(s) will be lost."
+            ));
+            ui.horizontal(|ui| {
+                if ui.button(button).clicked() {
+                    confirmed = true;
+                    ui.close();
+                }
+                if ui.button("Cancel").clicked() {
+                    ui.close();
+                }
+            });
+        });
+        if modal_response.should_close()
+            && let Some(action) = self.confirm_discard.take()
+            && confirmed
+        {
+            self.run_discard_action(ctx, action);
+        }
+    }
+
+    fn show_search_palette(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        if ctx.input_mut(|i| i.consume_shortcut(&SEARCH_PALETTE)) {
+            self.search_palette = match self.search_palette {
+                Some(_) => None,
+                None => Some(SearchPalette::default()),
+            };
+        }
+
+        let Some(search_palette) = &mut self.search_palette else {
+            return;
+        };
+        match search_palette.show(ctx, &self.project_data) {
+            PaletteAction::None => {}
+            PaletteAction::Close => self.search_palette = None,
+            PaletteAction::Open(key) => {
+                self.search_palette = None;
+                if let Some(editor) = key.open_editor(ctx, &self.project_data) {
+                    self.open_editor(ctx, editor);
+                }
+            }
+            PaletteAction::Run(command) => {
+                self.search_palette = None;
+                match command {
+                    PaletteCommand::Reload => {
+                        self.confirm_discard(ctx, DiscardAction::ReloadProject);
+                    }
+                    PaletteCommand::ExportArchive => self.pick_archive_path(frame),
+                    PaletteCommand::ExportRoomsCsv => self.pick_rooms_csv_path(frame),
+                    PaletteCommand::Settings => self.open_editor(ctx, Box::new(SettingsWindow)),
+                    PaletteCommand::GoToRoom => self.go_to_room = Some(String::new()),
+                }
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let mut new_editor: Option<Box<dyn EditorWindow>> = None;
+        let mut sidebar_action = None;
+
+        self.show_menu_bar(ctx, frame);
+        self.poll_archive_export(ctx);
+        self.poll_render_benchmark(ctx);
+        self.poll_rooms_csv_export(ctx);
+        self.poll_project_reload(ctx);
+        self.poll_watcher(ctx);
+        tile_view::set_texture_cache_max_age(ctx, Settings::get(ctx).texture_cache_max_age);
+        if self.show_cache_stats {
+            Self::show_cache_stats(ctx);
+        }
+        let status_bar_rect = Self::reserve_status_bar(ctx);
+
+        egui::SidePanel::left("editor_list").show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.collapsing("Rooms", |ui| {
+                    if let Some(action) = self.show_room_list(ui) {
+                        sidebar_action = Some(action);
                     }
                 });
                 ui.collapsing("Tilesets", |ui| {
-                    for (tileset_ref, tileset) in &self.project_data.tilesets {
-                        if tileset.palette.is_empty() {
-                            continue;
-                        }
+                    if let Some(action) = self.show_tileset_list(ui) {
+                        sidebar_action = Some(action);
+                    }
+                });
+                if ui
+                    .add(egui::Button::new("Area Overview").frame_when_inactive(false))
+                    .clicked()
+                {
+                    let first_area = self
+                        .project_data
+                        .room_ids
+                        .keys()
+                        .next()
+                        .map_or(0, |&(area, _)| area);
+                    new_editor = Some(Box::new(AreaOverview::new(first_area)));
+                }
+                ui.collapsing("Area Maps", |ui| {
+                    for &area in self.project_data.area_maps.keys() {
                         if ui
-                            .add(egui::Button::new(tileset.title()).frame_when_inactive(false))
+                            .add(
+                                egui::Button::new(format!("Area {}", HexU8(area)))
+                                    .frame_when_inactive(false),
+                            )
                             .clicked()
                         {
-                            new_editor = Some(Box::new(TilesetEditor::new(
-                                ctx,
-                                tileset_ref,
-                                &self.project_data,
-                            )));
+                            new_editor = Some(Box::new(MapViewer::new(area, &self.project_data)));
                         }
                     }
                 });
 
+                let diagnostic_count = self.project_data.all_diagnostics().count();
+                if diagnostic_count > 0 {
+                    egui::CollapsingHeader::new(format!("Diagnostics ({diagnostic_count})")).show(
+                        ui,
+                        |ui| {
+                            if let Some(action) = self.show_diagnostics(ui) {
+                                sidebar_action = Some(action);
+                            }
+                        },
+                    );
+                }
+
                 ui.allocate_space(ui.available_size());
             });
         });
 
+        match sidebar_action {
+            None => {}
+            Some(SidebarAction::Open(editor)) => new_editor = Some(editor),
+            Some(SidebarAction::ReloadRoom(room_ref)) => {
+                self.confirm_discard(ctx, DiscardAction::ReloadRoom(room_ref));
+            }
+            Some(SidebarAction::ReloadTileset(tileset_ref)) => {
+                self.reload_tileset(ctx, tileset_ref);
+            }
+            Some(SidebarAction::RevealRoom(room_ref)) => {
+                let path = self.project_data.room_file_path(room_ref);
+                self.reveal_path(ctx, Ok(path));
+            }
+            Some(SidebarAction::RevealTileset(tileset_ref)) => {
+                let path = self.project_data.tileset_dir_path(tileset_ref);
+                self.reveal_path(ctx, path);
+            }
+            Some(SidebarAction::ShowLocation(location)) => self.show_location(ctx, location),
+        }
         if let Some(new_editor) = new_editor {
             self.open_editor(ctx, new_editor);
         }
+        let mut requests = WorkspaceRequests::default();
+        let docked = if self.docked {
+            self.docked_editors()
+        } else {
+            [None, None]
+        };
+        let docked_closed = if self.docked {
+            self.show_docked_panes(ctx, docked, &mut requests)
+        } else {
+            [false; 2]
+        };
+        let mut index = 0;
         self.open_editors.retain_mut(|editor| {
-            let response = editor.show_window(&mut self.project_data, ctx);
+            let i = index;
+            index += 1;
+            if let Some(side) = docked.iter().position(|&d| d == Some(i)) {
+                return !docked_closed[side];
+            }
+            let response = editor.show_window(&mut self.project_data, &mut requests, ctx);
             let should_close = response.is_none_or(|r| r.should_close());
             !should_close
         });
+        for key in requests.open_editors {
+            if let Some(editor) = key.open_editor(ctx, &self.project_data) {
+                self.open_editor(ctx, editor);
+            }
+        }
+        for (level, text) in requests.toasts {
+            self.toasts.add(ctx, level, text);
+        }
+        Self::show_status_bar(ctx, status_bar_rect);
+        self.show_search_palette(ctx, frame);
+        self.show_go_to_room(ctx);
+        self.show_project_drop(ctx);
+        self.handle_close_request(ctx);
+        self.show_confirm_discard(ctx);
+        self.save_open_editors(ctx);
+        if self.toasts.show(ctx) == Some(RELOAD_ACTION) {
+            self.confirm_discard(ctx, DiscardAction::ReloadProject);
+        }
     }
 }
@@ -0,0 +1,155 @@
+/// Change to a `T` that can be reverted and reapplied.
+pub trait Edit<T> {
+    fn apply(&self, target: &mut T);
+    fn revert(&self, target: &mut T);
+
+    /// Tries to fold `next`, which is applied right after `self`, into `self`. Used so continuous
+    /// changes like dragging a value are undone in a single step. Returns whether it was merged.
+    fn merge(&mut self, _next: &Self) -> bool {
+        false
+    }
+}
+
+/// Linear undo/redo history. Edits are applied by the caller before being recorded.
+pub struct UndoHistory<E> {
+    done: Vec<E>,
+    undone: Vec<E>,
+}
+
+impl<E> Default for UndoHistory<E> {
+    fn default() -> Self {
+        Self {
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+}
+
+impl<E> UndoHistory<E> {
+    /// Records an already applied `edit`, discarding the redo history. If `mergeable` is set, the
+    /// edit is first offered to the previous one to be merged into it.
+    pub fn push<T>(&mut self, edit: E, mergeable: bool)
+    where
+        E: Edit<T>,
+    {
+        self.undone.clear();
+        if mergeable
+            && let Some(last) = self.done.last_mut()
+            && last.merge(&edit)
+        {
+            return;
+        }
+        self.done.push(edit);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn undo<T>(&mut self, target: &mut T)
+    where
+        E: Edit<T>,
+    {
+        if let Some(edit) = self.done.pop() {
+            edit.revert(target);
+            self.undone.push(edit);
+        }
+    }
+
+    pub fn redo<T>(&mut self, target: &mut T)
+    where
+        E: Edit<T>,
+    {
+        if let Some(edit) = self.undone.pop() {
+            edit.apply(target);
+            self.done.push(edit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sets the element at an index, remembering the previous value.
+    struct SetValue {
+        index: usize,
+        old: i32,
+        new: i32,
+    }
+
+    impl Edit<Vec<i32>> for SetValue {
+        fn apply(&self, target: &mut Vec<i32>) {
+            target[self.index] = self.new;
+        }
+
+        fn revert(&self, target: &mut Vec<i32>) {
+            target[self.index] = self.old;
+        }
+
+        fn merge(&mut self, next: &Self) -> bool {
+            let can_merge = self.index == next.index;
+            if can_merge {
+                self.new = next.new;
+            }
+            can_merge
+        }
+    }
+
+    fn set(
+        history: &mut UndoHistory<SetValue>,
+        v: &mut Vec<i32>,
+        index: usize,
+        new: i32,
+        mergeable: bool,
+    ) {
+        let edit = SetValue {
+            index,
+            old: v[index],
+            new,
+        };
+        edit.apply(v);
+        history.push(edit, mergeable);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = UndoHistory::default();
+        let mut v = vec![0, 0];
+        set(&mut history, &mut v, 0, 1, false);
+        set(&mut history, &mut v, 1, 2, false);
+
+        history.undo(&mut v);
+        assert_eq!(v, [1, 0]);
+        history.undo(&mut v);
+        assert_eq!(v, [0, 0]);
+        assert!(!history.can_undo());
+
+        history.redo(&mut v);
+        assert_eq!(v, [1, 0]);
+        set(&mut history, &mut v, 1, 3, false);
+        assert!(!history.can_redo());
+        assert_eq!(v, [1, 3]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut history = UndoHistory::default();
+        let mut v = vec![0, 0];
+        set(&mut history, &mut v, 0, 1, false);
+        set(&mut history, &mut v, 0, 2, true);
+        set(&mut history, &mut v, 0, 3, true);
+        // Different index, can't be merged
+        set(&mut history, &mut v, 1, 4, true);
+
+        history.undo(&mut v);
+        assert_eq!(v, [3, 0]);
+        history.undo(&mut v);
+        assert_eq!(v, [0, 0]);
+        assert!(!history.can_undo());
+    }
+}